@@ -0,0 +1,87 @@
+use burn::backend::{NdArray, Wgpu};
+use burn::tensor::{backend::Backend, Data, Tensor};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use hotnotdog::data::config::DatasetConfig;
+use hotnotdog::data::dataset::load_data;
+use hotnotdog::model::normalizer::Normalizer;
+use hotnotdog::model::squeezed_classifier::{decode_image, image_to_tensor};
+use hotnotdog::model::squeezenet;
+
+const BATCH_SIZES: [usize; 3] = [1, 4, 16];
+
+/// The dataset and preprocessing from `hotnotdog.toml`, or the defaults without one.
+fn config() -> DatasetConfig {
+    DatasetConfig::from_args(Vec::new()).unwrap_or_else(|error| panic!("{error}"))
+}
+
+fn sample_path(config: &DatasetConfig) -> String {
+    load_data(config)
+        .expect("The benchmarks need the seefood images")
+        .remove(0)
+        .image_path
+}
+
+fn preprocessing(c: &mut Criterion) {
+    let config = config();
+    let path = sample_path(&config);
+    let decoded = decode_image(&path);
+    let resized = config.preprocessing.apply(&decoded);
+
+    c.bench_function("decode", |b| b.iter(|| decode_image(&path)));
+    c.bench_function("resize", |b| b.iter(|| config.preprocessing.apply(&decoded)));
+    c.bench_function("to_tensor", |b| {
+        b.iter(|| image_to_tensor::<NdArray>(&resized))
+    });
+}
+
+fn model_stages<B: Backend>(c: &mut Criterion, backend: &str)
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    let config = config();
+    let path = sample_path(&config);
+    let image: Tensor<B, 4> = image_to_tensor(&config.preprocessing.apply(&decode_image(&path)));
+    let normalizer = Normalizer::<B>::new();
+    let squeezenet_imported = squeezenet::Model::<B>::from_embedded();
+    let model = squeezenet::Classifier::<B>::new_from_squeezenet(&squeezenet_imported);
+
+    let mut group = c.benchmark_group(format!("{backend}/model"));
+    for batch_size in BATCH_SIZES {
+        let batch = Tensor::cat(vec![image.clone(); batch_size], 0);
+        let normalized = normalizer.normalize(batch.clone());
+        let output = model.forward(normalized.clone());
+
+        group.bench_with_input(BenchmarkId::new("normalize", batch_size), &batch, |b, batch| {
+            b.iter(|| {
+                let normalized = normalizer.normalize(batch.clone());
+                B::sync(&normalized.device());
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("forward", batch_size), &normalized, |b, input| {
+            b.iter(|| {
+                let output = model.forward(input.clone());
+                B::sync(&output.device());
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("argmax", batch_size), &output, |b, output| {
+            b.iter(|| output.clone().argmax(1).into_data())
+        });
+    }
+    group.finish();
+}
+
+fn ndarray(c: &mut Criterion) {
+    model_stages::<NdArray>(c, "ndarray");
+}
+
+fn wgpu(c: &mut Criterion) {
+    model_stages::<Wgpu>(c, "wgpu");
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = preprocessing, ndarray, wgpu
+}
+criterion_main!(benches);
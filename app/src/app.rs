@@ -1,33 +1,53 @@
 use eframe::{
-    egui::{CentralPanel, RichText, SidePanel},
+    egui::{
+        self, Align2, CentralPanel, ComboBox, Image, ImageButton, Key, RichText, ScrollArea,
+        SidePanel, TopBottomPanel, Window,
+    },
     epaint::Color32,
     App,
 };
-use rand::seq::SliceRandom;
 use std::fmt;
 
 use burn::backend::{Autodiff, Wgpu};
 
+use hotnotdog::data::config::DatasetConfig;
+use hotnotdog::data::dataset::{load_data, load_split, DatasetError, HotNotDogsData};
+use hotnotdog::evaluation::calibrate_ood;
+use hotnotdog::model::label::UNSURE_LABEL;
+use hotnotdog::model::ood::OodMethod;
 use hotnotdog::model::squeezed_classifier::HotNotDogClassifier;
-use hotnotdog::model::squeezed_classifier::load_image;
+use hotnotdog::model::squeezed_classifier::{decode_image, image_to_tensor};
+use hotnotdog::profiling::{Stage, StageTimings};
+
+const THUMBNAIL_SIZE: f32 = 96.0;
 
 #[derive(Default)]
 pub struct HotNotDogApp {
+    // `None` if the config could not be read.
+    dataset: Option<DatasetConfig>,
     stream: Vec<HotNotDogsData>,
+    // Last prediction made for each image of the stream, used for filtering.
+    predictions: Vec<Option<Predicted>>,
     model: HotNotDogClassifier<Autodiff<Wgpu>>,
     true_label: TrueLabel,
     show_prediction: bool,
-    prediction: Option<TrueLabel>,
+    prediction: Option<Predicted>,
     show_training: bool,
     current_image: usize,
+    filter: BrowseFilter,
+    view: BrowseView,
+    // An image dropped onto the window from outside the dataset.
+    dropped_image: Option<String>,
+    show_timings: bool,
+    // Per-stage timings of the most recent prediction.
+    last_timings: Option<StageTimings>,
+    show_model_input: bool,
+    // The preprocessed 224x224 view of the displayed image, keyed by its path.
+    model_input: Option<(String, egui::TextureHandle)>,
+    load_error: Option<DatasetError>,
 }
 
-struct HotNotDogsData {
-    image_path: String,
-    label: bool,
-}
-
-#[derive(PartialEq, Default)]
+#[derive(PartialEq, Clone, Copy, Default)]
 pub enum TrueLabel {
     #[default]
     NotHotDog,
@@ -43,37 +63,151 @@ impl fmt::Display for TrueLabel {
     }
 }
 
+impl From<bool> for TrueLabel {
+    fn from(is_hot_dog: bool) -> Self {
+        if is_hot_dog {
+            TrueLabel::HotDog
+        } else {
+            TrueLabel::NotHotDog
+        }
+    }
+}
+
+/// What the model said about an image.
+#[derive(PartialEq, Clone, Copy)]
+pub enum Predicted {
+    Label(TrueLabel),
+    /// The image looks unlike the training data, see `hotnotdog::model::ood`.
+    Unsure,
+}
+
+impl fmt::Display for Predicted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Predicted::Label(label) => write!(f, "{label}"),
+            Predicted::Unsure => write!(f, "{UNSURE_LABEL}"),
+        }
+    }
+}
+
+/// Which images of the stream are shown in the browser.
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum BrowseFilter {
+    #[default]
+    All,
+    TrueLabel(TrueLabel),
+    PredictedLabel(TrueLabel),
+    Misclassified,
+    Unsure,
+}
+
+impl fmt::Display for BrowseFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BrowseFilter::All => write!(f, "All images"),
+            BrowseFilter::TrueLabel(label) => write!(f, "Labelled {label}"),
+            BrowseFilter::PredictedLabel(label) => write!(f, "Predicted {label}"),
+            BrowseFilter::Misclassified => write!(f, "Misclassified"),
+            BrowseFilter::Unsure => write!(f, "Predicted {UNSURE_LABEL}"),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum BrowseView {
+    #[default]
+    Single,
+    Grid,
+}
+
 impl App for HotNotDogApp {
     fn update(&mut self, ctx: &eframe::egui::Context, frame: &mut eframe::Frame) {
-        CentralPanel::default().show(ctx, |ui| {
-            ui.vertical_centered(|ui| {
-                ui.heading("Hot or Not Dog");
-                // concat
-                let mut path = String::new();
-                path.push_str("file://");
-                path.push_str(&self.stream[self.current_image].image_path);
-
-                ui.image(path);
-            })
-        });
+        if let Some(error) = &self.load_error {
+            CentralPanel::default().show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.heading("Hot or Not Dog");
+                    ui.separator();
+                    ui.label(RichText::new("Could not load the dataset").color(Color32::RED));
+                    ui.label(error.to_string());
+                    ui.separator();
+                    ui.label("Point the app at the seefood images with --data-root <dir>, or pass --config <file>.");
+                })
+            });
+            return;
+        }
+
+        self.handle_dropped_files(ctx);
+        self.handle_shortcuts(ctx);
+
+        let visible = self.visible_images();
+
+        if self.show_timings {
+            Window::new("Inference timings")
+                .anchor(Align2::LEFT_TOP, [8.0, 8.0])
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| match &self.last_timings {
+                    Some(timings) => {
+                        for stage in Stage::ALL {
+                            ui.label(format!("{:<10} {:>8.2} ms", stage.name(), timings.mean_ms(stage)));
+                        }
+                        ui.separator();
+                        ui.label(format!("{:<10} {:>8.2} ms", "total", timings.pipeline_ms()));
+                    }
+                    None => {
+                        ui.label("Predict an image to see its timings.");
+                    }
+                });
+        }
+
+        if self.view == BrowseView::Single {
+            TopBottomPanel::bottom("filmstrip").show(ctx, |ui| {
+                ScrollArea::horizontal().show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        for &index in &visible {
+                            if self.thumbnail(ui, index).clicked() {
+                                self.select_image(index);
+                            }
+                        }
+                    });
+                });
+            });
+        }
+
         SidePanel::right("side_panel").show(ctx, |ui| {
             ui.heading("Play the Hotdog Game");
+
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.view, BrowseView::Single, "Single");
+                ui.selectable_value(&mut self.view, BrowseView::Grid, "Grid");
+            });
+
+            ComboBox::from_label("Show")
+                .selected_text(self.filter.to_string())
+                .show_ui(ui, |ui| {
+                    for filter in [
+                        BrowseFilter::All,
+                        BrowseFilter::TrueLabel(TrueLabel::HotDog),
+                        BrowseFilter::TrueLabel(TrueLabel::NotHotDog),
+                        BrowseFilter::PredictedLabel(TrueLabel::HotDog),
+                        BrowseFilter::PredictedLabel(TrueLabel::NotHotDog),
+                        BrowseFilter::Misclassified,
+                        BrowseFilter::Unsure,
+                    ] {
+                        ui.selectable_value(&mut self.filter, filter, filter.to_string());
+                    }
+                });
+            ui.label(format!("{} of {} images", visible.len(), self.stream.len()));
+
+            ui.separator();
             ui.label("Predict!");
             // add button to run prediction on displayed image
 
             ui.horizontal(|ui| {
                 if ui.button("Predict").clicked() {
-                    println!("Predicting");
-
-                    let image: burn::tensor::Tensor<Autodiff<Wgpu>, 4> = load_image(&self.stream[self.current_image].image_path);
-                    let prediction = self.model.predict(image);
-                    self.prediction = Some(match prediction {
-                        "hot_dog" => TrueLabel::HotDog,
-                        _ => TrueLabel::NotHotDog,
-                    });
-                    self.show_prediction = true;
+                    self.predict_current();
                 }
-                if ui.button("Train Me").clicked() {
+                if self.dropped_image.is_none() && ui.button("Train Me").clicked() {
                     println!("Training enabeled");
                     self.show_training = true;
                 }
@@ -97,13 +231,18 @@ impl App for HotNotDogApp {
                     ui.selectable_value(&mut self.true_label, TrueLabel::NotHotDog, "NotHotDog");
                 });
 
+                // Only train on the image that is shown, not one the filter hides.
+                let shown = self.dropped_image.is_none() && visible.contains(&self.current_image);
                 if ui
-                    .button(RichText::new("Submit").color(Color32::DARK_BLUE))
+                    .add_enabled(
+                        shown,
+                        egui::Button::new(RichText::new("Submit").color(Color32::DARK_BLUE)),
+                    )
                     .clicked()
                 {
                     println!("Submitting");
 
-                    let image: burn::tensor::Tensor<Autodiff<Wgpu>, 4> = load_image(&self.stream[self.current_image].image_path);
+                    let image = self.model.load_image(&self.stream[self.current_image].image_path);
                     let label = match self.true_label {
                         TrueLabel::HotDog => burn::tensor::Tensor::<Autodiff<Wgpu>, 1, burn::tensor::Int>::from_data([1]),
                         TrueLabel::NotHotDog => burn::tensor::Tensor::<Autodiff<Wgpu>, 1, burn::tensor::Int>::from_data([0]),
@@ -117,76 +256,307 @@ impl App for HotNotDogApp {
             // add separator
             ui.separator();
 
-            // add button to get next image
-            if ui.button("Next").clicked() {
-                println!("Next");
-                self.next_image();
-            }
+            // add buttons to move through the filtered images
+            ui.horizontal(|ui| {
+                if ui.button("Previous").clicked() {
+                    self.previous_image();
+                }
+                if ui.button("Next").clicked() {
+                    self.next_image();
+                }
+            });
 
             // add separator
             ui.separator();
 
-            //
+            // Learn the "unsure" threshold from the training images, whichever split is shown.
+            let calibrate = ui
+                .add_enabled(self.dataset.is_some(), egui::Button::new("Calibrate Unsure"))
+                .on_hover_text("Scores every image of the train split, takes a while");
+            if calibrate.clicked() {
+                self.calibrate_unsure();
+            }
+            if let Some(detector) = self.model.ood_detector() {
+                ui.label(format!(
+                    "Unsure below {:.3} ({:.0}% of the train split accepted)",
+                    detector.threshold,
+                    detector.coverage * 100.0
+                ));
+            }
+
+            ui.separator();
+
+            ui.checkbox(&mut self.show_timings, "Show inference timings");
+            ui.checkbox(&mut self.show_model_input, "Show model input");
+
+            ui.label("Shortcuts: ← / → browse, P predict, T train, G toggle grid, I timings.");
+            ui.label("Drop an image onto the window to classify it.");
+        });
+
+        CentralPanel::default().show(ctx, |ui| match self.view {
+            BrowseView::Single => {
+                ui.vertical_centered(|ui| {
+                    ui.heading("Hot or Not Dog");
+
+                    match self.displayed_image().map(str::to_owned) {
+                        Some(image_path) => {
+                            ui.image(format!("file://{image_path}"));
+                            if self.show_model_input {
+                                let texture = self.model_input_texture(ctx, &image_path);
+                                ui.label("Model input");
+                                ui.image(&texture);
+                            }
+                        }
+                        None => {
+                            ui.label("No images match the current filter.");
+                        }
+                    }
+                });
+            }
+            BrowseView::Grid => {
+                ScrollArea::vertical().show(ui, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        for &index in &visible {
+                            if self.thumbnail(ui, index).clicked() {
+                                self.select_image(index);
+                                self.view = BrowseView::Single;
+                            }
+                        }
+                    });
+                });
+            }
         });
     }
 }
 
 impl HotNotDogApp {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>, config: Result<DatasetConfig, DatasetError>) -> Self {
+        let model = match &config {
+            Ok(config) => HotNotDogClassifier::new_seeded(config.seed)
+                .with_preprocessing(config.preprocessing),
+            Err(_) => HotNotDogClassifier::new(),
+        };
+        let dataset = config.as_ref().ok().cloned();
+        let (stream, load_error) = match config.and_then(|config| load_data(&config)) {
+            Ok(stream) => (stream, None),
+            Err(error) => {
+                eprintln!("{error}");
+                (Vec::new(), Some(error))
+            }
+        };
+
         Self {
-            stream: load_data(),
-            model: HotNotDogClassifier::new(),
+            dataset,
+            predictions: vec![None; stream.len()],
+            stream,
+            model,
             true_label: TrueLabel::HotDog,
             show_prediction: false,
             prediction: None,
             show_training: false,
             current_image: 0,
+            filter: BrowseFilter::All,
+            view: BrowseView::Single,
+            dropped_image: None,
+            show_timings: false,
+            last_timings: None,
+            show_model_input: false,
+            model_input: None,
+            load_error,
         }
     }
 
-    fn next_image(&mut self) {
-        self.current_image += 1;
-        if self.current_image >= self.stream.len() {
-            self.current_image = 0;
+    /// Indices into the stream of the images passing the current filter.
+    fn visible_images(&self) -> Vec<usize> {
+        (0..self.stream.len())
+            .filter(|&index| {
+                let truth = TrueLabel::from(self.stream[index].label);
+                let predicted = self.predictions[index];
+                match self.filter {
+                    BrowseFilter::All => true,
+                    BrowseFilter::TrueLabel(label) => truth == label,
+                    BrowseFilter::PredictedLabel(label) => predicted == Some(Predicted::Label(label)),
+                    BrowseFilter::Misclassified => {
+                        matches!(predicted, Some(Predicted::Label(p)) if p != truth)
+                    }
+                    BrowseFilter::Unsure => predicted == Some(Predicted::Unsure),
+                }
+            })
+            .collect()
+    }
+
+    fn displayed_image(&self) -> Option<&str> {
+        if let Some(path) = &self.dropped_image {
+            return Some(path);
+        }
+        if self.visible_images().contains(&self.current_image) {
+            Some(&self.stream[self.current_image].image_path)
+        } else {
+            None
         }
+    }
 
-        self.show_prediction = false;
-        self.show_training = false;
+    /// The displayed image after the model's preprocessing, rebuilt when the image changes.
+    fn model_input_texture(&mut self, ctx: &egui::Context, image_path: &str) -> egui::TextureHandle {
+        match &self.model_input {
+            Some((path, texture)) if path == image_path => texture.clone(),
+            _ => {
+                let input = self.model.preprocessing().apply(&decode_image(image_path)).to_rgba8();
+                let size = [input.width() as usize, input.height() as usize];
+                let texture = ctx.load_texture(
+                    "model_input",
+                    egui::ColorImage::from_rgba_unmultiplied(size, input.as_raw()),
+                    Default::default(),
+                );
+                self.model_input = Some((image_path.to_string(), texture.clone()));
+                texture
+            }
+        }
     }
-}
 
-fn load_data() -> Vec<HotNotDogsData> {
+    fn thumbnail(&self, ui: &mut egui::Ui, index: usize) -> egui::Response {
+        let image = Image::new(format!("file://{}", self.stream[index].image_path))
+            .fit_to_exact_size(egui::vec2(THUMBNAIL_SIZE, THUMBNAIL_SIZE));
+        let selected = self.dropped_image.is_none() && index == self.current_image;
+        ui.add(ImageButton::new(image).selected(selected))
+            .on_hover_text(format!(
+                "Label: {}",
+                TrueLabel::from(self.stream[index].label)
+            ))
+    }
 
-    let mut stream: Vec<HotNotDogsData> = Vec::new();
+    fn calibrate_unsure(&mut self) {
+        let Some(dataset) = &self.dataset else {
+            return;
+        };
+        let samples = match load_split(dataset, "train") {
+            Ok(samples) => samples,
+            Err(error) => {
+                eprintln!("{error}");
+                return;
+            }
+        };
+        let detector = calibrate_ood(&self.model, &samples, OodMethod::default(), 0.95, 8);
+        if let Some(detector) = detector {
+            self.model = std::mem::take(&mut self.model).with_ood_detector(detector);
+        }
+    }
 
-    let hotdog_path = "./artifacts/seefood_imgs/train/hot_dog/";
-    let not_hotdog_path = "./artifacts/seefood_imgs/train/not_hot_dog/";
+    fn predict_current(&mut self) {
+        let Some(image_path) = self.displayed_image().map(str::to_owned) else {
+            return;
+        };
+
+        let mut timings = StageTimings::default();
+        let decoded = timings.time(Stage::Decode, || decode_image(&image_path));
+        let resized = timings.time(Stage::Resize, || self.model.preprocessing().apply(&decoded));
+        let image: burn::tensor::Tensor<Autodiff<Wgpu>, 4> =
+            timings.time(Stage::ToTensor, || image_to_tensor(&resized));
+        let prediction = match self.model.predict_profiled(image, &mut timings) {
+            "HotDog" => Predicted::Label(TrueLabel::HotDog),
+            label if label == UNSURE_LABEL => Predicted::Unsure,
+            _ => Predicted::Label(TrueLabel::NotHotDog),
+        };
+        self.last_timings = Some(timings);
+        if self.dropped_image.is_none() {
+            self.predictions[self.current_image] = Some(prediction);
+        }
+        self.prediction = Some(prediction);
+        self.show_prediction = true;
+    }
 
-    let hotdog_files = std::fs::read_dir(hotdog_path).unwrap();
-    let not_hotdog_files = std::fs::read_dir(not_hotdog_path).unwrap();
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        // Leave the keyboard to text fields and combo boxes while they have focus.
+        if ctx.wants_keyboard_input() {
+            return;
+        }
 
-    for entry in hotdog_files {
-        let entry = entry.unwrap();
-        let path = entry.path();
-        let path_str = path.to_str().unwrap();
-        stream.push(HotNotDogsData {
-            image_path: path_str.to_string(),
-            label: true,
+        let (previous, next, predict, train, grid, timings) = ctx.input(|input| {
+            (
+                input.key_pressed(Key::ArrowLeft),
+                input.key_pressed(Key::ArrowRight),
+                input.key_pressed(Key::P),
+                input.key_pressed(Key::T),
+                input.key_pressed(Key::G),
+                input.key_pressed(Key::I),
+            )
         });
+
+        if previous {
+            self.previous_image();
+        }
+        if next {
+            self.next_image();
+        }
+        if predict {
+            self.predict_current();
+        }
+        if train && self.dropped_image.is_none() {
+            self.show_training = true;
+        }
+        if timings {
+            self.show_timings = !self.show_timings;
+        }
+        if grid {
+            self.view = match self.view {
+                BrowseView::Single => BrowseView::Grid,
+                BrowseView::Grid => BrowseView::Single,
+            };
+        }
     }
 
-    for entry in not_hotdog_files {
-        let entry = entry.unwrap();
-        let path = entry.path();
-        let path_str = path.to_str().unwrap();
-        stream.push(HotNotDogsData {
-            image_path: path_str.to_string(),
-            label: false,
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input(|input| {
+            input
+                .raw
+                .dropped_files
+                .iter()
+                .find_map(|file| file.path.clone())
         });
+
+        if let Some(path) = dropped {
+            self.dropped_image = Some(path.to_string_lossy().into_owned());
+            self.view = BrowseView::Single;
+            self.show_training = false;
+            self.predict_current();
+        }
     }
 
-    // shuffle the stream
-    stream.shuffle(&mut rand::thread_rng());
+    fn select_image(&mut self, index: usize) {
+        self.current_image = index;
+        self.dropped_image = None;
+        self.prediction = self.predictions[index];
+        self.show_prediction = self.prediction.is_some();
+        self.show_training = false;
+    }
 
-    stream
+    fn next_image(&mut self) {
+        let visible = self.visible_images();
+        if visible.is_empty() {
+            return;
+        }
+
+        // Wrap around to the first matching image after the last one.
+        let next = visible
+            .iter()
+            .copied()
+            .find(|&index| index > self.current_image)
+            .unwrap_or(visible[0]);
+        self.select_image(next);
+    }
+
+    fn previous_image(&mut self) {
+        let visible = self.visible_images();
+        if visible.is_empty() {
+            return;
+        }
+
+        let previous = visible
+            .iter()
+            .copied()
+            .rev()
+            .find(|&index| index < self.current_image)
+            .unwrap_or(visible[visible.len() - 1]);
+        self.select_image(previous);
+    }
 }
@@ -0,0 +1,61 @@
+//! Checks the dataset for broken files, duplicates and images leaking between splits.
+//!
+//! Exits with status 1 if anything was found. With `--quarantine` the undecodable files
+//! and all but one copy of every exact duplicate are moved out of the dataset, keeping
+//! their path relative to the data root; `--include-near` moves near-duplicates too.
+//! Images with a misleading extension are only listed with the name they should have.
+//!
+//! ```text
+//! cargo run --release --bin audit -- [--min-size 64] [--near-distance 4]
+//!     [--output audit.json] [--quarantine artifacts/quarantine] [--include-near]
+//!     [dataset flags, see DatasetConfig::from_args]
+//! ```
+use std::path::PathBuf;
+
+use hotnotdog::data::audit::{audit, quarantine, AuditConfig};
+use hotnotdog::data::config::{flag, DatasetConfig};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let dataset = DatasetConfig::from_args(args.clone()).unwrap_or_else(|error| panic!("{error}"));
+
+    let defaults = AuditConfig::default();
+    let config = AuditConfig {
+        min_size: flag(&args, "--min-size")
+            .map(|value| {
+                value
+                    .parse()
+                    .expect("--min-size expects a number of pixels")
+            })
+            .unwrap_or(defaults.min_size),
+        near_distance: flag(&args, "--near-distance")
+            .map(|value| {
+                value
+                    .parse()
+                    .expect("--near-distance expects a number of bits")
+            })
+            .unwrap_or(defaults.near_distance),
+    };
+
+    let report = audit(&dataset, &config).unwrap_or_else(|error| panic!("{error}"));
+    println!("{report}");
+
+    if let Some(output) = flag(&args, "--output") {
+        report
+            .save(&PathBuf::from(&output))
+            .unwrap_or_else(|error| panic!("{error}"));
+        println!("Report written to {output}");
+    }
+
+    if let Some(target) = flag(&args, "--quarantine") {
+        let include_near = args.iter().any(|arg| arg == "--include-near");
+        let moved = quarantine(&report, &PathBuf::from(&target), include_near)
+            .unwrap_or_else(|error| panic!("Quarantine failed: {error}"));
+        for (from, to) in &moved {
+            println!("Moved {} to {}", from.display(), to.display());
+        }
+        println!("Quarantined {} files in {target}", moved.len());
+    } else if !report.is_clean() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,84 @@
+//! Times every inference stage on ndarray and wgpu at several batch sizes and writes a JSON
+//! report, so runs on different commits can be compared.
+//!
+//! ```text
+//! cargo run --release --bin bench_report -- [--images 32] [--output bench-reports/latest.json]
+//!     [--compare bench-reports/previous.json] [dataset flags, see DatasetConfig::from_args]
+//! ```
+use std::path::PathBuf;
+
+use burn::backend::{NdArray, Wgpu};
+
+use hotnotdog::data::config::{flag, DatasetConfig};
+use hotnotdog::data::dataset::load_data;
+use hotnotdog::model::squeezenet;
+use hotnotdog::profiling::{profile_pipeline, BenchEntry, BenchReport};
+
+const BATCH_SIZES: [usize; 4] = [1, 4, 8, 16];
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let config = DatasetConfig::from_args(args.clone()).unwrap_or_else(|error| panic!("{error}"));
+
+    let image_count: usize = flag(&args, "--images")
+        .map(|value| value.parse().expect("--images expects a number"))
+        .unwrap_or(32);
+    let output = flag(&args, "--output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("bench-reports/latest.json"));
+
+    let paths: Vec<String> = load_data(&config)
+        .unwrap_or_else(|error| panic!("{error}"))
+        .into_iter()
+        .take(image_count)
+        .map(|sample| sample.image_path)
+        .collect();
+
+    let mut entries = Vec::new();
+
+    let squeezenet_imported = squeezenet::Model::<NdArray>::from_embedded();
+    let model = squeezenet::Classifier::<NdArray>::new_from_squeezenet(&squeezenet_imported);
+    for batch_size in BATCH_SIZES {
+        let timings = profile_pipeline(&model, &config.preprocessing, &paths, batch_size);
+        println!("ndarray batch {batch_size:>2}: {timings}");
+        entries.push(BenchEntry::new("ndarray", batch_size, paths.len(), &timings));
+    }
+
+    let squeezenet_imported = squeezenet::Model::<Wgpu>::from_embedded();
+    let model = squeezenet::Classifier::<Wgpu>::new_from_squeezenet(&squeezenet_imported);
+    // The first run compiles the shaders, keep it out of the numbers.
+    profile_pipeline(&model, &config.preprocessing, &paths[..paths.len().min(1)], 1);
+    for batch_size in BATCH_SIZES {
+        let timings = profile_pipeline(&model, &config.preprocessing, &paths, batch_size);
+        println!("wgpu    batch {batch_size:>2}: {timings}");
+        entries.push(BenchEntry::new("wgpu", batch_size, paths.len(), &timings));
+    }
+
+    let report = BenchReport::new(entries);
+    report.save(&output).expect("Failed to write the benchmark report");
+    println!("Wrote {}", output.display());
+
+    if let Some(previous) = flag(&args, "--compare") {
+        let previous = BenchReport::load(&PathBuf::from(&previous))
+            .unwrap_or_else(|error| panic!("Failed to read {previous}: {error}"));
+        println!(
+            "Throughput against {}:",
+            previous.commit.as_deref().unwrap_or("previous report")
+        );
+        for entry in &report.entries {
+            let before = previous.entries.iter().find(|old| {
+                old.backend == entry.backend && old.batch_size == entry.batch_size
+            });
+            if let Some(before) = before {
+                println!(
+                    "{:<8} batch {:>2}: {:>8.2} -> {:>8.2} images/s ({:+.1}%)",
+                    entry.backend,
+                    entry.batch_size,
+                    before.images_per_second,
+                    entry.images_per_second,
+                    (entry.images_per_second / before.images_per_second - 1.0) * 100.0
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Writes a fine-tuned classifier checkpoint as an ONNX graph.
+//!
+//! ```text
+//! cargo run --bin export_onnx -- --checkpoint classifier_f32.bin [--output classifier.onnx]
+//!     [--bake-normalization]
+//! ```
+use std::path::PathBuf;
+
+use burn::backend::NdArray;
+
+use hotnotdog::data::config::flag;
+use hotnotdog::model::onnx::{export_onnx, OnnxExportConfig};
+use hotnotdog::model::quantization::{load_classifier, Precision};
+use hotnotdog::model::squeezenet;
+
+type Backend = NdArray;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let output = flag(&args, "--output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("classifier.onnx"));
+    let config = OnnxExportConfig {
+        bake_normalization: args.iter().any(|arg| arg == "--bake-normalization"),
+    };
+
+    let model = match flag(&args, "--checkpoint") {
+        Some(path) => load_classifier::<Backend>(&PathBuf::from(path), Precision::Full)
+            .unwrap_or_else(|error| panic!("Failed to load checkpoint: {error}")),
+        None => {
+            println!("No --checkpoint given, exporting the untrained head on the ImageNet trunk.");
+            let squeezenet_imported = squeezenet::Model::<Backend>::from_embedded();
+            squeezenet::Classifier::<Backend>::new_from_squeezenet(&squeezenet_imported)
+        }
+    };
+
+    export_onnx(&model, &output, config).unwrap_or_else(|error| panic!("{error}"));
+    println!("Wrote {}", output.display());
+}
@@ -0,0 +1,90 @@
+//! Exports the classifier in f32, f16 and int8 and compares file size, accuracy and
+//! latency on the CPU.
+//!
+//! Each precision runs in its own arithmetic through a `LowPrecisionClassifier`, and the
+//! f32 classifier on the ndarray backend is timed for reference. Images are preprocessed
+//! the way the checkpoint was trained, read from its `.json` metadata when present.
+//!
+//! ```text
+//! cargo run --release --bin quantize -- [--checkpoint classifier_f32.bin] [--output dir]
+//!     [--calibration 8] [--limit 100] [dataset flags, see DatasetConfig::from_args]
+//! ```
+use std::path::PathBuf;
+
+use burn::backend::NdArray;
+use burn::tensor::Tensor;
+
+use hotnotdog::data::config::{flag, DatasetConfig};
+use hotnotdog::data::dataset::{load_split, HotNotDogsData};
+use hotnotdog::model::quantization::{compare_precisions, load_classifier, Precision};
+use hotnotdog::model::squeezed_classifier::{load_image_with, CheckpointMetadata};
+use hotnotdog::model::squeezenet;
+
+type Backend = NdArray;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let config = DatasetConfig::from_args(args.clone()).unwrap_or_else(|error| panic!("{error}"));
+
+    let output = flag(&args, "--output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("artifacts/quantized"));
+    let calibration_count: usize = flag(&args, "--calibration")
+        .map(|value| value.parse().expect("--calibration expects a number"))
+        .unwrap_or(8);
+    let limit: usize = flag(&args, "--limit")
+        .map(|value| value.parse().expect("--limit expects a number"))
+        .unwrap_or(usize::MAX);
+
+    let (model, preprocessing) = match flag(&args, "--checkpoint").map(PathBuf::from) {
+        Some(path) => {
+            let model = load_classifier::<Backend>(&path, Precision::Full)
+                .unwrap_or_else(|error| panic!("Failed to load checkpoint: {error}"));
+            // Checkpoints written by `train` carry the preprocessing they were trained with.
+            let preprocessing = if path.with_extension("json").is_file() {
+                CheckpointMetadata::load(&path)
+                    .unwrap_or_else(|error| panic!("Failed to load checkpoint: {error}"))
+                    .preprocessing
+            } else {
+                config.preprocessing
+            };
+            (model, preprocessing)
+        }
+        None => {
+            println!("No --checkpoint given, using the untrained head on the ImageNet trunk.");
+            let squeezenet_imported = squeezenet::Model::<Backend>::from_embedded();
+            let model =
+                squeezenet::Classifier::<Backend>::new_from_squeezenet(&squeezenet_imported);
+            (model, config.preprocessing)
+        }
+    };
+
+    let calibration: Vec<Tensor<Backend, 4>> = load_split(&config, "train")
+        .unwrap_or_else(|error| panic!("{error}"))
+        .iter()
+        .take(calibration_count)
+        .map(|sample| load_image_with(&sample.image_path, &preprocessing))
+        .collect();
+
+    let evaluation: Vec<HotNotDogsData> = load_split(&config, "test")
+        .unwrap_or_else(|error| panic!("{error}"))
+        .into_iter()
+        .take(limit)
+        .collect();
+    let images: Vec<Tensor<Backend, 4>> = evaluation
+        .iter()
+        .map(|sample| load_image_with(&sample.image_path, &preprocessing))
+        .collect();
+    let labels: Vec<usize> = evaluation.iter().map(|sample| sample.label as usize).collect();
+
+    println!(
+        "Calibrating on {} train images, evaluating on {} test images.",
+        calibration.len(),
+        images.len()
+    );
+
+    let comparison = compare_precisions(&model, &output, &calibration, &images, &labels)
+        .unwrap_or_else(|error| panic!("{error}"));
+    println!("{comparison}");
+    println!("Exported checkpoints to {}", output.display());
+}
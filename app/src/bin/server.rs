@@ -0,0 +1,50 @@
+//! Serves hot-dog predictions over HTTP, see `hotnotdog::server` for the endpoints.
+//!
+//! ```text
+//! cargo run --release --bin server -- [--addr 127.0.0.1:8080] [--max-batch 8]
+//!     [--batch-window-ms 5] [--threads 4] [--backend wgpu|ndarray]
+//!     [--resize exact|center_crop|letterbox] [--filter lanczos3]
+//!     [--checkpoint artifacts/checkpoints/best.bin]
+//! ```
+//!
+//! Preprocessing is read like the app's, including `hotnotdog.toml`.
+use std::path::PathBuf;
+use std::time::Duration;
+
+use burn::backend::{Autodiff, NdArray, Wgpu};
+
+use hotnotdog::data::config::{flag, DatasetConfig};
+use hotnotdog::server::{InferenceServer, ServerConfig};
+
+fn main() {
+    env_logger::init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let dataset = DatasetConfig::from_args(args.clone()).unwrap_or_else(|error| panic!("{error}"));
+    let addr = flag(&args, "--addr").unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let defaults = ServerConfig::default();
+    let config = ServerConfig {
+        max_batch: flag(&args, "--max-batch")
+            .map(|value| value.parse().expect("--max-batch expects a number"))
+            .unwrap_or(defaults.max_batch),
+        batch_window: flag(&args, "--batch-window-ms")
+            .map(|value| Duration::from_millis(value.parse().expect("--batch-window-ms expects a number")))
+            .unwrap_or(defaults.batch_window),
+        http_threads: flag(&args, "--threads")
+            .map(|value| value.parse().expect("--threads expects a number"))
+            .unwrap_or(defaults.http_threads),
+        preprocessing: dataset.preprocessing,
+        checkpoint: flag(&args, "--checkpoint").map(PathBuf::from),
+        ..defaults
+    };
+
+    let server = match flag(&args, "--backend").as_deref() {
+        Some("ndarray") => InferenceServer::start::<Autodiff<NdArray>>(&addr, config),
+        Some("wgpu") | None => InferenceServer::start::<Autodiff<Wgpu>>(&addr, config),
+        Some(other) => panic!("Unknown backend {other}, expected wgpu or ndarray"),
+    }
+    .unwrap_or_else(|error| panic!("Failed to start server on {addr}: {error}"));
+
+    println!("Listening on http://{}", server.addr());
+    server.join();
+}
@@ -0,0 +1,83 @@
+//! Grid or random search over the training hyperparameters on the ndarray backend.
+//!
+//! List flags take comma separated candidates, anything not given uses
+//! `SearchSpace::default()`.
+//!
+//! ```text
+//! cargo run --release --bin sweep -- [--random 10] [--lr 0.1,0.01] [--momentum 0,0.9]
+//!     [--weight-decay 0,0.0001] [--batch-size 8,16] [--freeze 0,26] [--epochs 10]
+//!     [--patience 2] [--validation 0.2] [--results artifacts/sweeps]
+//!     [dataset flags, see DatasetConfig::from_args]
+//! ```
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use burn::backend::{Autodiff, NdArray};
+
+use hotnotdog::data::config::{flag, parsed, DatasetConfig};
+use hotnotdog::data::dataset::{load_split, split_validation};
+use hotnotdog::sweep::{run_sweep, SearchSpace, Strategy};
+use hotnotdog::training::TrainingConfig;
+
+type Backend = Autodiff<NdArray>;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let dataset = DatasetConfig::from_args(args.clone()).unwrap_or_else(|error| panic!("{error}"));
+
+    let defaults = SearchSpace::default();
+    let space = SearchSpace {
+        learning_rates: list(&args, "--lr").unwrap_or(defaults.learning_rates),
+        momentums: list(&args, "--momentum").unwrap_or(defaults.momentums),
+        weight_decays: list(&args, "--weight-decay").unwrap_or(defaults.weight_decays),
+        batch_sizes: list(&args, "--batch-size").unwrap_or(defaults.batch_sizes),
+        freeze_depths: list(&args, "--freeze").unwrap_or(defaults.freeze_depths),
+    };
+    let strategy = match flag(&args, "--random") {
+        Some(trials) => Strategy::Random {
+            trials: trials.parse().expect("--random expects a number of trials"),
+        },
+        None => Strategy::Grid,
+    };
+
+    let training = TrainingConfig {
+        max_epochs: parsed(&args, "--epochs").unwrap_or(10),
+        patience: parsed(&args, "--patience").unwrap_or(2),
+        seed: dataset.seed,
+        ..TrainingConfig::default()
+    };
+    let validation_fraction: f32 = parsed(&args, "--validation").unwrap_or(0.2);
+    let results = flag(&args, "--results")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("artifacts/sweeps"));
+
+    let samples = load_split(&dataset, "train").unwrap_or_else(|error| panic!("{error}"));
+    let (train, validation) = split_validation(&samples, validation_fraction, dataset.seed);
+
+    let leaderboard = run_sweep::<Backend>(
+        &space,
+        strategy,
+        &train,
+        &validation,
+        &training,
+        dataset.preprocessing,
+        &results,
+    )
+    .unwrap_or_else(|error| panic!("{error}"));
+    print!("{leaderboard}");
+    println!("Results written to {}", results.display());
+}
+
+fn list<T: FromStr>(args: &[String], name: &str) -> Option<Vec<T>> {
+    flag(args, name).map(|values| {
+        values
+            .split(',')
+            .map(|value| {
+                value
+                    .trim()
+                    .parse()
+                    .unwrap_or_else(|_| panic!("{name} got an invalid value `{value}`"))
+            })
+            .collect()
+    })
+}
@@ -0,0 +1,107 @@
+//! Fine-tunes the classifier head on the seefood training folders with early stopping.
+//!
+//! A stratified share of `train` is held out for validation. The best epoch is kept in
+//! `<checkpoint-dir>/best.bin`. With `--folds k` the `train` folders are instead split
+//! into `k` stratified folds, one classifier is trained per fold, stopping early on the
+//! `--validation` share of the other folds, and the spread of its metrics on the fold is
+//! reported.
+//!
+//! After training the out-of-distribution threshold is calibrated on the training
+//! images so that `--ood-coverage` of them are accepted, and stored in the checkpoint.
+//!
+//! ```text
+//! cargo run --release --bin train -- [--batch-size 8] [--epochs 20] [--patience 3]
+//!     [--min-delta 0.0001] [--monitor loss|accuracy] [--validation 0.2] [--folds 5]
+//!     [--lr 0.1] [--momentum 0] [--weight-decay 0] [--ood-coverage 0.95]
+//!     [--checkpoint-dir artifacts/checkpoints] [dataset flags, see DatasetConfig::from_args]
+//! ```
+use std::path::PathBuf;
+
+use burn::backend::{Autodiff, Wgpu};
+
+use hotnotdog::cross_validation::cross_validate;
+use hotnotdog::data::config::{flag, parsed, DatasetConfig};
+use hotnotdog::data::dataset::{load_split, split_validation};
+use hotnotdog::evaluation::calibrate_ood;
+use hotnotdog::model::ood::OodMethod;
+use hotnotdog::model::squeezed_classifier::{HotNotDogClassifier, OptimizerSettings};
+use hotnotdog::training::{fit, Monitor, TrainingConfig};
+
+type Backend = Autodiff<Wgpu>;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let dataset = DatasetConfig::from_args(args.clone()).unwrap_or_else(|error| panic!("{error}"));
+
+    let defaults = TrainingConfig::default();
+    let config = TrainingConfig {
+        batch_size: parsed(&args, "--batch-size").unwrap_or(defaults.batch_size),
+        max_epochs: parsed(&args, "--epochs").unwrap_or(defaults.max_epochs),
+        patience: parsed(&args, "--patience").unwrap_or(defaults.patience),
+        min_delta: parsed(&args, "--min-delta").unwrap_or(defaults.min_delta),
+        monitor: match flag(&args, "--monitor").as_deref() {
+            None | Some("loss") => Monitor::Loss,
+            Some("accuracy") => Monitor::Accuracy,
+            Some(other) => panic!("--monitor expects loss or accuracy, got `{other}`"),
+        },
+        checkpoint_dir: flag(&args, "--checkpoint-dir")
+            .map(PathBuf::from)
+            .unwrap_or(defaults.checkpoint_dir),
+        seed: dataset.seed,
+    };
+    let optimizer_defaults = OptimizerSettings::default();
+    let settings = OptimizerSettings {
+        learning_rate: parsed(&args, "--lr").unwrap_or(optimizer_defaults.learning_rate),
+        momentum: parsed(&args, "--momentum").unwrap_or(optimizer_defaults.momentum),
+        weight_decay: parsed(&args, "--weight-decay").unwrap_or(optimizer_defaults.weight_decay),
+    };
+    let validation_fraction: f32 = parsed(&args, "--validation").unwrap_or(0.2);
+
+    let samples = load_split(&dataset, "train").unwrap_or_else(|error| panic!("{error}"));
+
+    if let Some(folds) = parsed::<usize>(&args, "--folds") {
+        let report = cross_validate::<Backend>(
+            &samples,
+            folds,
+            validation_fraction,
+            &config,
+            settings,
+            dataset.preprocessing,
+        )
+        .unwrap_or_else(|error| panic!("{error}"));
+        println!("{report}");
+        return;
+    }
+
+    let (train, validation) = split_validation(&samples, validation_fraction, dataset.seed);
+    println!(
+        "Training on {} images, validating on {}.",
+        train.len(),
+        validation.len()
+    );
+
+    let mut model = HotNotDogClassifier::<Backend>::new_seeded(dataset.seed)
+        .with_optimizer(settings)
+        .with_preprocessing(dataset.preprocessing);
+    let report =
+        fit(&mut model, &train, &validation, &config).unwrap_or_else(|error| panic!("{error}"));
+    println!("{report}");
+
+    if report.best.is_none() {
+        return;
+    }
+    let coverage: f32 = parsed(&args, "--ood-coverage").unwrap_or(0.95);
+    if let Some(detector) =
+        calibrate_ood(&model, &train, OodMethod::default(), coverage, config.batch_size)
+    {
+        println!(
+            "Out-of-distribution threshold {:.4} keeps {:.1}% of the training images.",
+            detector.threshold,
+            detector.coverage * 100.0
+        );
+        model = model.with_ood_detector(detector);
+        model
+            .save_checkpoint(&report.checkpoint)
+            .unwrap_or_else(|error| panic!("{error}"));
+    }
+}
@@ -0,0 +1,59 @@
+//! Compares single-view prediction against test-time augmentation on the test split.
+//!
+//! ```text
+//! cargo run --release --bin tta_report -- [--checkpoint artifacts/checkpoints/best.bin]
+//!     [--limit 100] [--output tta_report.json] [dataset flags, see DatasetConfig::from_args]
+//! ```
+use std::path::PathBuf;
+
+use burn::backend::{Autodiff, NdArray};
+
+use hotnotdog::data::config::{flag, DatasetConfig};
+use hotnotdog::data::dataset::{load_split, HotNotDogsData};
+use hotnotdog::evaluation::compare_tta;
+use hotnotdog::model::squeezed_classifier::HotNotDogClassifier;
+use hotnotdog::model::tta::TestTimeAugmentation;
+
+type Backend = Autodiff<NdArray>;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let config = DatasetConfig::from_args(args.clone()).unwrap_or_else(|error| panic!("{error}"));
+    let limit: usize = flag(&args, "--limit")
+        .map(|value| value.parse().expect("--limit expects a number"))
+        .unwrap_or(usize::MAX);
+
+    // A checkpoint brings the preprocessing it was trained with.
+    let model = match flag(&args, "--checkpoint") {
+        Some(path) => HotNotDogClassifier::<Backend>::load_checkpoint(&PathBuf::from(path))
+            .unwrap_or_else(|error| panic!("Failed to load checkpoint: {error}")),
+        None => {
+            println!("No --checkpoint given, using the untrained head on the ImageNet trunk.");
+            HotNotDogClassifier::<Backend>::new_seeded(config.seed)
+                .with_preprocessing(config.preprocessing)
+        }
+    };
+
+    let samples: Vec<HotNotDogsData> = load_split(&config, "test")
+        .unwrap_or_else(|error| panic!("{error}"))
+        .into_iter()
+        .take(limit)
+        .collect();
+    println!("Evaluating on {} test images.", samples.len());
+
+    let settings = [
+        ("single", TestTimeAugmentation::default()),
+        ("flips", TestTimeAugmentation::flips()),
+        ("full", TestTimeAugmentation::full()),
+    ];
+    let reports = compare_tta(&model, &samples, &settings);
+    for report in &reports {
+        println!("{report}");
+    }
+
+    if let Some(output) = flag(&args, "--output") {
+        let json = serde_json::to_string_pretty(&reports).expect("reports serialize");
+        std::fs::write(&output, json).unwrap_or_else(|error| panic!("{error}"));
+        println!("Report written to {output}");
+    }
+}
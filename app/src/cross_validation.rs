@@ -0,0 +1,198 @@
+//! Stratified k-fold cross-validation.
+//!
+//! Every fold is held out once while a freshly seeded classifier is trained on the
+//! others with [`fit`], so the spread across folds shows how much a single split's
+//! accuracy can be trusted. Early stopping watches a validation share carved out of the
+//! other folds; the held-out fold is only used to score the final model.
+use std::fmt;
+
+use burn::tensor::{
+    backend::{AutodiffBackend, Backend},
+    Data,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::data::dataset::{split_validation, stratified_folds, HotNotDogsData};
+use crate::evaluation::{confusion_matrix, ConfusionMatrix};
+use crate::model::preprocessing::Preprocessing;
+use crate::model::squeezed_classifier::{HotNotDogClassifier, OptimizerSettings};
+use crate::training::{fit, validate, StopReason, TrainingConfig, TrainingError};
+
+/// Result of one fold.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoldResult {
+    pub fold: usize,
+    pub train_size: usize,
+    /// Images of the other folds held out for early stopping.
+    pub validation_size: usize,
+    /// Images of the fold itself, which `loss` and `confusion` are measured on.
+    pub test_size: usize,
+    pub loss: f32,
+    pub confusion: ConfusionMatrix,
+    pub epochs: usize,
+    pub stop_reason: StopReason,
+}
+
+/// Mean and sample standard deviation of one metric across folds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub mean: f32,
+    pub std: f32,
+}
+
+impl Summary {
+    pub fn of(values: &[f32]) -> Self {
+        let count = values.len() as f32;
+        if values.is_empty() {
+            return Self {
+                mean: 0.0,
+                std: 0.0,
+            };
+        }
+        let mean = values.iter().sum::<f32>() / count;
+        let variance = if values.len() > 1 {
+            values
+                .iter()
+                .map(|value| (value - mean).powi(2))
+                .sum::<f32>()
+                / (count - 1.0)
+        } else {
+            0.0
+        };
+        Self {
+            mean,
+            std: variance.sqrt(),
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.4} ± {:.4}", self.mean, self.std)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossValidationReport {
+    pub folds: Vec<FoldResult>,
+    pub loss: Summary,
+    pub accuracy: Summary,
+    pub precision: Summary,
+    pub recall: Summary,
+    pub f1: Summary,
+}
+
+impl CrossValidationReport {
+    fn new(folds: Vec<FoldResult>) -> Self {
+        let metric =
+            |f: fn(&FoldResult) -> f32| Summary::of(&folds.iter().map(f).collect::<Vec<_>>());
+        Self {
+            loss: metric(|fold| fold.loss),
+            accuracy: metric(|fold| fold.confusion.accuracy()),
+            precision: metric(|fold| fold.confusion.precision()),
+            recall: metric(|fold| fold.confusion.recall()),
+            f1: metric(|fold| fold.confusion.f1()),
+            folds,
+        }
+    }
+
+    /// Sum of the per-fold matrices, every image counted exactly once.
+    pub fn total_confusion(&self) -> ConfusionMatrix {
+        let mut total = ConfusionMatrix::default();
+        for fold in &self.folds {
+            total += fold.confusion;
+        }
+        total
+    }
+}
+
+impl fmt::Display for CrossValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for fold in &self.folds {
+            writeln!(
+                f,
+                "Fold {}: {} train / {} validation / {} test images, {} epochs ({})",
+                fold.fold,
+                fold.train_size,
+                fold.validation_size,
+                fold.test_size,
+                fold.epochs,
+                fold.stop_reason
+            )?;
+            writeln!(f, "{}", fold.confusion)?;
+        }
+        writeln!(f, "{} folds:", self.folds.len())?;
+        writeln!(f, "  loss      {}", self.loss)?;
+        writeln!(f, "  accuracy  {}", self.accuracy)?;
+        writeln!(f, "  precision {}", self.precision)?;
+        writeln!(f, "  recall    {}", self.recall)?;
+        write!(f, "  f1        {}", self.f1)
+    }
+}
+
+/// Trains one classifier per fold of `samples` and summarises its metrics on the fold.
+///
+/// `validation_fraction` of the other folds is held out for early stopping, like
+/// [`split_validation`] does for a single run. Fold `i` keeps its best checkpoint in
+/// `<config.checkpoint_dir>/fold-<i>`.
+pub fn cross_validate<B: AutodiffBackend>(
+    samples: &[HotNotDogsData],
+    k: usize,
+    validation_fraction: f32,
+    config: &TrainingConfig,
+    settings: OptimizerSettings,
+    preprocessing: Preprocessing,
+) -> Result<CrossValidationReport, TrainingError>
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    if k < 2 {
+        return Err(TrainingError::Argument {
+            name: "number of folds",
+            reason: format!("{k} leaves nothing to train on, use at least 2"),
+        });
+    }
+
+    let folds = stratified_folds(samples, k, config.seed);
+    let mut results = Vec::with_capacity(folds.len());
+
+    for (index, test) in folds.iter().enumerate() {
+        let others: Vec<HotNotDogsData> = folds
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != index)
+            .flat_map(|(_, fold)| fold.iter().cloned())
+            .collect();
+        let (train, validation) = split_validation(&others, validation_fraction, config.seed);
+        println!(
+            "Fold {index} of {}: {} train, {} validation, {} test images",
+            folds.len(),
+            train.len(),
+            validation.len(),
+            test.len()
+        );
+
+        let fold_config = TrainingConfig {
+            checkpoint_dir: config.checkpoint_dir.join(format!("fold-{index}")),
+            ..config.clone()
+        };
+        let mut model = HotNotDogClassifier::<B>::new_seeded(config.seed)
+            .with_optimizer(settings)
+            .with_preprocessing(preprocessing);
+        let report = fit(&mut model, &train, &validation, &fold_config)?;
+        let (loss, _) = validate(&model, test, config.batch_size);
+
+        results.push(FoldResult {
+            fold: index,
+            train_size: train.len(),
+            validation_size: validation.len(),
+            test_size: test.len(),
+            loss,
+            confusion: confusion_matrix(&model, test, config.batch_size),
+            epochs: report.history.len(),
+            stop_reason: report.stop_reason,
+        });
+    }
+
+    Ok(CrossValidationReport::new(results))
+}
@@ -0,0 +1,553 @@
+//! Integrity checks for the seefood folders.
+//!
+//! Every file below the class folders of every split is read and checked for:
+//!
+//! * contents that do not decode,
+//! * an extension that does not match the contents, or that the loader ignores,
+//! * images smaller than [`AuditConfig::min_size`],
+//! * byte-identical copies, found by hash and confirmed by comparing their contents,
+//! * near-duplicates, i.e. images whose 64-bit difference hashes are within
+//!   [`AuditConfig::near_distance`] bits of each other.
+//!
+//! Duplicates spanning splits are flagged as leaks, since a test image that also sits
+//! in `train` inflates the reported accuracy.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+use image::{imageops::FilterType, GenericImageView, ImageFormat};
+use serde::{Deserialize, Serialize};
+
+use crate::data::config::DatasetConfig;
+use crate::data::dataset::DatasetError;
+
+#[derive(Debug, Clone)]
+pub struct AuditConfig {
+    /// Images with a smaller width or height are reported as tiny.
+    pub min_size: u32,
+    /// Largest Hamming distance between difference hashes that counts as a near-duplicate.
+    pub near_distance: u32,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            min_size: 64,
+            near_distance: 4,
+        }
+    }
+}
+
+/// A problem with a single file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FileIssue {
+    Undecodable {
+        path: PathBuf,
+        reason: String,
+    },
+    /// The contents are `actual`, which the extension does not claim. The file still
+    /// decodes, so it is only suggested for renaming to `extension`.
+    WrongExtension {
+        path: PathBuf,
+        actual: String,
+        extension: String,
+    },
+    /// The extension is not in [`DatasetConfig::extensions`], so the loader skips the file.
+    IgnoredExtension {
+        path: PathBuf,
+    },
+    Tiny {
+        path: PathBuf,
+        width: u32,
+        height: u32,
+    },
+}
+
+impl FileIssue {
+    pub fn path(&self) -> &Path {
+        match self {
+            FileIssue::Undecodable { path, .. }
+            | FileIssue::WrongExtension { path, .. }
+            | FileIssue::IgnoredExtension { path }
+            | FileIssue::Tiny { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for FileIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileIssue::Undecodable { path, reason } => {
+                write!(f, "undecodable      {} ({reason})", path.display())
+            }
+            FileIssue::WrongExtension {
+                path,
+                actual,
+                extension,
+            } => {
+                write!(
+                    f,
+                    "wrong extension  {} (contents are {actual}, rename to .{extension})",
+                    path.display()
+                )
+            }
+            FileIssue::IgnoredExtension { path } => {
+                write!(
+                    f,
+                    "ignored          {} (extension not accepted)",
+                    path.display()
+                )
+            }
+            FileIssue::Tiny {
+                path,
+                width,
+                height,
+            } => write!(f, "tiny             {} ({width}x{height})", path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateKind {
+    Exact,
+    Near,
+}
+
+/// Files that are copies of each other.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    /// Sorted, so the first path is stable between runs.
+    pub paths: Vec<PathBuf>,
+    /// Splits the copies were found in.
+    pub splits: BTreeSet<String>,
+    /// Whether the copies disagree on the class folder they were filed under.
+    pub conflicting_labels: bool,
+}
+
+impl DuplicateGroup {
+    /// The group spans more than one split.
+    pub fn leaks(&self) -> bool {
+        self.splits.len() > 1
+    }
+
+    /// The copy that stays when the group is quarantined: the first one in `test`
+    /// for leaks (so the test set keeps its size), otherwise the first path.
+    pub fn keeper(&self, root: &Path) -> &Path {
+        self.paths
+            .iter()
+            .find(|path| self.leaks() && split_of(root, path).as_deref() == Some("test"))
+            .unwrap_or(&self.paths[0])
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditReport {
+    pub root: PathBuf,
+    pub files_scanned: usize,
+    pub issues: Vec<FileIssue>,
+    pub duplicates: Vec<DuplicateGroup>,
+}
+
+impl AuditReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty() && self.duplicates.is_empty()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))?;
+        std::fs::write(path, json)
+    }
+
+    /// Files that [`quarantine`] moves: undecodable ones, and every copy but the keeper
+    /// of each duplicate group (near-duplicates only with `include_near`). Images that
+    /// decode stay, whatever else is wrong with them.
+    pub fn quarantine_candidates(&self, include_near: bool) -> Vec<PathBuf> {
+        let mut paths: BTreeSet<PathBuf> = self
+            .issues
+            .iter()
+            .filter(|issue| matches!(issue, FileIssue::Undecodable { .. }))
+            .map(|issue| issue.path().to_path_buf())
+            .collect();
+
+        for group in &self.duplicates {
+            if group.kind == DuplicateKind::Near && !include_near {
+                continue;
+            }
+            let keeper = group.keeper(&self.root);
+            paths.extend(group.paths.iter().filter(|path| *path != keeper).cloned());
+        }
+        paths.into_iter().collect()
+    }
+
+    /// Files with a misleading extension and the name they should have.
+    pub fn rename_suggestions(&self) -> Vec<(PathBuf, PathBuf)> {
+        self.issues
+            .iter()
+            .filter_map(|issue| match issue {
+                FileIssue::WrongExtension {
+                    path, extension, ..
+                } => Some((path.clone(), path.with_extension(extension))),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Scanned {} files in {}",
+            self.files_scanned,
+            self.root.display()
+        )?;
+        for issue in &self.issues {
+            writeln!(f, "  {issue}")?;
+        }
+
+        for group in &self.duplicates {
+            let kind = match group.kind {
+                DuplicateKind::Exact => "exact duplicates",
+                DuplicateKind::Near => "near duplicates",
+            };
+            let mut notes = Vec::new();
+            if group.leaks() {
+                let splits: Vec<&str> = group.splits.iter().map(String::as_str).collect();
+                notes.push(format!("leak across {}", splits.join(" and ")));
+            }
+            if group.conflicting_labels {
+                notes.push("conflicting labels".to_string());
+            }
+            if notes.is_empty() {
+                writeln!(f, "  {kind}:")?;
+            } else {
+                writeln!(f, "  {kind} ({}):", notes.join(", "))?;
+            }
+            for path in &group.paths {
+                writeln!(f, "      {}", path.display())?;
+            }
+        }
+
+        let leaks = self.duplicates.iter().filter(|group| group.leaks()).count();
+        write!(
+            f,
+            "{} file issues, {} duplicate groups, {leaks} leaking between splits",
+            self.issues.len(),
+            self.duplicates.len()
+        )
+    }
+}
+
+struct Scanned {
+    path: PathBuf,
+    split: String,
+    class: String,
+    size: usize,
+    content_hash: u64,
+    /// `None` if the file does not decode.
+    difference_hash: Option<u64>,
+}
+
+/// Audits every split below `dataset.root`.
+pub fn audit(dataset: &DatasetConfig, config: &AuditConfig) -> Result<AuditReport, DatasetError> {
+    let mut issues = Vec::new();
+    let mut scanned = Vec::new();
+
+    for split in splits(&dataset.root)? {
+        let classes = dataset.hot_dog_dirs.iter().chain(&dataset.not_hot_dog_dirs);
+        for class in classes {
+            let class_dir = dataset.split_dir(&split).join(class);
+            if !class_dir.is_dir() {
+                continue;
+            }
+            let mut files = Vec::new();
+            collect_files(&class_dir, dataset.recursive, &mut files)?;
+            files.sort();
+
+            for path in files {
+                let file = check_file(dataset, config, &path, &mut issues)?;
+                scanned.push(Scanned {
+                    split: split.clone(),
+                    class: class.clone(),
+                    ..file
+                });
+            }
+        }
+    }
+
+    let mut duplicates = exact_duplicates(&scanned)?;
+    duplicates.extend(near_duplicates(&scanned, &duplicates, config.near_distance));
+
+    Ok(AuditReport {
+        root: dataset.root.clone(),
+        files_scanned: scanned.len(),
+        issues,
+        duplicates,
+    })
+}
+
+/// Moves the [`AuditReport::quarantine_candidates`] below `target`, keeping their path
+/// relative to the dataset root, and returns where each file went.
+pub fn quarantine(
+    report: &AuditReport,
+    target: &Path,
+    include_near: bool,
+) -> std::io::Result<Vec<(PathBuf, PathBuf)>> {
+    let mut moved = Vec::new();
+    for path in report.quarantine_candidates(include_near) {
+        let relative = path.strip_prefix(&report.root).unwrap_or(&path);
+        let destination = target.join(relative);
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if std::fs::rename(&path, &destination).is_err() {
+            // `rename` cannot cross file systems.
+            std::fs::copy(&path, &destination)?;
+            std::fs::remove_file(&path)?;
+        }
+        moved.push((path, destination));
+    }
+    Ok(moved)
+}
+
+fn splits(root: &Path) -> Result<Vec<String>, DatasetError> {
+    if !root.is_dir() {
+        return Err(DatasetError::MissingDirectory(root.to_path_buf()));
+    }
+    let entries = std::fs::read_dir(root).map_err(|source| DatasetError::Unreadable {
+        path: root.to_path_buf(),
+        reason: source.to_string(),
+    })?;
+
+    let mut splits: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    splits.sort();
+    Ok(splits)
+}
+
+fn split_of(root: &Path, path: &Path) -> Option<String> {
+    path.strip_prefix(root)
+        .ok()?
+        .components()
+        .next()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+}
+
+fn collect_files(
+    dir: &Path,
+    recursive: bool,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), DatasetError> {
+    let entries = std::fs::read_dir(dir).map_err(|source| DatasetError::Unreadable {
+        path: dir.to_path_buf(),
+        reason: source.to_string(),
+    })?;
+    for entry in entries {
+        let path = entry
+            .map_err(|source| DatasetError::Unreadable {
+                path: dir.to_path_buf(),
+                reason: source.to_string(),
+            })?
+            .path();
+        if path.is_dir() {
+            if recursive {
+                collect_files(&path, recursive, files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Hashes one file and records its issues. The split and class are left for the caller.
+fn check_file(
+    dataset: &DatasetConfig,
+    config: &AuditConfig,
+    path: &Path,
+    issues: &mut Vec<FileIssue>,
+) -> Result<Scanned, DatasetError> {
+    let bytes = read_file(path)?;
+
+    if !dataset.accepts(path) {
+        issues.push(FileIssue::IgnoredExtension {
+            path: path.to_path_buf(),
+        });
+    }
+
+    let claimed = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(ImageFormat::from_extension);
+    if let Ok(actual) = image::guess_format(&bytes) {
+        if claimed != Some(actual) {
+            issues.push(FileIssue::WrongExtension {
+                path: path.to_path_buf(),
+                actual: format!("{actual:?}"),
+                extension: actual
+                    .extensions_str()
+                    .first()
+                    .copied()
+                    .unwrap_or_default()
+                    .to_string(),
+            });
+        }
+    }
+
+    let difference_hash = match image::load_from_memory(&bytes) {
+        Ok(image) => {
+            let (width, height) = image.dimensions();
+            if width < config.min_size || height < config.min_size {
+                issues.push(FileIssue::Tiny {
+                    path: path.to_path_buf(),
+                    width,
+                    height,
+                });
+            }
+            Some(difference_hash(&image))
+        }
+        Err(error) => {
+            issues.push(FileIssue::Undecodable {
+                path: path.to_path_buf(),
+                reason: error.to_string(),
+            });
+            None
+        }
+    };
+
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&bytes);
+
+    Ok(Scanned {
+        path: path.to_path_buf(),
+        split: String::new(),
+        class: String::new(),
+        size: bytes.len(),
+        content_hash: hasher.finish(),
+        difference_hash,
+    })
+}
+
+/// 64-bit dHash: each bit says whether a pixel of the 9x8 grayscale thumbnail is
+/// brighter than its right neighbour. Robust to rescaling and recompression.
+pub fn difference_hash(image: &image::DynamicImage) -> u64 {
+    let thumbnail = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = thumbnail.get_pixel(x, y)[0] > thumbnail.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | brighter as u64;
+        }
+    }
+    hash
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, DatasetError> {
+    std::fs::read(path).map_err(|source| DatasetError::Unreadable {
+        path: path.to_path_buf(),
+        reason: source.to_string(),
+    })
+}
+
+/// Groups byte-identical files. Hash and size only pick the candidates; the 64-bit hash
+/// can collide, so the candidates are read again and compared byte for byte.
+fn exact_duplicates(scanned: &[Scanned]) -> Result<Vec<DuplicateGroup>, DatasetError> {
+    let mut by_hash: HashMap<(u64, usize), Vec<&Scanned>> = HashMap::new();
+    for file in scanned {
+        by_hash
+            .entry((file.content_hash, file.size))
+            .or_default()
+            .push(file);
+    }
+
+    let mut groups = Vec::new();
+    for candidates in by_hash.into_values().filter(|files| files.len() > 1) {
+        let mut identical: Vec<(Vec<u8>, Vec<&Scanned>)> = Vec::new();
+        for file in candidates {
+            let bytes = read_file(&file.path)?;
+            match identical.iter_mut().find(|(contents, _)| *contents == bytes) {
+                Some((_, files)) => files.push(file),
+                None => identical.push((bytes, vec![file])),
+            }
+        }
+        groups.extend(
+            identical
+                .into_iter()
+                .filter(|(_, files)| files.len() > 1)
+                .map(|(_, files)| group(DuplicateKind::Exact, files)),
+        );
+    }
+    groups.sort_by(|a, b| a.paths.cmp(&b.paths));
+    Ok(groups)
+}
+
+/// Groups images whose difference hashes are close. Byte-identical files are
+/// represented by the first copy so they do not show up twice.
+fn near_duplicates(
+    scanned: &[Scanned],
+    exact: &[DuplicateGroup],
+    max_distance: u32,
+) -> Vec<DuplicateGroup> {
+    let copies: BTreeSet<&Path> = exact
+        .iter()
+        .flat_map(|group| group.paths.iter().skip(1).map(PathBuf::as_path))
+        .collect();
+    let candidates: Vec<(&Scanned, u64)> = scanned
+        .iter()
+        .filter(|file| !copies.contains(file.path.as_path()))
+        .filter_map(|file| file.difference_hash.map(|hash| (file, hash)))
+        .collect();
+
+    // Union-find over all close pairs.
+    let mut parent: Vec<usize> = (0..candidates.len()).collect();
+    fn find(parent: &mut [usize], mut index: usize) -> usize {
+        while parent[index] != index {
+            parent[index] = parent[parent[index]];
+            index = parent[index];
+        }
+        index
+    }
+    for a in 0..candidates.len() {
+        for b in a + 1..candidates.len() {
+            if (candidates[a].1 ^ candidates[b].1).count_ones() <= max_distance {
+                let (root_a, root_b) = (find(&mut parent, a), find(&mut parent, b));
+                parent[root_b] = root_a;
+            }
+        }
+    }
+
+    let mut clusters: HashMap<usize, Vec<&Scanned>> = HashMap::new();
+    for (index, (file, _)) in candidates.iter().enumerate() {
+        let root = find(&mut parent, index);
+        clusters.entry(root).or_default().push(file);
+    }
+
+    let mut groups: Vec<DuplicateGroup> = clusters
+        .into_values()
+        .filter(|files| files.len() > 1)
+        .map(|files| group(DuplicateKind::Near, files))
+        .collect();
+    groups.sort_by(|a, b| a.paths.cmp(&b.paths));
+    groups
+}
+
+fn group(kind: DuplicateKind, files: Vec<&Scanned>) -> DuplicateGroup {
+    let mut paths: Vec<PathBuf> = files.iter().map(|file| file.path.clone()).collect();
+    paths.sort();
+    let classes: BTreeSet<&str> = files.iter().map(|file| file.class.as_str()).collect();
+    DuplicateGroup {
+        kind,
+        paths,
+        splits: files.iter().map(|file| file.split.clone()).collect(),
+        conflicting_labels: classes.len() > 1,
+    }
+}
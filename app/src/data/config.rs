@@ -0,0 +1,183 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::data::dataset::DatasetError;
+use crate::model::preprocessing::Preprocessing;
+
+/// Default config file looked up in the working directory when `--config` is not given.
+pub const DEFAULT_CONFIG_FILE: &str = "hotnotdog.toml";
+
+/// Where the seefood images live and how to read them.
+///
+/// Every field has a default, so a config file only needs to list what it overrides:
+///
+/// ```toml
+/// root = "/data/seefood_imgs"
+/// split = "test"
+/// seed = 7
+///
+/// [preprocessing]
+/// resize = { mode = "center_crop", resize = 256 }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DatasetConfig {
+    /// Directory holding one sub-directory per split.
+    pub root: PathBuf,
+    /// Split shown in the UI, e.g. `train` or `test`.
+    pub split: String,
+    /// Class folders (relative to the split) whose images are hot dogs.
+    pub hot_dog_dirs: Vec<String>,
+    /// Class folders (relative to the split) whose images are not hot dogs.
+    pub not_hot_dog_dirs: Vec<String>,
+    /// Accepted file extensions, compared case-insensitively.
+    pub extensions: Vec<String>,
+    /// Descend into sub-directories of the class folders.
+    pub recursive: bool,
+    /// Seed for shuffling, head initialisation and dropout, see [`crate::seed`].
+    pub seed: u64,
+    /// How images are resized for the model in training, evaluation and the UI.
+    pub preprocessing: Preprocessing,
+}
+
+impl Default for DatasetConfig {
+    fn default() -> Self {
+        Self {
+            // Relative to the working directory, like a root given with `--data-root`.
+            root: PathBuf::from("artifacts/seefood_imgs"),
+            split: "train".to_string(),
+            hot_dog_dirs: vec!["hot_dog".to_string()],
+            not_hot_dog_dirs: vec!["not_hot_dog".to_string()],
+            extensions: vec!["jpg".to_string(), "jpeg".to_string(), "png".to_string()],
+            recursive: true,
+            seed: 42,
+            preprocessing: Preprocessing::default(),
+        }
+    }
+}
+
+impl DatasetConfig {
+    /// Reads a config from a TOML file. Relative roots are resolved against the file's directory.
+    pub fn from_file(path: &Path) -> Result<Self, DatasetError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| DatasetError::Config {
+            path: path.to_path_buf(),
+            reason: source.to_string(),
+        })?;
+        let mut config: Self =
+            toml::from_str(&contents).map_err(|source| DatasetError::Config {
+                path: path.to_path_buf(),
+                reason: source.to_string(),
+            })?;
+
+        if config.root.is_relative() {
+            if let Some(parent) = path.parent() {
+                config.root = parent.join(&config.root);
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Builds a config from command line arguments.
+    ///
+    /// `--config <file>` is read first (falling back to `hotnotdog.toml` if it exists),
+    /// then `--data-root`, `--split`, `--seed`, `--no-recursive`, `--resize` and `--filter`
+    /// override single fields.
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Self, DatasetError> {
+        let args: Vec<String> = args.into_iter().collect();
+
+        let config_path = flag_value(&args, "--config")?.map(PathBuf::from);
+        let mut config = match config_path {
+            Some(path) => Self::from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).is_file() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => Self::default(),
+        };
+
+        if let Some(root) = flag_value(&args, "--data-root")? {
+            config.root = PathBuf::from(root);
+        }
+        if let Some(split) = flag_value(&args, "--split")? {
+            config.split = split;
+        }
+        if let Some(seed) = flag_value(&args, "--seed")? {
+            config.seed = seed.parse().map_err(|_| DatasetError::Argument {
+                flag: "--seed".to_string(),
+                reason: format!("`{seed}` is not an unsigned integer"),
+            })?;
+        }
+        if args.iter().any(|arg| arg == "--no-recursive") {
+            config.recursive = false;
+        }
+        if let Some(resize) = flag_value(&args, "--resize")? {
+            config.preprocessing.resize =
+                Preprocessing::parse_mode(&resize).ok_or_else(|| DatasetError::Argument {
+                    flag: "--resize".to_string(),
+                    reason: format!(
+                        "`{resize}` is not exact, center_crop, center_crop:<size> or letterbox"
+                    ),
+                })?;
+        }
+        if let Some(filter) = flag_value(&args, "--filter")? {
+            config.preprocessing.filter =
+                Preprocessing::parse_filter(&filter).ok_or_else(|| DatasetError::Argument {
+                    flag: "--filter".to_string(),
+                    reason: format!(
+                        "`{filter}` is not nearest, triangle, catmull_rom, gaussian or lanczos3"
+                    ),
+                })?;
+        }
+
+        Ok(config)
+    }
+
+    /// Directory of the given split.
+    pub fn split_dir(&self, split: &str) -> PathBuf {
+        self.root.join(split)
+    }
+
+    /// Whether `path` has one of the accepted extensions.
+    pub fn accepts(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| {
+                self.extensions
+                    .iter()
+                    .any(|accepted| accepted.eq_ignore_ascii_case(extension))
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// The value following `flag`, if the flag is present. A missing value, or another
+/// flag where the value should be, is an error.
+pub fn flag_value(args: &[String], flag: &str) -> Result<Option<String>, DatasetError> {
+    let Some(index) = args.iter().position(|arg| arg == flag) else {
+        return Ok(None);
+    };
+    match args.get(index + 1) {
+        Some(value) if !value.starts_with("--") => Ok(Some(value.clone())),
+        _ => Err(DatasetError::Argument {
+            flag: flag.to_string(),
+            reason: "missing value".to_string(),
+        }),
+    }
+}
+
+/// [`flag_value`] for the binaries, which stop on a bad command line: panics when the
+/// flag is given without a value.
+pub fn flag(args: &[String], name: &str) -> Option<String> {
+    flag_value(args, name).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// [`flag`] parsed as a `T`, panicking on a value that does not parse.
+pub fn parsed<T: FromStr>(args: &[String], name: &str) -> Option<T> {
+    flag(args, name).map(|value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("{name} got an invalid value `{value}`"))
+    })
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use rand::seq::SliceRandom;
+
+use crate::data::config::DatasetConfig;
+use crate::seed::{stream_rng, RngStream};
+
+/// One image of the stream together with its ground truth.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HotNotDogsData {
+    pub image_path: String,
+    /// `true` for hot dogs.
+    pub label: bool,
+}
+
+/// Everything that can go wrong while locating and reading the dataset.
+#[derive(Debug)]
+pub enum DatasetError {
+    /// The config file could not be read or parsed.
+    Config { path: PathBuf, reason: String },
+    /// A command line flag was malformed.
+    Argument { flag: String, reason: String },
+    /// A class folder does not exist.
+    MissingDirectory(PathBuf),
+    /// A class folder could not be listed.
+    Unreadable { path: PathBuf, reason: String },
+    /// A class folder exists but holds no images with an accepted extension.
+    EmptyDirectory(PathBuf),
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DatasetError::Config { path, reason } => {
+                write!(f, "Could not read config {}: {reason}", path.display())
+            }
+            DatasetError::Argument { flag, reason } => write!(f, "Invalid {flag}: {reason}"),
+            DatasetError::MissingDirectory(path) => {
+                write!(f, "Dataset directory {} does not exist", path.display())
+            }
+            DatasetError::Unreadable { path, reason } => {
+                write!(f, "Could not read {}: {reason}", path.display())
+            }
+            DatasetError::EmptyDirectory(path) => {
+                write!(f, "Dataset directory {} contains no images", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for DatasetError {}
+
+/// Loads every image of `split`, labelled by the class folder it was found in,
+/// and shuffles them with the configured seed.
+pub fn load_split(config: &DatasetConfig, split: &str) -> Result<Vec<HotNotDogsData>, DatasetError> {
+    let split_dir = config.split_dir(split);
+    let mut stream: Vec<HotNotDogsData> = Vec::new();
+
+    let classes = config
+        .hot_dog_dirs
+        .iter()
+        .map(|dir| (dir, true))
+        .chain(config.not_hot_dog_dirs.iter().map(|dir| (dir, false)));
+
+    for (dir, label) in classes {
+        let class_dir = split_dir.join(dir);
+        let mut files = Vec::new();
+        collect_images(config, &class_dir, &mut files)?;
+        if files.is_empty() {
+            return Err(DatasetError::EmptyDirectory(class_dir));
+        }
+
+        stream.extend(files.into_iter().map(|path| HotNotDogsData {
+            image_path: path.to_string_lossy().into_owned(),
+            label,
+        }));
+    }
+
+    // `read_dir` order is platform dependent, sort before shuffling so the seed
+    // alone decides the order.
+    stream.sort_by(|a, b| a.image_path.cmp(&b.image_path));
+    stream.shuffle(&mut stream_rng(config.seed, RngStream::Shuffle));
+
+    Ok(stream)
+}
+
+/// Loads the split selected in the config.
+pub fn load_data(config: &DatasetConfig) -> Result<Vec<HotNotDogsData>, DatasetError> {
+    load_split(config, &config.split)
+}
+
+/// Holds out `fraction` of each class for validation, returns `(train, validation)`.
+///
+/// Per-class hold-out keeps the hot dog ratio the same in both halves. Which images
+/// are held out only depends on the seed, not on the order of `samples`.
+pub fn split_validation(
+    samples: &[HotNotDogsData],
+    fraction: f32,
+    seed: u64,
+) -> (Vec<HotNotDogsData>, Vec<HotNotDogsData>) {
+    let mut train = Vec::new();
+    let mut validation = Vec::new();
+    let mut rng = stream_rng(seed, RngStream::Split);
+
+    for label in [true, false] {
+        let mut class: Vec<HotNotDogsData> = samples
+            .iter()
+            .filter(|sample| sample.label == label)
+            .cloned()
+            .collect();
+        class.sort_by(|a, b| a.image_path.cmp(&b.image_path));
+        class.shuffle(&mut rng);
+
+        let held_out = (class.len() as f32 * fraction.clamp(0.0, 1.0)).round() as usize;
+        let rest = class.split_off(held_out);
+        validation.extend(class);
+        train.extend(rest);
+    }
+
+    train.shuffle(&mut rng);
+    validation.shuffle(&mut rng);
+    (train, validation)
+}
+
+/// Partitions `samples` into `k` folds with the same hot dog ratio.
+///
+/// Each class is shuffled with the seed and dealt round-robin, so fold sizes differ
+/// by at most one image per class.
+pub fn stratified_folds(samples: &[HotNotDogsData], k: usize, seed: u64) -> Vec<Vec<HotNotDogsData>> {
+    let k = k.max(1);
+    let mut folds = vec![Vec::new(); k];
+    let mut rng = stream_rng(seed, RngStream::Split);
+    let mut next = 0;
+
+    for label in [true, false] {
+        let mut class: Vec<HotNotDogsData> = samples
+            .iter()
+            .filter(|sample| sample.label == label)
+            .cloned()
+            .collect();
+        class.sort_by(|a, b| a.image_path.cmp(&b.image_path));
+        class.shuffle(&mut rng);
+
+        // Continue dealing where the previous class stopped so the extra images of
+        // uneven classes do not all land in the first folds.
+        for sample in class {
+            folds[next % k].push(sample);
+            next += 1;
+        }
+    }
+
+    for fold in &mut folds {
+        fold.shuffle(&mut rng);
+    }
+    folds
+}
+
+fn collect_images(
+    config: &DatasetConfig,
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), DatasetError> {
+    if !dir.is_dir() {
+        return Err(DatasetError::MissingDirectory(dir.to_path_buf()));
+    }
+
+    let entries = std::fs::read_dir(dir).map_err(|source| DatasetError::Unreadable {
+        path: dir.to_path_buf(),
+        reason: source.to_string(),
+    })?;
+
+    for entry in entries {
+        let path = entry
+            .map_err(|source| DatasetError::Unreadable {
+                path: dir.to_path_buf(),
+                reason: source.to_string(),
+            })?
+            .path();
+
+        if path.is_dir() {
+            if config.recursive {
+                collect_images(config, &path, files)?;
+            }
+        } else if config.accepts(&path) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,3 @@
+pub mod audit;
+pub mod config;
+pub mod dataset;
@@ -0,0 +1,214 @@
+//! Confusion matrices, the metrics derived from them (hot dog being the positive class),
+//! the accuracy/latency comparison of test-time augmentation settings and the
+//! calibration of the out-of-distribution threshold.
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use burn::tensor::{
+    backend::{AutodiffBackend, Backend},
+    Data, Tensor,
+};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+use crate::data::dataset::HotNotDogsData;
+use crate::model::ood::{OodDetector, OodMethod};
+use crate::model::squeezed_classifier::{decode_image, HotNotDogClassifier};
+use crate::model::tta::TestTimeAugmentation;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfusionMatrix {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub true_negatives: usize,
+    pub false_negatives: usize,
+}
+
+impl ConfusionMatrix {
+    pub fn record(&mut self, is_hot_dog: bool, predicted_hot_dog: bool) {
+        match (is_hot_dog, predicted_hot_dog) {
+            (true, true) => self.true_positives += 1,
+            (false, true) => self.false_positives += 1,
+            (false, false) => self.true_negatives += 1,
+            (true, false) => self.false_negatives += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.true_positives + self.false_positives + self.true_negatives + self.false_negatives
+    }
+
+    pub fn accuracy(&self) -> f32 {
+        ratio(self.true_positives + self.true_negatives, self.total())
+    }
+
+    pub fn precision(&self) -> f32 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_positives,
+        )
+    }
+
+    pub fn recall(&self) -> f32 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_negatives,
+        )
+    }
+
+    pub fn f1(&self) -> f32 {
+        let (precision, recall) = (self.precision(), self.recall());
+        if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        }
+    }
+}
+
+impl std::ops::AddAssign for ConfusionMatrix {
+    fn add_assign(&mut self, other: Self) {
+        self.true_positives += other.true_positives;
+        self.false_positives += other.false_positives;
+        self.true_negatives += other.true_negatives;
+        self.false_negatives += other.false_negatives;
+    }
+}
+
+impl fmt::Display for ConfusionMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<16} {:>10} {:>10}", "", "pred hot", "pred not")?;
+        writeln!(
+            f,
+            "{:<16} {:>10} {:>10}",
+            "actual hot", self.true_positives, self.false_negatives
+        )?;
+        write!(
+            f,
+            "{:<16} {:>10} {:>10}",
+            "actual not", self.false_positives, self.true_negatives
+        )
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f32 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f32 / denominator as f32
+    }
+}
+
+/// Runs `model` in inference mode over `samples` and tallies its predictions.
+pub fn confusion_matrix<B: AutodiffBackend>(
+    model: &HotNotDogClassifier<B>,
+    samples: &[HotNotDogsData],
+    batch_size: usize,
+) -> ConfusionMatrix
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    let mut matrix = ConfusionMatrix::default();
+    for batch in samples.chunks(batch_size.max(1)) {
+        let images = batch
+            .iter()
+            .map(|sample| model.load_image(&sample.image_path))
+            .collect();
+        let probabilities = model.predict_probabilities(Tensor::cat(images, 0));
+        for (sample, probabilities) in batch.iter().zip(probabilities) {
+            // Index 1 is HotDog, see `LABELS_DOG`.
+            matrix.record(sample.label, probabilities[1] > probabilities[0]);
+        }
+    }
+    matrix
+}
+
+/// Accuracy and latency of one test-time augmentation setting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtaReport {
+    pub name: String,
+    pub views: usize,
+    pub confusion: ConfusionMatrix,
+    /// Mean time from decoded image to averaged probabilities.
+    pub mean_latency_ms: f64,
+}
+
+impl fmt::Display for TtaReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<10} {:>3} views  accuracy {:>5.1}%  f1 {:.3}  {:>8.2} ms/image",
+            self.name,
+            self.views,
+            self.confusion.accuracy() * 100.0,
+            self.confusion.f1(),
+            self.mean_latency_ms
+        )
+    }
+}
+
+/// Evaluates `model` on `samples` once per augmentation setting.
+///
+/// Decoding is left out of the latency so the numbers only differ by the views.
+pub fn compare_tta<B: AutodiffBackend>(
+    model: &HotNotDogClassifier<B>,
+    samples: &[HotNotDogsData],
+    settings: &[(&str, TestTimeAugmentation)],
+) -> Vec<TtaReport>
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    let images: Vec<DynamicImage> = samples
+        .iter()
+        .map(|sample| decode_image(&sample.image_path))
+        .collect();
+
+    settings
+        .iter()
+        .map(|(name, tta)| {
+            let mut confusion = ConfusionMatrix::default();
+            let mut elapsed = Duration::ZERO;
+            for (sample, image) in samples.iter().zip(&images) {
+                let start = Instant::now();
+                let probabilities = model.predict_tta(image, tta);
+                elapsed += start.elapsed();
+                confusion.record(sample.label, probabilities[1] > probabilities[0]);
+            }
+
+            TtaReport {
+                name: name.to_string(),
+                views: tta.view_count(),
+                confusion,
+                mean_latency_ms: elapsed.as_secs_f64() * 1000.0 / samples.len().max(1) as f64,
+            }
+        })
+        .collect()
+}
+
+/// Learns the out-of-distribution threshold that keeps `coverage` of `samples`,
+/// normally the training images. `None` if `samples` is empty.
+pub fn calibrate_ood<B: AutodiffBackend>(
+    model: &HotNotDogClassifier<B>,
+    samples: &[HotNotDogsData],
+    method: OodMethod,
+    coverage: f32,
+    batch_size: usize,
+) -> Option<OodDetector>
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    let mut scores = Vec::with_capacity(samples.len());
+    for batch in samples.chunks(batch_size.max(1)) {
+        let images = batch
+            .iter()
+            .map(|sample| model.load_image(&sample.image_path))
+            .collect();
+        scores.extend(
+            model
+                .predict_logits(Tensor::cat(images, 0))
+                .iter()
+                .map(|logits| method.score(logits)),
+        );
+    }
+    OodDetector::from_scores(method, scores, coverage)
+}
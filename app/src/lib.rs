@@ -1 +1,9 @@
+pub mod cross_validation;
+pub mod data;
+pub mod evaluation;
 pub mod model;
+pub mod profiling;
+pub mod seed;
+pub mod server;
+pub mod sweep;
+pub mod training;
@@ -1,11 +1,16 @@
 use eframe::egui;
 use eframe::run_native;
 
+use hotnotdog::data::config::DatasetConfig;
+
 mod app;
 
 use app::HotNotDogApp;
 
 fn main() {
+    // Errors are shown in the window instead of aborting, so the user can see what is wrong.
+    let config = DatasetConfig::from_args(std::env::args().skip(1));
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([600.0, 800.0]),
         ..Default::default()
@@ -16,7 +21,7 @@ fn main() {
         Box::new(|cc| {
             // Add the egui_extras crate as a dependency
             egui_extras::install_image_loaders(&cc.egui_ctx);
-            Box::new(HotNotDogApp::new(cc))
+            Box::new(HotNotDogApp::new(cc, config))
         }),
     ); // Added closing parenthesis here
 }
@@ -1001,4 +1001,8 @@ pub static LABELS: &[&str] = &[
     "toilet tissue",
 ];
 
-pub static LABELS_DOG: &[&str] = &["HotDog", "NotHotDog"];
+// Indexed by the class the classifier head outputs; training uses 1 for hot dogs.
+pub static LABELS_DOG: &[&str] = &["NotHotDog", "HotDog"];
+
+/// Reported instead of a class when the image looks unlike the training data.
+pub static UNSURE_LABEL: &str = "Unsure";
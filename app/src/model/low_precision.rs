@@ -0,0 +1,427 @@
+//! CPU inference that keeps the classifier's weights in their storage precision.
+//!
+//! burn's ndarray backend only computes in f32, so an f16 or int8 checkpoint loaded
+//! into a [`Classifier`] runs exactly as fast as the f32 one. [`LowPrecisionClassifier`]
+//! instead runs the SqueezeNet graph with its own im2col and dot product kernels:
+//!
+//! - f32 keeps the weights as they are, the baseline for the other two.
+//! - f16 keeps the weights in half precision and widens one output channel at a time
+//!   to f32 for the arithmetic, since CPUs have no f16 arithmetic to speak of. It
+//!   halves the weight memory, not the work.
+//! - int8 also quantizes the input of every layer to int8, with one scale per layer
+//!   picked from the largest activation, and accumulates the products in i32.
+//!
+//! All three share the same loops, so timing them against each other measures the
+//! precision and nothing else. Biases stay in f32.
+use std::path::Path;
+
+use burn::module::{Module, ModuleVisitor, ParamId};
+use burn::tensor::{backend::Backend, Tensor};
+use half::f16;
+
+use crate::model::quantization::{load_classifier, Int8Weights, Precision, QuantizationError};
+use crate::model::squeezenet::Classifier;
+
+// 26 convolutions and the linear head.
+const LAYER_COUNT: usize = 27;
+
+enum Weights {
+    Full(Vec<f32>),
+    Half(Vec<f16>),
+    Int8 { values: Vec<i8>, scale: f32 },
+}
+
+/// A convolution, or the linear head as a 1x1 convolution over a single pixel.
+struct Layer {
+    weights: Weights,
+    bias: Vec<f32>,
+    outputs: usize,
+    /// Inputs per output channel: input channels times kernel height times width.
+    inputs: usize,
+    kernel: [usize; 2],
+}
+
+/// One image in channel, row, column order.
+struct FeatureMap {
+    channels: usize,
+    height: usize,
+    width: usize,
+    values: Vec<f32>,
+}
+
+/// The SqueezeNet classifier with its weights in f32, f16 or int8, run on the CPU.
+pub struct LowPrecisionClassifier {
+    precision: Precision,
+    layers: Vec<Layer>,
+}
+
+impl LowPrecisionClassifier {
+    /// Loads a file written by [`export_classifier`](crate::model::quantization::export_classifier).
+    /// int8 files are used as stored, f32 and f16 ones through their burn record.
+    pub fn load<B: Backend>(path: &Path, precision: Precision) -> Result<Self, QuantizationError> {
+        match precision {
+            Precision::Int8 => {
+                let weights = Int8Weights::load(path)?;
+                // Rejects files whose tensors do not fit the classifier.
+                weights.dequantize_into(Classifier::<B>::new())?;
+                Ok(Self::from_int8(&weights))
+            }
+            precision => Ok(Self::new(
+                &load_classifier::<B>(path, precision)?,
+                precision,
+            )),
+        }
+    }
+
+    /// Copies the weights of `model`, rounded to f16 for [`Precision::Half`] and
+    /// quantized without clipping for [`Precision::Int8`].
+    pub fn new<B: Backend>(model: &Classifier<B>, precision: Precision) -> Self {
+        if precision == Precision::Int8 {
+            return Self::from_int8(&Int8Weights::quantize(model, 1.0));
+        }
+
+        let mut collector = ParamCollector { params: Vec::new() };
+        model.visit(&mut collector);
+        let layers = collector
+            .params
+            .chunks(2)
+            .map(|pair| {
+                let (shape, weights) = &pair[0];
+                let weights = match precision {
+                    Precision::Half => {
+                        Weights::Half(weights.iter().map(|&value| f16::from_f32(value)).collect())
+                    }
+                    _ => Weights::Full(weights.clone()),
+                };
+                Layer::new(shape, weights, pair[1].1.clone())
+            })
+            .collect();
+
+        Self { precision, layers }
+    }
+
+    /// Takes the int8 weights as they are, without going back through f32. They must
+    /// fit the classifier.
+    fn from_int8(weights: &Int8Weights) -> Self {
+        let layers = weights
+            .tensors
+            .chunks(2)
+            .map(|pair| {
+                let (weight, bias) = (&pair[0], &pair[1]);
+                let bias = bias
+                    .values
+                    .iter()
+                    .map(|&value| value as f32 * bias.scale)
+                    .collect();
+                Layer::new(
+                    &weight.shape,
+                    Weights::Int8 {
+                        values: weight.values.clone(),
+                        scale: weight.scale,
+                    },
+                    bias,
+                )
+            })
+            .collect();
+
+        Self {
+            precision: Precision::Int8,
+            layers,
+        }
+    }
+
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    /// Logits for each image of a normalized `[batch, 3, 224, 224]` batch, like
+    /// [`Classifier::forward`].
+    pub fn forward<B: Backend>(&self, input: Tensor<B, 4>) -> Vec<Vec<f32>> {
+        let [batch, channels, height, width] = input.dims();
+        let values = input.into_data().convert::<f32>().value;
+        let size = channels * height * width;
+
+        (0..batch)
+            .map(|index| {
+                let image = FeatureMap {
+                    channels,
+                    height,
+                    width,
+                    values: values[index * size..(index + 1) * size].to_vec(),
+                };
+                self.forward_image(image)
+            })
+            .collect()
+    }
+
+    /// The same graph as [`Classifier::forward`]; dropout is the identity at inference.
+    fn forward_image(&self, image: FeatureMap) -> Vec<f32> {
+        let mut current = self.conv(&image, 0, 2, 0);
+        current = max_pool(&current);
+        current = self.fire(&current, 1);
+        current = self.fire(&current, 4);
+        current = max_pool(&current);
+        current = self.fire(&current, 7);
+        current = self.fire(&current, 10);
+        current = max_pool(&current);
+        current = self.fire(&current, 13);
+        current = self.fire(&current, 16);
+        current = self.fire(&current, 19);
+        current = self.fire(&current, 22);
+        current = self.conv(&current, 25, 1, 0);
+        let pooled = average_pool(&current);
+
+        let head = &self.layers[LAYER_COUNT - 1];
+        let flat = FeatureMap {
+            channels: pooled.values.len(),
+            height: 1,
+            width: 1,
+            values: pooled.values,
+        };
+        head.apply(&patches(&flat, [1, 1], 1, 0), 1, false)
+    }
+
+    /// A fire module: squeeze 1x1, then concatenated 1x1 and 3x3 expands.
+    fn fire(&self, input: &FeatureMap, squeeze: usize) -> FeatureMap {
+        let squeezed = self.conv(input, squeeze, 1, 0);
+        let expand_1x1 = self.conv(&squeezed, squeeze + 1, 1, 0);
+        let expand_3x3 = self.conv(&squeezed, squeeze + 2, 1, 1);
+
+        let mut values = expand_1x1.values;
+        values.extend(expand_3x3.values);
+        FeatureMap {
+            channels: expand_1x1.channels + expand_3x3.channels,
+            height: squeezed.height,
+            width: squeezed.width,
+            values,
+        }
+    }
+
+    /// Convolution `layer`, followed by a ReLU like every convolution of the graph.
+    fn conv(&self, input: &FeatureMap, layer: usize, stride: usize, padding: usize) -> FeatureMap {
+        let layer = &self.layers[layer];
+        let [kernel_height, kernel_width] = layer.kernel;
+        let height = (input.height + 2 * padding - kernel_height) / stride + 1;
+        let width = (input.width + 2 * padding - kernel_width) / stride + 1;
+
+        let patches = patches(input, layer.kernel, stride, padding);
+        FeatureMap {
+            channels: layer.outputs,
+            height,
+            width,
+            values: layer.apply(&patches, height * width, true),
+        }
+    }
+}
+
+impl Layer {
+    fn new(shape: &[usize], weights: Weights, bias: Vec<f32>) -> Self {
+        match *shape {
+            // Convolutions are [out, in, height, width].
+            [outputs, channels, kernel_height, kernel_width] => Self {
+                weights,
+                bias,
+                outputs,
+                inputs: channels * kernel_height * kernel_width,
+                kernel: [kernel_height, kernel_width],
+            },
+            // The linear weight is [in, out], the other way round from a convolution.
+            [inputs, outputs] => Self {
+                weights: weights.transposed(inputs, outputs),
+                bias,
+                outputs,
+                inputs,
+                kernel: [1, 1],
+            },
+            _ => unreachable!("the classifier only has convolutions and a linear head"),
+        }
+    }
+
+    /// `[outputs, pixels]` from `[pixels, inputs]` patches.
+    fn apply(&self, patches: &[f32], pixels: usize, relu: bool) -> Vec<f32> {
+        let mut output = vec![0.0; self.outputs * pixels];
+        let mut row = vec![0.0; self.inputs];
+
+        match &self.weights {
+            Weights::Full(weights) => {
+                for (channel, out) in output.chunks_mut(pixels).enumerate() {
+                    let weights = &weights[channel * self.inputs..(channel + 1) * self.inputs];
+                    for (pixel, value) in out.iter_mut().enumerate() {
+                        let patch = &patches[pixel * self.inputs..(pixel + 1) * self.inputs];
+                        *value = dot(weights, patch) + self.bias[channel];
+                    }
+                }
+            }
+            Weights::Half(weights) => {
+                for (channel, out) in output.chunks_mut(pixels).enumerate() {
+                    let weights = &weights[channel * self.inputs..(channel + 1) * self.inputs];
+                    for (widened, weight) in row.iter_mut().zip(weights) {
+                        *widened = weight.to_f32();
+                    }
+                    for (pixel, value) in out.iter_mut().enumerate() {
+                        let patch = &patches[pixel * self.inputs..(pixel + 1) * self.inputs];
+                        *value = dot(&row, patch) + self.bias[channel];
+                    }
+                }
+            }
+            Weights::Int8 { values, scale } => {
+                let (patches, input_scale) = quantize(patches);
+                let scale = scale * input_scale;
+                for (channel, out) in output.chunks_mut(pixels).enumerate() {
+                    let weights = &values[channel * self.inputs..(channel + 1) * self.inputs];
+                    for (pixel, value) in out.iter_mut().enumerate() {
+                        let patch = &patches[pixel * self.inputs..(pixel + 1) * self.inputs];
+                        *value = dot_i8(weights, patch) as f32 * scale + self.bias[channel];
+                    }
+                }
+            }
+        }
+
+        if relu {
+            output.iter_mut().for_each(|value| *value = value.max(0.0));
+        }
+        output
+    }
+}
+
+impl Weights {
+    fn transposed(self, rows: usize, columns: usize) -> Self {
+        fn transpose<T: Copy>(values: &[T], rows: usize, columns: usize) -> Vec<T> {
+            (0..columns)
+                .flat_map(|column| (0..rows).map(move |row| values[row * columns + column]))
+                .collect()
+        }
+
+        match self {
+            Weights::Full(values) => Weights::Full(transpose(&values, rows, columns)),
+            Weights::Half(values) => Weights::Half(transpose(&values, rows, columns)),
+            Weights::Int8 { values, scale } => Weights::Int8 {
+                values: transpose(&values, rows, columns),
+                scale,
+            },
+        }
+    }
+}
+
+/// im2col: one row per output pixel holding every input the kernel covers there, in
+/// the weights' channel, row, column order. Padding reads as zero.
+fn patches(input: &FeatureMap, kernel: [usize; 2], stride: usize, padding: usize) -> Vec<f32> {
+    let [kernel_height, kernel_width] = kernel;
+    let height = (input.height + 2 * padding - kernel_height) / stride + 1;
+    let width = (input.width + 2 * padding - kernel_width) / stride + 1;
+
+    let mut patches =
+        Vec::with_capacity(height * width * input.channels * kernel_height * kernel_width);
+    for y in 0..height {
+        for x in 0..width {
+            for channel in 0..input.channels {
+                let plane = &input.values[channel * input.height * input.width..];
+                for ky in 0..kernel_height {
+                    for kx in 0..kernel_width {
+                        let (row, column) = (y * stride + ky, x * stride + kx);
+                        let inside = (padding..input.height + padding).contains(&row)
+                            && (padding..input.width + padding).contains(&column);
+                        patches.push(if inside {
+                            plane[(row - padding) * input.width + column - padding]
+                        } else {
+                            0.0
+                        });
+                    }
+                }
+            }
+        }
+    }
+    patches
+}
+
+/// 3x3 max pooling with stride 2 and no padding.
+fn max_pool(input: &FeatureMap) -> FeatureMap {
+    let height = (input.height - 3) / 2 + 1;
+    let width = (input.width - 3) / 2 + 1;
+
+    let mut values = Vec::with_capacity(input.channels * height * width);
+    for plane in input.values.chunks(input.height * input.width) {
+        for y in 0..height {
+            for x in 0..width {
+                let mut max = f32::NEG_INFINITY;
+                for row in 2 * y..2 * y + 3 {
+                    for column in 2 * x..2 * x + 3 {
+                        max = max.max(plane[row * input.width + column]);
+                    }
+                }
+                values.push(max);
+            }
+        }
+    }
+
+    FeatureMap {
+        channels: input.channels,
+        height,
+        width,
+        values,
+    }
+}
+
+/// The 13x13 average pool at the end of the trunk, which covers the whole 13x13 map.
+fn average_pool(input: &FeatureMap) -> FeatureMap {
+    let area = (input.height * input.width) as f32;
+    FeatureMap {
+        channels: input.channels,
+        height: 1,
+        width: 1,
+        values: input
+            .values
+            .chunks(input.height * input.width)
+            .map(|plane| plane.iter().sum::<f32>() / area)
+            .collect(),
+    }
+}
+
+/// Symmetric int8 with one scale for all values.
+fn quantize(values: &[f32]) -> (Vec<i8>, f32) {
+    let max_abs = values
+        .iter()
+        .fold(0.0f32, |max, value| max.max(value.abs()));
+    let scale = if max_abs == 0.0 { 1.0 } else { max_abs / 127.0 };
+    let quantized = values
+        .iter()
+        .map(|value| (value / scale).round().clamp(-127.0, 127.0) as i8)
+        .collect();
+    (quantized, scale)
+}
+
+// Eight running sums so the compiler can keep them in one vector register; a single
+// sum has to be added up in order.
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    let (a_chunks, b_chunks) = (a.chunks_exact(8), b.chunks_exact(8));
+    let tail: f32 = a_chunks
+        .remainder()
+        .iter()
+        .zip(b_chunks.remainder())
+        .map(|(a, b)| a * b)
+        .sum();
+
+    let mut sums = [0.0f32; 8];
+    for (a, b) in a_chunks.zip(b_chunks) {
+        for ((sum, a), b) in sums.iter_mut().zip(a).zip(b) {
+            *sum += a * b;
+        }
+    }
+    sums.iter().sum::<f32>() + tail
+}
+
+// At most 576 products of 127 * 127 per output, far from overflowing.
+fn dot_i8(a: &[i8], b: &[i8]) -> i32 {
+    a.iter().zip(b).map(|(&a, &b)| a as i32 * b as i32).sum()
+}
+
+struct ParamCollector {
+    params: Vec<(Vec<usize>, Vec<f32>)>,
+}
+
+impl<B: Backend> ModuleVisitor<B> for ParamCollector {
+    fn visit<const D: usize>(&mut self, _id: &ParamId, tensor: &Tensor<B, D>) {
+        let data = tensor.to_data().convert::<f32>();
+        self.params.push((data.shape.dims.to_vec(), data.value));
+    }
+}
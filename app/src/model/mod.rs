@@ -1,4 +1,10 @@
 pub mod label;
+pub mod low_precision;
 pub mod normalizer;
+pub mod ood;
+pub mod onnx;
+pub mod preprocessing;
+pub mod quantization;
 pub mod squeezed_classifier;
 pub mod squeezenet;
+pub mod tta;
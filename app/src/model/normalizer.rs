@@ -2,8 +2,8 @@ use burn::tensor::{backend::Backend, Tensor};
 
 // Values are taken from the [ONNX SqueezeNet]
 // (https://github.com/onnx/models/tree/main/vision/classification/squeezenet#preprocessing)
-const MEAN: [f32; 3] = [0.485, 0.456, 0.406];
-const STD: [f32; 3] = [0.229, 0.224, 0.225];
+pub const MEAN: [f32; 3] = [0.485, 0.456, 0.406];
+pub const STD: [f32; 3] = [0.229, 0.224, 0.225];
 
 /// Normalizer for the imagenet dataset.
 pub struct Normalizer<B: Backend> {
@@ -0,0 +1,573 @@
+//! Writes the fine-tuned [`Classifier`] as an ONNX graph and reads such a graph back.
+//!
+//! Only the subset of the ONNX protobuf schema needed for SqueezeNet is encoded by hand,
+//! which keeps the exporter free of a protobuf toolchain. The importer only accepts the
+//! exact graph the exporter writes; whether that graph means what the classifier computes
+//! is checked against an independent ONNX runtime in `tests/onnx_round_trip.rs`.
+use std::fmt;
+use std::path::Path;
+
+use burn::{
+    module::{Module, ModuleMapper, ModuleVisitor, ParamId},
+    tensor::{backend::Backend, Data, Shape, Tensor},
+};
+
+use crate::model::normalizer::{MEAN, STD};
+use crate::model::squeezenet::Classifier;
+
+const IR_VERSION: i64 = 8;
+const OPSET_VERSION: i64 = 13;
+const FLOAT: i64 = 1;
+
+// Attribute types from onnx.proto.
+const ATTRIBUTE_INT: i64 = 2;
+const ATTRIBUTE_INTS: i64 = 7;
+
+const INPUT_NAME: &str = "input";
+const OUTPUT_NAME: &str = "logits";
+
+#[derive(Debug)]
+pub enum OnnxError {
+    Io(std::io::Error),
+    /// The file is not a graph this module can read back into a [`Classifier`].
+    Format(String),
+}
+
+impl fmt::Display for OnnxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OnnxError::Io(error) => write!(f, "I/O error: {error}"),
+            OnnxError::Format(reason) => write!(f, "Invalid ONNX model: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for OnnxError {}
+
+impl From<std::io::Error> for OnnxError {
+    fn from(error: std::io::Error) -> Self {
+        OnnxError::Io(error)
+    }
+}
+
+/// Options for [`export_onnx`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OnnxExportConfig {
+    /// Prepend `(input - mean) / std` so the graph takes raw `[0, 1]` images.
+    pub bake_normalization: bool,
+}
+
+/// A classifier read back from an exported graph.
+pub struct ImportedClassifier<B: Backend> {
+    pub model: Classifier<B>,
+    /// Whether the graph normalizes its input itself.
+    pub baked_normalization: bool,
+}
+
+/// Writes `model` to `path` as an ONNX graph with a dynamic batch dimension.
+pub fn export_onnx<B: Backend>(
+    model: &Classifier<B>,
+    path: &Path,
+    config: OnnxExportConfig,
+) -> Result<(), OnnxError> {
+    let params = collect_params(model);
+    if params.len() != PARAM_COUNT {
+        return Err(OnnxError::Format(format!(
+            "expected {PARAM_COUNT} parameters, classifier has {}",
+            params.len()
+        )));
+    }
+
+    let mut graph = GraphBuilder::default();
+    for (index, (dims, values)) in params.into_iter().enumerate() {
+        graph.initializer(&param_name(index), &dims, &values);
+    }
+
+    if config.bake_normalization {
+        graph.initializer("normalizer.mean", &[1, 3, 1, 1], &MEAN);
+        graph.initializer("normalizer.std", &[1, 3, 1, 1], &STD);
+    }
+    graph.classifier(config.bake_normalization);
+
+    std::fs::write(path, graph.into_model())?;
+    Ok(())
+}
+
+/// Reads a graph written by [`export_onnx`] back into a [`Classifier`].
+///
+/// Fails if the nodes differ from the ones [`export_onnx`] writes.
+pub fn import_onnx<B: Backend>(path: &Path) -> Result<ImportedClassifier<B>, OnnxError> {
+    let bytes = std::fs::read(path)?;
+    let model = decode::Message::parse(&bytes)?;
+    let graph = model
+        .message(7)?
+        .ok_or_else(|| OnnxError::Format("missing graph".to_string()))?;
+
+    let mut initializers = Vec::new();
+    for tensor in graph.messages(5)? {
+        let name = tensor.string(8)?.unwrap_or_default();
+        let dims: Vec<usize> = tensor.varints(1).into_iter().map(|dim| dim as usize).collect();
+        let raw = tensor.bytes(9).unwrap_or_default();
+        let values: Vec<f32> = raw
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        initializers.push((name, dims, values));
+    }
+
+    let nodes = graph.repeated_bytes(1);
+    let baked_normalization = match nodes.first() {
+        Some(node) => decode::Message::parse(node)?.string(4)?.as_deref() == Some("Sub"),
+        None => false,
+    };
+    let mut expected = GraphBuilder::default();
+    expected.classifier(baked_normalization);
+    if nodes.len() != expected.nodes.len() {
+        return Err(OnnxError::Format(format!(
+            "graph has {} nodes, the classifier has {}",
+            nodes.len(),
+            expected.nodes.len()
+        )));
+    }
+    if let Some(index) = nodes
+        .iter()
+        .zip(&expected.nodes)
+        .position(|(node, expected)| *node != expected.as_slice())
+    {
+        return Err(OnnxError::Format(format!(
+            "node {index} differs from the classifier graph"
+        )));
+    }
+
+    let mut loader = OnnxLoader {
+        initializers: &initializers,
+        next: 0,
+        error: None,
+    };
+    let classifier = Classifier::<B>::new().map(&mut loader);
+    if let Some(error) = loader.error {
+        return Err(OnnxError::Format(error));
+    }
+
+    Ok(ImportedClassifier {
+        model: classifier,
+        baked_normalization,
+    })
+}
+
+// 26 convolutions and the linear head, each with a weight and a bias.
+const PARAM_COUNT: usize = 54;
+
+/// Name of the `index`-th parameter in the order the `Module` derive visits them.
+fn param_name(index: usize) -> String {
+    let kind = if index % 2 == 0 { "weight" } else { "bias" };
+    match index / 2 {
+        26 => format!("linear.{kind}"),
+        layer => format!("conv2d{}.{kind}", layer + 1),
+    }
+}
+
+struct ParamCollector {
+    params: Vec<(Vec<usize>, Vec<f32>)>,
+}
+
+impl<B: Backend> ModuleVisitor<B> for ParamCollector {
+    fn visit<const D: usize>(&mut self, _id: &ParamId, tensor: &Tensor<B, D>) {
+        let data = tensor.to_data().convert::<f32>();
+        self.params.push((data.shape.dims.to_vec(), data.value));
+    }
+}
+
+fn collect_params<B: Backend>(model: &Classifier<B>) -> Vec<(Vec<usize>, Vec<f32>)> {
+    let mut collector = ParamCollector { params: Vec::new() };
+    model.visit(&mut collector);
+    collector.params
+}
+
+struct OnnxLoader<'a> {
+    initializers: &'a [(String, Vec<usize>, Vec<f32>)],
+    next: usize,
+    error: Option<String>,
+}
+
+impl<'a, B: Backend> ModuleMapper<B> for OnnxLoader<'a> {
+    fn map<const D: usize>(&mut self, _id: &ParamId, tensor: Tensor<B, D>) -> Tensor<B, D> {
+        let name = param_name(self.next);
+        self.next += 1;
+
+        let Some((_, dims, values)) = self.initializers.iter().find(|(n, _, _)| *n == name) else {
+            self.error.get_or_insert_with(|| format!("missing initializer {name}"));
+            return tensor;
+        };
+        if *dims != tensor.dims() || values.len() != dims.iter().product::<usize>() {
+            self.error.get_or_insert_with(|| {
+                format!("{name} has shape {dims:?}, expected {:?}", tensor.dims())
+            });
+            return tensor;
+        }
+
+        let mut shape = [0; D];
+        shape.copy_from_slice(dims);
+        let data = Data::new(values.clone(), Shape::new(shape)).convert();
+        Tensor::from_data_device(data, &tensor.device())
+    }
+}
+
+struct Attribute {
+    name: &'static str,
+    values: Vec<i64>,
+    repeated: bool,
+}
+
+fn int(name: &'static str, value: i64) -> Attribute {
+    Attribute {
+        name,
+        values: vec![value],
+        repeated: false,
+    }
+}
+
+fn ints(name: &'static str, values: &[i64]) -> Attribute {
+    Attribute {
+        name,
+        values: values.to_vec(),
+        repeated: true,
+    }
+}
+
+#[derive(Default)]
+struct GraphBuilder {
+    nodes: Vec<Vec<u8>>,
+    initializers: Vec<Vec<u8>>,
+    counter: usize,
+}
+
+impl GraphBuilder {
+    fn initializer(&mut self, name: &str, dims: &[usize], values: &[f32]) {
+        let mut tensor = encode::Writer::default();
+        for dim in dims {
+            tensor.varint(1, *dim as u64);
+        }
+        tensor.varint(2, FLOAT as u64);
+        tensor.string(8, name);
+        let raw: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+        tensor.bytes(9, &raw);
+        self.initializers.push(tensor.finish());
+    }
+
+    /// Adds the SqueezeNet nodes from [`INPUT_NAME`] to [`OUTPUT_NAME`], optionally
+    /// starting with `(input - mean) / std`.
+    fn classifier(&mut self, bake_normalization: bool) {
+        let mut current = INPUT_NAME.to_string();
+        if bake_normalization {
+            current = self.node("Sub", &[&current, "normalizer.mean"], &[]);
+            current = self.node("Div", &[&current, "normalizer.std"], &[]);
+        }
+
+        current = self.conv(&current, 1, 2, 0);
+        current = self.node("Relu", &[&current], &[]);
+        current = self.max_pool(&current);
+        current = self.fire(&current, 2);
+        current = self.fire(&current, 5);
+        current = self.max_pool(&current);
+        current = self.fire(&current, 8);
+        current = self.fire(&current, 11);
+        current = self.max_pool(&current);
+        current = self.fire(&current, 14);
+        current = self.fire(&current, 17);
+        current = self.fire(&current, 20);
+        current = self.fire(&current, 23);
+        // Dropout is the identity at inference and is left out of the graph.
+        current = self.conv(&current, 26, 1, 0);
+        current = self.node("Relu", &[&current], &[]);
+        current = self.node(
+            "AveragePool",
+            &[&current],
+            &[ints("kernel_shape", &[13, 13]), ints("strides", &[13, 13])],
+        );
+        current = self.node("Flatten", &[&current], &[int("axis", 1)]);
+        self.named_node(
+            "Gemm",
+            &[&current, "linear.weight", "linear.bias"],
+            OUTPUT_NAME,
+            &[],
+        );
+    }
+
+    /// Adds a node with a generated output name and returns that name.
+    fn node(&mut self, op_type: &str, inputs: &[&str], attributes: &[Attribute]) -> String {
+        self.counter += 1;
+        let output = format!("{}_{}", op_type.to_lowercase(), self.counter);
+        self.named_node(op_type, inputs, &output, attributes);
+        output
+    }
+
+    fn named_node(&mut self, op_type: &str, inputs: &[&str], output: &str, attributes: &[Attribute]) {
+        let mut node = encode::Writer::default();
+        for input in inputs {
+            node.string(1, input);
+        }
+        node.string(2, output);
+        node.string(3, &format!("{output}_node"));
+        node.string(4, op_type);
+        for attribute in attributes {
+            let mut encoded = encode::Writer::default();
+            encoded.string(1, attribute.name);
+            if attribute.repeated {
+                encoded.varint(20, ATTRIBUTE_INTS as u64);
+                for value in &attribute.values {
+                    encoded.varint(8, *value as u64);
+                }
+            } else {
+                encoded.varint(20, ATTRIBUTE_INT as u64);
+                encoded.varint(3, attribute.values[0] as u64);
+            }
+            node.message(5, &encoded.finish());
+        }
+        self.nodes.push(node.finish());
+    }
+
+    fn conv(&mut self, input: &str, index: usize, stride: i64, padding: i64) -> String {
+        let weight = format!("conv2d{index}.weight");
+        let bias = format!("conv2d{index}.bias");
+        self.node(
+            "Conv",
+            &[input, &weight, &bias],
+            &[
+                ints("strides", &[stride, stride]),
+                ints("pads", &[padding, padding, padding, padding]),
+            ],
+        )
+    }
+
+    fn max_pool(&mut self, input: &str) -> String {
+        self.node(
+            "MaxPool",
+            &[input],
+            &[ints("kernel_shape", &[3, 3]), ints("strides", &[2, 2])],
+        )
+    }
+
+    /// A SqueezeNet fire module: squeeze 1x1, then concatenated 1x1 and 3x3 expands.
+    fn fire(&mut self, input: &str, squeeze: usize) -> String {
+        let squeezed = self.conv(input, squeeze, 1, 0);
+        let squeezed = self.node("Relu", &[&squeezed], &[]);
+        let expand_1x1 = self.conv(&squeezed, squeeze + 1, 1, 0);
+        let expand_1x1 = self.node("Relu", &[&expand_1x1], &[]);
+        let expand_3x3 = self.conv(&squeezed, squeeze + 2, 1, 1);
+        let expand_3x3 = self.node("Relu", &[&expand_3x3], &[]);
+        self.node("Concat", &[&expand_1x1, &expand_3x3], &[int("axis", 1)])
+    }
+
+    fn into_model(self) -> Vec<u8> {
+        let mut graph = encode::Writer::default();
+        for node in &self.nodes {
+            graph.message(1, node);
+        }
+        graph.string(2, "hotnotdog_classifier");
+        for initializer in &self.initializers {
+            graph.message(5, initializer);
+        }
+        graph.message(11, &value_info(INPUT_NAME, &[None, Some(3), Some(224), Some(224)]));
+        graph.message(12, &value_info(OUTPUT_NAME, &[None, Some(2)]));
+
+        let mut opset = encode::Writer::default();
+        opset.string(1, "");
+        opset.varint(2, OPSET_VERSION as u64);
+
+        let mut model = encode::Writer::default();
+        model.varint(1, IR_VERSION as u64);
+        model.string(2, "hotnotdog");
+        model.string(3, env!("CARGO_PKG_VERSION"));
+        model.message(7, &graph.finish());
+        model.message(8, &opset.finish());
+        model.finish()
+    }
+}
+
+/// `None` dimensions become the symbolic `batch` dimension.
+fn value_info(name: &str, dims: &[Option<i64>]) -> Vec<u8> {
+    let mut shape = encode::Writer::default();
+    for dim in dims {
+        let mut dimension = encode::Writer::default();
+        match dim {
+            Some(value) => dimension.varint(1, *value as u64),
+            None => dimension.string(2, "batch"),
+        }
+        shape.message(1, &dimension.finish());
+    }
+
+    let mut tensor_type = encode::Writer::default();
+    tensor_type.varint(1, FLOAT as u64);
+    tensor_type.message(2, &shape.finish());
+
+    let mut type_proto = encode::Writer::default();
+    type_proto.message(1, &tensor_type.finish());
+
+    let mut info = encode::Writer::default();
+    info.string(1, name);
+    info.message(2, &type_proto.finish());
+    info.finish()
+}
+
+mod encode {
+    const VARINT: u64 = 0;
+    const LENGTH_DELIMITED: u64 = 2;
+
+    #[derive(Default)]
+    pub struct Writer {
+        buffer: Vec<u8>,
+    }
+
+    impl Writer {
+        pub fn varint(&mut self, field: u64, value: u64) {
+            self.raw_varint(field << 3 | VARINT);
+            self.raw_varint(value);
+        }
+
+        pub fn bytes(&mut self, field: u64, value: &[u8]) {
+            self.raw_varint(field << 3 | LENGTH_DELIMITED);
+            self.raw_varint(value.len() as u64);
+            self.buffer.extend_from_slice(value);
+        }
+
+        pub fn string(&mut self, field: u64, value: &str) {
+            self.bytes(field, value.as_bytes());
+        }
+
+        pub fn message(&mut self, field: u64, value: &[u8]) {
+            self.bytes(field, value);
+        }
+
+        pub fn finish(self) -> Vec<u8> {
+            self.buffer
+        }
+
+        fn raw_varint(&mut self, mut value: u64) {
+            while value >= 0x80 {
+                self.buffer.push((value as u8) | 0x80);
+                value >>= 7;
+            }
+            self.buffer.push(value as u8);
+        }
+    }
+}
+
+mod decode {
+    use super::OnnxError;
+
+    enum Value<'a> {
+        Varint(u64),
+        Bytes(&'a [u8]),
+    }
+
+    /// A decoded protobuf message, kept as a flat list of fields.
+    pub struct Message<'a> {
+        fields: Vec<(u64, Value<'a>)>,
+    }
+
+    impl<'a> Message<'a> {
+        pub fn parse(mut bytes: &'a [u8]) -> Result<Self, OnnxError> {
+            let mut fields = Vec::new();
+            while !bytes.is_empty() {
+                let key = read_varint(&mut bytes)?;
+                let value = match key & 7 {
+                    0 => Value::Varint(read_varint(&mut bytes)?),
+                    1 => Value::Bytes(take(&mut bytes, 8)?),
+                    2 => {
+                        let length = read_varint(&mut bytes)? as usize;
+                        Value::Bytes(take(&mut bytes, length)?)
+                    }
+                    5 => Value::Bytes(take(&mut bytes, 4)?),
+                    wire_type => {
+                        return Err(OnnxError::Format(format!("unsupported wire type {wire_type}")))
+                    }
+                };
+                fields.push((key >> 3, value));
+            }
+            Ok(Self { fields })
+        }
+
+        /// All varints of a repeated field, accepting both packed and unpacked encoding.
+        pub fn varints(&self, field: u64) -> Vec<u64> {
+            let mut values = Vec::new();
+            for (number, value) in &self.fields {
+                if *number != field {
+                    continue;
+                }
+                match value {
+                    Value::Varint(value) => values.push(*value),
+                    Value::Bytes(packed) => {
+                        let mut packed = *packed;
+                        while let Ok(value) = read_varint(&mut packed) {
+                            values.push(value);
+                        }
+                    }
+                }
+            }
+            values
+        }
+
+        pub fn bytes(&self, field: u64) -> Option<&'a [u8]> {
+            self.fields.iter().rev().find_map(|(number, value)| match value {
+                Value::Bytes(bytes) if *number == field => Some(*bytes),
+                _ => None,
+            })
+        }
+
+        /// Every value of a repeated length-delimited field, undecoded.
+        pub fn repeated_bytes(&self, field: u64) -> Vec<&'a [u8]> {
+            self.fields
+                .iter()
+                .filter_map(|(number, value)| match value {
+                    Value::Bytes(bytes) if *number == field => Some(*bytes),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        pub fn string(&self, field: u64) -> Result<Option<String>, OnnxError> {
+            self.bytes(field)
+                .map(|bytes| {
+                    String::from_utf8(bytes.to_vec())
+                        .map_err(|_| OnnxError::Format(format!("field {field} is not UTF-8")))
+                })
+                .transpose()
+        }
+
+        pub fn message(&self, field: u64) -> Result<Option<Message<'a>>, OnnxError> {
+            self.bytes(field).map(Message::parse).transpose()
+        }
+
+        pub fn messages(&self, field: u64) -> Result<Vec<Message<'a>>, OnnxError> {
+            self.repeated_bytes(field)
+                .into_iter()
+                .map(Message::parse)
+                .collect()
+        }
+    }
+
+    fn read_varint(bytes: &mut &[u8]) -> Result<u64, OnnxError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = bytes
+                .split_first()
+                .ok_or_else(|| OnnxError::Format("truncated varint".to_string()))?;
+            *bytes = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(OnnxError::Format("varint too long".to_string()))
+    }
+
+    fn take<'a>(bytes: &mut &'a [u8], length: usize) -> Result<&'a [u8], OnnxError> {
+        if bytes.len() < length {
+            return Err(OnnxError::Format("truncated field".to_string()));
+        }
+        let (value, rest) = bytes.split_at(length);
+        *bytes = rest;
+        Ok(value)
+    }
+}
@@ -0,0 +1,83 @@
+//! Out-of-distribution detection, so photos that are neither hot dogs nor the usual
+//! not-hot-dog food come back as "unsure" instead of a confident wrong class.
+//!
+//! A score is computed from the classifier's logits, higher meaning more like the
+//! training images. The threshold is the score that `coverage` of the training set
+//! reaches, anything below it is reported as [`UNSURE_LABEL`](crate::model::label::UNSURE_LABEL).
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum OodMethod {
+    /// Largest softmax probability.
+    MaxSoftmax,
+    /// Negative free energy, `T * logsumexp(logits / T)`. Unlike the softmax it keeps
+    /// the magnitude of the logits, which is what separates unfamiliar images.
+    Energy { temperature: f32 },
+}
+
+impl Default for OodMethod {
+    fn default() -> Self {
+        OodMethod::Energy { temperature: 1.0 }
+    }
+}
+
+impl OodMethod {
+    pub fn score(&self, logits: &[f32]) -> f32 {
+        match *self {
+            OodMethod::MaxSoftmax => {
+                let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                let sum: f32 = logits.iter().map(|logit| (logit - max).exp()).sum();
+                1.0 / sum
+            }
+            OodMethod::Energy { temperature } => {
+                let scaled: Vec<f32> = logits.iter().map(|logit| logit / temperature).collect();
+                let max = scaled.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                let sum: f32 = scaled.iter().map(|logit| (logit - max).exp()).sum();
+                temperature * (max + sum.ln())
+            }
+        }
+    }
+}
+
+/// A scoring method together with the threshold learned for it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OodDetector {
+    pub method: OodMethod,
+    /// Scores below this are out of distribution.
+    pub threshold: f32,
+    /// Fraction of the calibration images that scored at or above the threshold.
+    pub coverage: f32,
+}
+
+impl OodDetector {
+    /// Picks the threshold that keeps `coverage` of the in-distribution `scores`.
+    ///
+    /// Returns `None` without scores.
+    pub fn from_scores(method: OodMethod, mut scores: Vec<f32>, coverage: f32) -> Option<Self> {
+        scores.retain(|score| score.is_finite());
+        if scores.is_empty() {
+            return None;
+        }
+        scores.sort_by(f32::total_cmp);
+
+        let coverage = coverage.clamp(0.0, 1.0);
+        let rejected = ((1.0 - coverage) * scores.len() as f32).floor() as usize;
+        let threshold = scores[rejected.min(scores.len() - 1)];
+        let kept = scores.iter().filter(|&&score| score >= threshold).count();
+
+        Some(Self {
+            method,
+            threshold,
+            coverage: kept as f32 / scores.len() as f32,
+        })
+    }
+
+    pub fn score(&self, logits: &[f32]) -> f32 {
+        self.method.score(logits)
+    }
+
+    pub fn is_unsure(&self, logits: &[f32]) -> bool {
+        self.score(logits) < self.threshold
+    }
+}
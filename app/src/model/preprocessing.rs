@@ -0,0 +1,140 @@
+//! How a decoded photo is turned into the 224x224 input of SqueezeNet.
+//!
+//! The settings travel with the classifier and its checkpoints, so an image is
+//! preprocessed the same way in training, evaluation, the server and the UI.
+use image::{imageops::FilterType, DynamicImage, GenericImageView, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+
+/// Side length of the model input.
+pub const INPUT_SIZE: u32 = 224;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ResizeMode {
+    /// Stretches the image to a square, distorting non-square photos.
+    Exact,
+    /// Resizes the shorter side to `resize`, then crops the center. The ImageNet
+    /// preprocessing SqueezeNet was trained with uses `resize = 256`.
+    CenterCrop { resize: u32 },
+    /// Fits the whole image inside the square and pads the rest with `fill`.
+    Letterbox { fill: [u8; 3] },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizeFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(filter: ResizeFilter) -> Self {
+        match filter {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Triangle => FilterType::Triangle,
+            ResizeFilter::CatmullRom => FilterType::CatmullRom,
+            ResizeFilter::Gaussian => FilterType::Gaussian,
+            ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+/// Resize settings, e.g. in `hotnotdog.toml`:
+///
+/// ```toml
+/// [preprocessing]
+/// filter = "triangle"
+/// resize = { mode = "center_crop", resize = 256 }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preprocessing {
+    pub resize: ResizeMode,
+    pub filter: ResizeFilter,
+}
+
+impl Default for Preprocessing {
+    /// The stretching Lanczos resize the classifier was first fine-tuned with.
+    fn default() -> Self {
+        Self {
+            resize: ResizeMode::Exact,
+            filter: ResizeFilter::Lanczos3,
+        }
+    }
+}
+
+impl Preprocessing {
+    /// The standard ImageNet "resize 256, center crop 224" with a bilinear filter.
+    pub fn imagenet() -> Self {
+        Self {
+            resize: ResizeMode::CenterCrop { resize: 256 },
+            filter: ResizeFilter::Triangle,
+        }
+    }
+
+    /// Parses the `--resize` flag: `exact`, `center_crop[:<size>]` or `letterbox`.
+    pub fn parse_mode(value: &str) -> Option<ResizeMode> {
+        match value.split_once(':') {
+            None if value == "exact" => Some(ResizeMode::Exact),
+            None if value == "center_crop" => Some(ResizeMode::CenterCrop { resize: 256 }),
+            None if value == "letterbox" => Some(ResizeMode::Letterbox { fill: [0, 0, 0] }),
+            Some(("center_crop", size)) => size
+                .parse()
+                .ok()
+                .map(|resize| ResizeMode::CenterCrop { resize }),
+            _ => None,
+        }
+    }
+
+    /// Parses the `--filter` flag.
+    pub fn parse_filter(value: &str) -> Option<ResizeFilter> {
+        match value {
+            "nearest" => Some(ResizeFilter::Nearest),
+            "triangle" => Some(ResizeFilter::Triangle),
+            "catmull_rom" => Some(ResizeFilter::CatmullRom),
+            "gaussian" => Some(ResizeFilter::Gaussian),
+            "lanczos3" => Some(ResizeFilter::Lanczos3),
+            _ => None,
+        }
+    }
+
+    /// Produces the 224x224 model input.
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        self.apply_to_size(image, INPUT_SIZE)
+    }
+
+    /// Same as [`Self::apply`] for a `size`x`size` output, used by test-time
+    /// augmentation to build larger views that are cropped afterwards.
+    pub fn apply_to_size(&self, image: &DynamicImage, size: u32) -> DynamicImage {
+        let filter = FilterType::from(self.filter);
+        match self.resize {
+            ResizeMode::Exact => image.resize_exact(size, size, filter),
+            ResizeMode::CenterCrop { resize } => {
+                // Keep the crop ratio when scaling to something other than 224.
+                let shorter =
+                    (resize.max(INPUT_SIZE) as u64 * size as u64 / INPUT_SIZE as u64) as u32;
+                let (width, height) = image.dimensions();
+                let scale = shorter as f32 / width.min(height).max(1) as f32;
+                let resized = image.resize_exact(
+                    ((width as f32 * scale).round() as u32).max(size),
+                    ((height as f32 * scale).round() as u32).max(size),
+                    filter,
+                );
+                let x = (resized.width() - size) / 2;
+                let y = (resized.height() - size) / 2;
+                resized.crop_imm(x, y, size, size)
+            }
+            ResizeMode::Letterbox { fill } => {
+                let fitted = image.resize(size, size, filter).to_rgb8();
+                let mut canvas = RgbImage::from_pixel(size, size, Rgb(fill));
+                let x = (size - fitted.width()) / 2;
+                let y = (size - fitted.height()) / 2;
+                image::imageops::overlay(&mut canvas, &fitted, x as i64, y as i64);
+                DynamicImage::ImageRgb8(canvas)
+            }
+        }
+    }
+}
@@ -0,0 +1,496 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use burn::{
+    module::{Module, ModuleMapper, ModuleVisitor, ParamId},
+    record::{BinFileRecorder, FullPrecisionSettings, HalfPrecisionSettings, RecorderError},
+    tensor::{backend::Backend, Data, Shape, Tensor},
+};
+use num_traits::cast::ToPrimitive;
+
+use crate::model::low_precision::LowPrecisionClassifier;
+use crate::model::normalizer::Normalizer;
+use crate::model::squeezenet::Classifier;
+
+const INT8_MAGIC: &[u8; 8] = b"HNDQ8v1\0";
+
+// Fractions of the largest absolute weight tried as clipping range during calibration.
+const CLIP_CANDIDATES: [f32; 5] = [1.0, 0.999, 0.99, 0.98, 0.95];
+
+/// Storage precision of an exported classifier.
+///
+/// [`load_classifier`] expands every variant to the backend's float type, so on a burn
+/// backend they only differ in file size and rounding error. Running them in their own
+/// precision takes a [`LowPrecisionClassifier`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    Full,
+    Half,
+    Int8,
+}
+
+impl Precision {
+    pub const ALL: [Precision; 3] = [Precision::Full, Precision::Half, Precision::Int8];
+
+    /// File name used inside an export directory.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Precision::Full => "classifier_f32.bin",
+            Precision::Half => "classifier_f16.bin",
+            Precision::Int8 => "classifier_int8.bin",
+        }
+    }
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Precision::Full => write!(f, "f32"),
+            Precision::Half => write!(f, "f16"),
+            Precision::Int8 => write!(f, "int8"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum QuantizationError {
+    Io(std::io::Error),
+    Recorder(RecorderError),
+    /// The int8 file does not match the classifier it is loaded into.
+    Format(String),
+}
+
+impl fmt::Display for QuantizationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuantizationError::Io(error) => write!(f, "I/O error: {error}"),
+            QuantizationError::Recorder(error) => write!(f, "Recorder error: {error:?}"),
+            QuantizationError::Format(reason) => write!(f, "Invalid int8 checkpoint: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for QuantizationError {}
+
+impl From<std::io::Error> for QuantizationError {
+    fn from(error: std::io::Error) -> Self {
+        QuantizationError::Io(error)
+    }
+}
+
+impl From<RecorderError> for QuantizationError {
+    fn from(error: RecorderError) -> Self {
+        QuantizationError::Recorder(error)
+    }
+}
+
+/// One weight tensor stored as symmetric int8 with a single scale.
+#[derive(Debug, Clone)]
+pub struct QuantizedTensor {
+    pub shape: Vec<usize>,
+    pub scale: f32,
+    pub values: Vec<i8>,
+}
+
+impl QuantizedTensor {
+    fn quantize(values: &[f32], shape: Vec<usize>, clip: f32) -> Self {
+        let max_abs = values.iter().fold(0.0f32, |max, value| max.max(value.abs()));
+        let scale = if max_abs == 0.0 {
+            1.0
+        } else {
+            clip * max_abs / 127.0
+        };
+        let values = values
+            .iter()
+            .map(|value| (value / scale).round().clamp(-127.0, 127.0) as i8)
+            .collect();
+
+        Self {
+            shape,
+            scale,
+            values,
+        }
+    }
+
+    fn dequantize(&self) -> Vec<f32> {
+        self.values
+            .iter()
+            .map(|&value| value as f32 * self.scale)
+            .collect()
+    }
+}
+
+/// Every float parameter of a classifier in int8, in module traversal order.
+#[derive(Debug, Clone, Default)]
+pub struct Int8Weights {
+    pub tensors: Vec<QuantizedTensor>,
+    /// Clipping fraction picked during calibration.
+    pub clip: f32,
+}
+
+impl Int8Weights {
+    /// Quantizes all parameters of `model` with the given clipping fraction.
+    pub fn quantize<B: Backend>(model: &Classifier<B>, clip: f32) -> Self {
+        let mut collector = Int8Collector {
+            clip,
+            tensors: Vec::new(),
+        };
+        model.visit(&mut collector);
+
+        Self {
+            tensors: collector.tensors,
+            clip,
+        }
+    }
+
+    /// Post-training quantization: tries several clipping ranges and keeps the one whose
+    /// logits on the calibration images are closest to the f32 model's.
+    pub fn calibrate<B: Backend>(model: &Classifier<B>, calibration: &[Tensor<B, 4>]) -> Self {
+        if calibration.is_empty() {
+            return Self::quantize(model, 1.0);
+        }
+
+        let normalizer = Normalizer::<B>::new();
+        let batch = normalizer.normalize(Tensor::cat(calibration.to_vec(), 0));
+        let reference = model.forward(batch.clone());
+
+        let mut best: Option<(f32, Int8Weights)> = None;
+        for clip in CLIP_CANDIDATES {
+            let weights = Self::quantize(model, clip);
+            let quantized = match weights.dequantize_into(model.clone()) {
+                Ok(quantized) => quantized,
+                Err(_) => continue,
+            };
+            let error = (quantized.forward(batch.clone()) - reference.clone())
+                .powf(2.0)
+                .mean()
+                .into_scalar()
+                .to_f32()
+                .unwrap_or(f32::INFINITY);
+
+            if best.as_ref().map_or(true, |(best_error, _)| error < *best_error) {
+                best = Some((error, weights));
+            }
+        }
+
+        best.map(|(_, weights)| weights)
+            .unwrap_or_else(|| Self::quantize(model, 1.0))
+    }
+
+    /// Replaces the parameters of `model` with the dequantized weights. The result is an
+    /// ordinary float classifier.
+    pub fn dequantize_into<B: Backend>(
+        &self,
+        model: Classifier<B>,
+    ) -> Result<Classifier<B>, QuantizationError> {
+        let mut loader = Int8Loader {
+            tensors: &self.tensors,
+            next: 0,
+            error: None,
+        };
+        let model = model.map(&mut loader);
+
+        if let Some(error) = loader.error {
+            return Err(QuantizationError::Format(error));
+        }
+        if loader.next != self.tensors.len() {
+            return Err(QuantizationError::Format(format!(
+                "file has {} tensors, classifier has {}",
+                self.tensors.len(),
+                loader.next
+            )));
+        }
+
+        Ok(model)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), QuantizationError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(INT8_MAGIC)?;
+        writer.write_all(&self.clip.to_le_bytes())?;
+        writer.write_all(&(self.tensors.len() as u64).to_le_bytes())?;
+
+        for tensor in &self.tensors {
+            writer.write_all(&(tensor.shape.len() as u64).to_le_bytes())?;
+            for dim in &tensor.shape {
+                writer.write_all(&(*dim as u64).to_le_bytes())?;
+            }
+            writer.write_all(&tensor.scale.to_le_bytes())?;
+            let bytes: Vec<u8> = tensor.values.iter().map(|&value| value as u8).collect();
+            writer.write_all(&bytes)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, QuantizationError> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != INT8_MAGIC {
+            return Err(QuantizationError::Format("wrong file header".to_string()));
+        }
+
+        let clip = read_f32(&mut reader)?;
+        let count = read_u64(&mut reader)? as usize;
+        let mut tensors = Vec::with_capacity(count);
+        for _ in 0..count {
+            let rank = read_u64(&mut reader)? as usize;
+            let mut shape = Vec::with_capacity(rank);
+            for _ in 0..rank {
+                shape.push(read_u64(&mut reader)? as usize);
+            }
+            let scale = read_f32(&mut reader)?;
+            let mut bytes = vec![0u8; shape.iter().product()];
+            reader.read_exact(&mut bytes)?;
+
+            tensors.push(QuantizedTensor {
+                shape,
+                scale,
+                values: bytes.into_iter().map(|byte| byte as i8).collect(),
+            });
+        }
+
+        Ok(Self { tensors, clip })
+    }
+}
+
+struct Int8Collector {
+    clip: f32,
+    tensors: Vec<QuantizedTensor>,
+}
+
+impl<B: Backend> ModuleVisitor<B> for Int8Collector {
+    fn visit<const D: usize>(&mut self, _id: &ParamId, tensor: &Tensor<B, D>) {
+        let data = tensor.to_data().convert::<f32>();
+        self.tensors.push(QuantizedTensor::quantize(
+            &data.value,
+            data.shape.dims.to_vec(),
+            self.clip,
+        ));
+    }
+}
+
+struct Int8Loader<'a> {
+    tensors: &'a [QuantizedTensor],
+    next: usize,
+    error: Option<String>,
+}
+
+impl<'a, B: Backend> ModuleMapper<B> for Int8Loader<'a> {
+    fn map<const D: usize>(&mut self, _id: &ParamId, tensor: Tensor<B, D>) -> Tensor<B, D> {
+        let index = self.next;
+        self.next += 1;
+
+        let Some(quantized) = self.tensors.get(index) else {
+            self.error.get_or_insert_with(|| "file has too few tensors".to_string());
+            return tensor;
+        };
+        if quantized.shape != tensor.dims() {
+            self.error.get_or_insert_with(|| {
+                format!(
+                    "tensor {index} has shape {:?}, expected {:?}",
+                    quantized.shape,
+                    tensor.dims()
+                )
+            });
+            return tensor;
+        }
+
+        let mut dims = [0; D];
+        dims.copy_from_slice(&quantized.shape);
+        let data = Data::new(quantized.dequantize(), Shape::new(dims)).convert();
+        Tensor::from_data_device(data, &tensor.device())
+    }
+}
+
+fn read_u64(reader: &mut impl Read) -> std::io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f32(reader: &mut impl Read) -> std::io::Result<f32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+/// Writes `model` to `dir` in the given precision and returns the file path.
+///
+/// `calibration` images (unnormalized, in `[0, 1]`) are only used for int8.
+pub fn export_classifier<B: Backend>(
+    model: &Classifier<B>,
+    dir: &Path,
+    precision: Precision,
+    calibration: &[Tensor<B, 4>],
+) -> Result<PathBuf, QuantizationError> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(precision.file_name());
+
+    match precision {
+        Precision::Full => model
+            .clone()
+            .save_file(path.clone(), &BinFileRecorder::<FullPrecisionSettings>::new())?,
+        Precision::Half => model
+            .clone()
+            .save_file(path.clone(), &BinFileRecorder::<HalfPrecisionSettings>::new())?,
+        Precision::Int8 => Int8Weights::calibrate(model, calibration).save(&path)?,
+    }
+
+    Ok(path)
+}
+
+/// Loads a classifier written by [`export_classifier`].
+pub fn load_classifier<B: Backend>(
+    path: &Path,
+    precision: Precision,
+) -> Result<Classifier<B>, QuantizationError> {
+    let model = Classifier::<B>::new();
+
+    match precision {
+        Precision::Full => Ok(model.load_file(
+            path.to_path_buf(),
+            &BinFileRecorder::<FullPrecisionSettings>::new(),
+        )?),
+        Precision::Half => Ok(model.load_file(
+            path.to_path_buf(),
+            &BinFileRecorder::<HalfPrecisionSettings>::new(),
+        )?),
+        Precision::Int8 => Int8Weights::load(path)?.dequantize_into(model),
+    }
+}
+
+/// File size, accuracy and latency of one precision on an evaluation set, run by a
+/// [`LowPrecisionClassifier`].
+#[derive(Debug, Clone)]
+pub struct PrecisionReport {
+    pub precision: Precision,
+    pub file_bytes: u64,
+    pub accuracy: f32,
+    /// Fraction of images where the prediction matches the f32 model.
+    pub agreement: f32,
+    /// Mean time of a forward pass over one image.
+    pub mean_latency_ms: f64,
+    /// f32 latency divided by this one; below 1 means slower than f32.
+    pub speedup: f64,
+}
+
+impl fmt::Display for PrecisionReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>5} | {:>8.2} MB | {:>8.2} ms/image ({:.2}x f32) | accuracy {:>6.2}% | agrees with f32 {:>6.2}%",
+            self.precision.to_string(),
+            self.file_bytes as f64 / 1_000_000.0,
+            self.mean_latency_ms,
+            self.speedup,
+            self.accuracy * 100.0,
+            self.agreement * 100.0
+        )
+    }
+}
+
+/// The reports of [`compare_precisions`] and the f32 classifier on the burn backend for
+/// reference.
+#[derive(Debug, Clone)]
+pub struct PrecisionComparison {
+    pub reports: Vec<PrecisionReport>,
+    /// Mean time of [`Classifier::forward`] over one image on the backend compared on.
+    pub backend_latency_ms: f64,
+}
+
+impl fmt::Display for PrecisionComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for report in &self.reports {
+            writeln!(f, "{report}")?;
+        }
+        write!(
+            f,
+            "burn f32 forward for reference: {:.2} ms/image",
+            self.backend_latency_ms
+        )
+    }
+}
+
+/// Exports `model` in every precision, reloads each one into a [`LowPrecisionClassifier`]
+/// and measures what the smaller files cost in accuracy and gain in speed.
+///
+/// `labels` holds the class index of each image. Images are in `[0, 1]` and unnormalized.
+pub fn compare_precisions<B: Backend>(
+    model: &Classifier<B>,
+    dir: &Path,
+    calibration: &[Tensor<B, 4>],
+    images: &[Tensor<B, 4>],
+    labels: &[usize],
+) -> Result<PrecisionComparison, QuantizationError> {
+    let normalizer = Normalizer::<B>::new();
+    let normalized: Vec<Tensor<B, 4>> = images
+        .iter()
+        .map(|image| normalizer.normalize(image.clone()))
+        .collect();
+    let count = images.len().max(1) as f32;
+
+    let start = Instant::now();
+    for image in &normalized {
+        model.forward(image.clone()).argmax(1).into_scalar();
+    }
+    let backend_latency_ms = start.elapsed().as_secs_f64() * 1000.0 / count as f64;
+
+    let mut reference: Vec<usize> = Vec::new();
+    let mut full_latency_ms = 0.0;
+    let mut reports = Vec::new();
+
+    for precision in Precision::ALL {
+        let path = export_classifier(model, dir, precision, calibration)?;
+        let loaded = LowPrecisionClassifier::load::<B>(&path, precision)?;
+
+        let start = Instant::now();
+        let predictions: Vec<usize> = normalized
+            .iter()
+            .map(|image| argmax(&loaded.forward(image.clone())[0]))
+            .collect();
+        let mean_latency_ms = start.elapsed().as_secs_f64() * 1000.0 / count as f64;
+
+        if precision == Precision::Full {
+            reference = predictions.clone();
+            full_latency_ms = mean_latency_ms;
+        }
+
+        let correct = predictions.iter().zip(labels).filter(|(p, l)| p == l).count();
+        let agreeing = predictions.iter().zip(&reference).filter(|(p, r)| p == r).count();
+
+        reports.push(PrecisionReport {
+            precision,
+            file_bytes: std::fs::metadata(&path)?.len(),
+            accuracy: correct as f32 / count,
+            agreement: agreeing as f32 / count,
+            mean_latency_ms,
+            speedup: full_latency_ms / mean_latency_ms.max(f64::EPSILON),
+        });
+    }
+
+    Ok(PrecisionComparison {
+        reports,
+        backend_latency_ms,
+    })
+}
+
+fn argmax(logits: &[f32]) -> usize {
+    logits
+        .iter()
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |best, (index, &value)| {
+            if value > best.1 {
+                (index, value)
+            } else {
+                best
+            }
+        })
+        .0
+}
@@ -1,24 +1,82 @@
+use std::fmt;
+use std::path::Path;
+
 use burn::{
+    module::{AutodiffModule, Module},
     nn::loss::CrossEntropyLoss,
-    optim::{adaptor::OptimizerAdaptor, GradientsParams, Optimizer, Sgd, SgdConfig},
-    tensor::Data,
+    optim::{
+        adaptor::OptimizerAdaptor, decay::WeightDecayConfig, momentum::MomentumConfig,
+        GradientsParams, Optimizer, Sgd, SgdConfig,
+    },
+    record::{BinFileRecorder, FullPrecisionSettings, RecorderError},
+    tensor::{Data, Shape},
 };
+use serde::{Deserialize, Serialize};
 
-use crate::model::label::LABELS_DOG;
+use crate::model::label::{LABELS_DOG, UNSURE_LABEL};
 use crate::model::normalizer::Normalizer;
+use crate::model::ood::OodDetector;
+use crate::model::preprocessing::Preprocessing;
 use crate::model::squeezenet;
+use crate::model::tta::TestTimeAugmentation;
+use crate::profiling::{Stage, StageTimings};
+use crate::seed::seed_backend;
 use burn::tensor::{
+    activation::softmax,
     backend::{AutodiffBackend, Backend},
     Int, Tensor,
 };
-use image::{self, GenericImageView, Pixel};
+use image::{self, DynamicImage, GenericImageView, Pixel};
 
 use super::squeezenet::Classifier;
 use num_traits::cast::ToPrimitive;
 pub struct HotNotDogClassifier<B: AutodiffBackend> {
     model: Classifier<B>,
     normalizer: Normalizer<B>,
+    eval_normalizer: Normalizer<B::InnerBackend>,
     pub optimizer: OptimizerAdaptor<Sgd<B::InnerBackend>, Classifier<B>, B>,
+    settings: OptimizerSettings,
+    preprocessing: Preprocessing,
+    ood: Option<OodDetector>,
+    seed: Option<u64>,
+    steps: u64,
+}
+
+/// SGD settings used by [`HotNotDogClassifier::train_batch`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OptimizerSettings {
+    pub learning_rate: f64,
+    /// `0.0` disables momentum.
+    pub momentum: f64,
+    /// L2 penalty, `0.0` disables weight decay.
+    pub weight_decay: f64,
+}
+
+impl Default for OptimizerSettings {
+    fn default() -> Self {
+        Self {
+            learning_rate: 0.10,
+            momentum: 0.0,
+            weight_decay: 0.0,
+        }
+    }
+}
+
+impl OptimizerSettings {
+    fn init<B: AutodiffBackend>(&self) -> OptimizerAdaptor<Sgd<B::InnerBackend>, Classifier<B>, B> {
+        let momentum = (self.momentum > 0.0).then(|| {
+            MomentumConfig::new()
+                .with_momentum(self.momentum)
+                .with_dampening(0.0)
+        });
+        let weight_decay =
+            (self.weight_decay > 0.0).then(|| WeightDecayConfig::new(self.weight_decay));
+
+        SgdConfig::new()
+            .with_momentum(momentum)
+            .with_weight_decay(weight_decay)
+            .init()
+    }
 }
 
 impl<B: AutodiffBackend> Default for HotNotDogClassifier<B> {
@@ -26,11 +84,91 @@ impl<B: AutodiffBackend> Default for HotNotDogClassifier<B> {
         Self {
             model: squeezenet::Classifier::<B>::default(),
             normalizer: Normalizer::<B>::default(),
+            eval_normalizer: Normalizer::default(),
             optimizer: SgdConfig::new().init(),
+            settings: OptimizerSettings::default(),
+            preprocessing: Preprocessing::default(),
+            ood: None,
+            seed: None,
+            steps: 0,
         }
     }
 }
 
+/// Outcome of one prediction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verdict {
+    /// A class of [`LABELS_DOG`], or [`UNSURE_LABEL`] when `unsure`.
+    pub label: &'static str,
+    /// Most likely class, even when `unsure`.
+    pub class_index: usize,
+    pub probabilities: Vec<f32>,
+    /// `None` until an [`OodDetector`] is set.
+    pub ood_score: Option<f32>,
+    pub unsure: bool,
+}
+
+/// Stored next to a checkpoint so a run can be traced back and repeated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointMetadata {
+    /// Seed the run was started with, `None` for unseeded runs.
+    pub seed: Option<u64>,
+    /// Number of optimizer steps taken.
+    pub steps: u64,
+    pub crate_version: String,
+    /// Checkpoints written before preprocessing was configurable used the default.
+    #[serde(default)]
+    pub preprocessing: Preprocessing,
+    /// Present once the out-of-distribution threshold has been calibrated.
+    #[serde(default)]
+    pub ood: Option<OodDetector>,
+}
+
+impl CheckpointMetadata {
+    /// Reads the metadata stored next to the checkpoint at `path`.
+    pub fn load(path: &Path) -> Result<Self, CheckpointError> {
+        let json = std::fs::read_to_string(path.with_extension("json"))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(std::io::Error),
+    Recorder(RecorderError),
+    Metadata(serde_json::Error),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckpointError::Io(error) => write!(f, "I/O error: {error}"),
+            CheckpointError::Recorder(error) => write!(f, "Recorder error: {error:?}"),
+            CheckpointError::Metadata(error) => write!(f, "Invalid checkpoint metadata: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<std::io::Error> for CheckpointError {
+    fn from(error: std::io::Error) -> Self {
+        CheckpointError::Io(error)
+    }
+}
+
+impl From<RecorderError> for CheckpointError {
+    fn from(error: RecorderError) -> Self {
+        CheckpointError::Recorder(error)
+    }
+}
+
+impl From<serde_json::Error> for CheckpointError {
+    fn from(error: serde_json::Error) -> Self {
+        CheckpointError::Metadata(error)
+    }
+}
+
 impl<B: AutodiffBackend> HotNotDogClassifier<B> {
     pub fn new() -> Self {
         let squeezenet_imported = squeezenet::Model::<B>::from_embedded();
@@ -41,37 +179,321 @@ impl<B: AutodiffBackend> HotNotDogClassifier<B> {
         Self {
             model,
             normalizer,
+            eval_normalizer: Normalizer::new(),
             optimizer: optim,
+            settings: OptimizerSettings::default(),
+            preprocessing: Preprocessing::default(),
+            ood: None,
+            seed: None,
+            steps: 0,
+        }
+    }
+
+    /// Like [`Self::new`], but seeds the backend first so the new head and every
+    /// dropout mask are the same on each run with the same seed.
+    pub fn new_seeded(seed: u64) -> Self {
+        seed_backend::<B>(seed);
+        Self {
+            seed: Some(seed),
+            ..Self::new()
+        }
+    }
+
+    /// Wraps an already fine-tuned classifier, e.g. one loaded from a checkpoint.
+    pub fn from_model(model: Classifier<B>) -> Self {
+        Self {
+            model,
+            normalizer: Normalizer::<B>::new(),
+            eval_normalizer: Normalizer::new(),
+            optimizer: SgdConfig::new().init(),
+            settings: OptimizerSettings::default(),
+            preprocessing: Preprocessing::default(),
+            ood: None,
+            seed: None,
+            steps: 0,
         }
     }
 
+    /// Replaces the optimizer, dropping any momentum accumulated so far.
+    pub fn with_optimizer(mut self, settings: OptimizerSettings) -> Self {
+        self.optimizer = settings.init();
+        self.settings = settings;
+        self
+    }
+
+    /// Freezes the first `depth` convolution layers, see [`Classifier::freeze`].
+    pub fn with_frozen_layers(mut self, depth: usize) -> Self {
+        self.model = self.model.freeze(depth);
+        self
+    }
+
+    /// Sets how images are resized before they reach this classifier.
+    pub fn with_preprocessing(mut self, preprocessing: Preprocessing) -> Self {
+        self.preprocessing = preprocessing;
+        self
+    }
+
+    pub fn preprocessing(&self) -> &Preprocessing {
+        &self.preprocessing
+    }
+
+    /// Enables the "unsure" outcome, see [`crate::model::ood`].
+    pub fn with_ood_detector(mut self, detector: OodDetector) -> Self {
+        self.ood = Some(detector);
+        self
+    }
+
+    pub fn ood_detector(&self) -> Option<&OodDetector> {
+        self.ood.as_ref()
+    }
+
+    pub fn optimizer_settings(&self) -> OptimizerSettings {
+        self.settings
+    }
+
+    pub fn model(&self) -> &Classifier<B> {
+        &self.model
+    }
+
+    /// The classifier on the inner backend: no autodiff graph and dropout disabled.
+    pub fn eval_model(&self) -> Classifier<B::InnerBackend> {
+        self.model.valid()
+    }
+
+    /// Normalizes an image and moves it to the inner backend for inference.
+    fn eval_input(&self, image: Tensor<B, 4>) -> Tensor<B::InnerBackend, 4> {
+        self.eval_normalizer.normalize(image.inner())
+    }
+
     pub fn predict(&self, image: Tensor<B, 4>) -> &'static str
     {
-        let image = self.normalizer.normalize(image);
-        let output = self.model.forward(image);
-
-        let arg_max = output.argmax(1).into_scalar().to_usize().unwrap() as usize;
+        let logits = self.predict_logits(image).remove(0);
 
-        let label = LABELS_DOG[arg_max];
+        let label = self.verdict(logits).label;
 
         label
     }
 
-    pub fn train(&mut self, image: Tensor<B, 4>, label: Tensor<B, 1, Int>) -> () {
-        let image = self.normalizer.normalize(image);
-        let prediction = self.model.forward(image);
-        let loss = CrossEntropyLoss::new(None).forward(prediction.clone(), label.clone());
+    /// Same as [`Self::predict`], but records how long each step took.
+    pub fn predict_profiled(&self, image: Tensor<B, 4>, timings: &mut StageTimings) -> &'static str {
+        let image = timings.time(Stage::Normalize, || {
+            let image = self.eval_input(image);
+            B::InnerBackend::sync(&image.device());
+            image
+        });
+        let output = timings.time(Stage::Forward, || {
+            let output = self.eval_model().forward(image);
+            B::InnerBackend::sync(&output.device());
+            output
+        });
+        timings.time(Stage::Argmax, || {
+            let logits = output.into_data().convert::<f32>().value;
+            self.verdict(logits).label
+        })
+    }
+
+    /// Raw class scores for every image of a `[batch, 3, 224, 224]` tensor.
+    pub fn predict_logits(&self, images: Tensor<B, 4>) -> Vec<Vec<f32>> {
+        let images = self.eval_input(images);
+        let values = self.eval_model().forward(images).into_data().convert::<f32>().value;
+        values
+            .chunks(LABELS_DOG.len())
+            .map(|row| row.to_vec())
+            .collect()
+    }
+
+    /// Label, probabilities and out-of-distribution check for every image of a batch.
+    pub fn predict_verdicts(&self, images: Tensor<B, 4>) -> Vec<Verdict> {
+        self.predict_logits(images)
+            .into_iter()
+            .map(|logits| self.verdict(logits))
+            .collect()
+    }
+
+    fn verdict(&self, logits: Vec<f32>) -> Verdict {
+        let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let exp: Vec<f32> = logits.iter().map(|logit| (logit - max).exp()).collect();
+        let sum: f32 = exp.iter().sum();
+        let probabilities: Vec<f32> = exp.iter().map(|value| value / sum).collect();
+        let class_index = probabilities
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+
+        let ood_score = self.ood.map(|detector| detector.score(&logits));
+        let unsure = self
+            .ood
+            .zip(ood_score)
+            .is_some_and(|(detector, score)| score < detector.threshold);
+
+        Verdict {
+            label: if unsure { UNSURE_LABEL } else { LABELS_DOG[class_index] },
+            class_index,
+            probabilities,
+            ood_score,
+            unsure,
+        }
+    }
+
+    /// Class probabilities for every image of a `[batch, 3, 224, 224]` tensor.
+    pub fn predict_probabilities(&self, images: Tensor<B, 4>) -> Vec<Vec<f32>> {
+        let [batch_size, _, _, _] = images.dims();
+        let images = self.eval_input(images);
+        let output = softmax(self.eval_model().forward(images), 1);
+
+        let values = output.into_data().convert::<f32>().value;
+        values
+            .chunks(values.len() / batch_size.max(1))
+            .map(|row| row.to_vec())
+            .collect()
+    }
+
+    /// Runs one SGD step on a single labelled image and returns the loss.
+    pub fn train(&mut self, image: Tensor<B, 4>, label: Tensor<B, 1, Int>) -> f32 {
+        let loss_value = self.train_batch(image, label);
         // print the loss please
-        println!("Loss: {}", loss);
+        println!("Loss: {}", loss_value);
+
+        loss_value
+    }
+
+    /// Runs one SGD step on a `[batch, 3, 224, 224]` tensor and returns the mean loss.
+    pub fn train_batch(&mut self, images: Tensor<B, 4>, labels: Tensor<B, 1, Int>) -> f32 {
+        let images = self.normalizer.normalize(images);
+        let prediction = self.model.forward(images);
+        let loss = CrossEntropyLoss::new(None).forward(prediction, labels);
+        let loss_value = loss.clone().into_scalar().to_f32().unwrap();
 
         // Gradients for the current backward pass
         let grads = loss.backward();
         // Gradients linked to each parameter of the model.
         let grads = GradientsParams::from_grads(grads, &self.model);
-        
+
         // Update the parameters of the model.
-        let updated_model = self.optimizer.step(0.10, self.model.clone(), grads);
+        let updated_model = self
+            .optimizer
+            .step(self.settings.learning_rate, self.model.clone(), grads);
         self.model = updated_model;
+        self.steps += 1;
+
+        loss_value
+    }
+
+    /// Mean loss and number of correct predictions on a batch, in inference mode.
+    pub fn evaluate(&self, images: Tensor<B, 4>, labels: &[usize]) -> (f32, usize) {
+        let images = self.eval_input(images);
+        let output = self.eval_model().forward(images);
+        let targets = Tensor::<B::InnerBackend, 1, Int>::from_data(
+            Data::new(
+                labels.iter().map(|&label| label as i64).collect(),
+                Shape::new([labels.len()]),
+            )
+            .convert(),
+        );
+
+        let loss = CrossEntropyLoss::new(None)
+            .forward(output.clone(), targets)
+            .into_scalar()
+            .to_f32()
+            .unwrap();
+        let predicted = output.argmax(1).into_data().convert::<i64>().value;
+        let correct = predicted
+            .iter()
+            .zip(labels)
+            .filter(|(predicted, &label)| **predicted == label as i64)
+            .count();
+
+        (loss, correct)
+    }
+
+    /// Number of optimizer steps taken so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+}
+
+impl<B: AutodiffBackend> HotNotDogClassifier<B> {
+    pub fn metadata(&self) -> CheckpointMetadata {
+        CheckpointMetadata {
+            seed: self.seed,
+            steps: self.steps,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            preprocessing: self.preprocessing,
+            ood: self.ood,
+        }
+    }
+
+    /// Saves the weights to `path` and the [`CheckpointMetadata`] to `path` with a
+    /// `.json` extension.
+    pub fn save_checkpoint(&self, path: &Path) -> Result<(), CheckpointError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.model
+            .clone()
+            .save_file(path.to_path_buf(), &BinFileRecorder::<FullPrecisionSettings>::new())?;
+        let metadata = serde_json::to_string_pretty(&self.metadata())?;
+        std::fs::write(path.with_extension("json"), metadata)?;
+        Ok(())
+    }
+
+    /// Loads the weights of a checkpoint written by [`Self::save_checkpoint`] into this
+    /// classifier, keeping its frozen layers, optimizer settings, preprocessing and
+    /// detector. The optimizer starts fresh.
+    pub fn restore_weights(&mut self, path: &Path) -> Result<(), CheckpointError> {
+        self.model = self.model.clone().load_file(
+            path.to_path_buf(),
+            &BinFileRecorder::<FullPrecisionSettings>::new(),
+        )?;
+        self.optimizer = self.settings.init();
+        Ok(())
+    }
+
+    /// Loads a checkpoint written by [`Self::save_checkpoint`]. The optimizer starts fresh.
+    pub fn load_checkpoint(path: &Path) -> Result<Self, CheckpointError> {
+        let model = Classifier::<B>::new().load_file(
+            path.to_path_buf(),
+            &BinFileRecorder::<FullPrecisionSettings>::new(),
+        )?;
+        let metadata = CheckpointMetadata::load(path)?;
+
+        Ok(Self {
+            preprocessing: metadata.preprocessing,
+            ood: metadata.ood,
+            seed: metadata.seed,
+            steps: metadata.steps,
+            ..Self::from_model(model)
+        })
+    }
+}
+
+impl<B: AutodiffBackend> HotNotDogClassifier<B>
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    /// Loads an image file with this classifier's preprocessing.
+    pub fn load_image(&self, path: &str) -> Tensor<B, 4> {
+        load_image_with(path, &self.preprocessing)
+    }
+
+    /// Class probabilities of a decoded image, averaged over the views of `tta`.
+    pub fn predict_tta(&self, image: &DynamicImage, tta: &TestTimeAugmentation) -> Vec<f32> {
+        let views: Vec<Tensor<B, 4>> = tta
+            .views(image, &self.preprocessing)
+            .iter()
+            .map(|view| image_to_tensor(view))
+            .collect();
+        let count = views.len() as f32;
+
+        let mut mean = vec![0.0; LABELS_DOG.len()];
+        for probabilities in self.predict_probabilities(Tensor::cat(views, 0)) {
+            for (sum, probability) in mean.iter_mut().zip(probabilities) {
+                *sum += probability / count;
+            }
+        }
+        mean
     }
 }
 
@@ -79,8 +501,35 @@ pub fn load_image<B: Backend>(path: &str) -> Tensor<B, 4>
 where
     Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
 {
-    let img = image::open(&path).unwrap_or_else(|_| panic!("Failed to load image: {path}"));
-    let resized_img = img.resize_exact(224, 224, image::imageops::FilterType::Lanczos3);
+    load_image_with(path, &Preprocessing::default())
+}
+
+/// Like [`load_image`], with explicit preprocessing.
+pub fn load_image_with<B: Backend>(path: &str, preprocessing: &Preprocessing) -> Tensor<B, 4>
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    let img = decode_image(path);
+    let resized_img = preprocessing.apply(&img);
+    image_to_tensor(&resized_img)
+}
+
+/// Reads and decodes an image file.
+pub fn decode_image(path: &str) -> DynamicImage {
+    image::open(path).unwrap_or_else(|_| panic!("Failed to load image: {path}"))
+}
+
+/// Resizes a decoded image to the 224x224 input of SqueezeNet with the default
+/// [`Preprocessing`].
+pub fn resize_image(img: &DynamicImage) -> DynamicImage {
+    Preprocessing::default().apply(img)
+}
+
+/// Converts a 224x224 image to a `[1, 3, 224, 224]` tensor with values in `[0, 1]`.
+pub fn image_to_tensor<B: Backend>(resized_img: &DynamicImage) -> Tensor<B, 4>
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
     let mut img_array = [[[0.0; 224]; 224]; 3];
     for y in 0..224usize {
         for x in 0..224usize {
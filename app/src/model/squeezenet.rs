@@ -1069,6 +1069,37 @@ impl<B: Backend> Classifier<B> {
     }
 }
 
+impl<B: Backend> Classifier<B> {
+    /// Number of convolution layers in the trunk, the upper bound for [`Self::freeze`].
+    pub const CONV_LAYERS: usize = 26;
+
+    /// Stops gradients for the first `depth` convolution layers of the trunk.
+    ///
+    /// `0` trains everything, [`Self::CONV_LAYERS`] trains only the linear head.
+    pub fn freeze(mut self, depth: usize) -> Self {
+        macro_rules! freeze {
+            ($($layer:literal => $field:ident),* $(,)?) => {
+                $(
+                    if depth >= $layer {
+                        self.$field = self.$field.no_grad();
+                    }
+                )*
+            };
+        }
+
+        freeze!(
+            1 => conv2d1, 2 => conv2d2, 3 => conv2d3, 4 => conv2d4,
+            5 => conv2d5, 6 => conv2d6, 7 => conv2d7, 8 => conv2d8,
+            9 => conv2d9, 10 => conv2d10, 11 => conv2d11, 12 => conv2d12,
+            13 => conv2d13, 14 => conv2d14, 15 => conv2d15, 16 => conv2d16,
+            17 => conv2d17, 18 => conv2d18, 19 => conv2d19, 20 => conv2d20,
+            21 => conv2d21, 22 => conv2d22, 23 => conv2d23, 24 => conv2d24,
+            25 => conv2d25, 26 => conv2d26
+        );
+        self
+    }
+}
+
 impl<B: Backend> Default for Classifier<B> {
     fn default() -> Self {
         Self::new()
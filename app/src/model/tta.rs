@@ -0,0 +1,109 @@
+//! Test-time augmentation: several views of one image are classified and their
+//! softmax outputs averaged.
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+use crate::model::preprocessing::{Preprocessing, INPUT_SIZE as SIZE};
+
+/// Which 224x224 crops are taken from an upscaled view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CropMode {
+    Center,
+    /// The four corners and the center.
+    FiveCrop,
+}
+
+/// Views to average over. `Default` is a single view, the same one
+/// [`Preprocessing::apply`] gives.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestTimeAugmentation {
+    /// Adds a mirrored copy of every view.
+    pub horizontal_flip: bool,
+    pub crops: CropMode,
+    /// Each scale resizes the image to `224 * scale` before cropping. A scale of `1.0`
+    /// is the plain preprocessed view, which has nothing left to crop.
+    pub scales: Vec<f32>,
+}
+
+impl Default for TestTimeAugmentation {
+    fn default() -> Self {
+        Self {
+            horizontal_flip: false,
+            crops: CropMode::Center,
+            scales: vec![1.0],
+        }
+    }
+}
+
+impl TestTimeAugmentation {
+    /// The plain view and its mirror image.
+    pub fn flips() -> Self {
+        Self {
+            horizontal_flip: true,
+            ..Self::default()
+        }
+    }
+
+    /// Flips, five crops and two scales: 2 * (1 + 5) = 12 views.
+    pub fn full() -> Self {
+        Self {
+            horizontal_flip: true,
+            crops: CropMode::FiveCrop,
+            scales: vec![1.0, 1.15],
+        }
+    }
+
+    /// Number of views [`Self::views`] produces per image.
+    pub fn view_count(&self) -> usize {
+        let crops = match self.crops {
+            CropMode::Center => 1,
+            CropMode::FiveCrop => 5,
+        };
+        let per_flip: usize = self
+            .scales
+            .iter()
+            .map(|&scale| if upscaled(scale) > SIZE { crops } else { 1 })
+            .sum();
+        per_flip.max(1) * if self.horizontal_flip { 2 } else { 1 }
+    }
+
+    /// All 224x224 views of `image`. Upscaled views are preprocessed at their larger
+    /// size before cropping.
+    pub fn views(&self, image: &DynamicImage, preprocessing: &Preprocessing) -> Vec<DynamicImage> {
+        let mut views = Vec::with_capacity(self.view_count());
+        for &scale in &self.scales {
+            let size = upscaled(scale);
+            if size <= SIZE {
+                views.push(preprocessing.apply(image));
+                continue;
+            }
+
+            let resized = preprocessing.apply_to_size(image, size);
+            let far = size - SIZE;
+            let offsets = match self.crops {
+                CropMode::Center => vec![(far / 2, far / 2)],
+                CropMode::FiveCrop => {
+                    vec![(0, 0), (far, 0), (0, far), (far, far), (far / 2, far / 2)]
+                }
+            };
+            views.extend(
+                offsets
+                    .into_iter()
+                    .map(|(x, y)| resized.crop_imm(x, y, SIZE, SIZE)),
+            );
+        }
+        if views.is_empty() {
+            views.push(preprocessing.apply(image));
+        }
+
+        if self.horizontal_flip {
+            let flipped: Vec<DynamicImage> = views.iter().map(DynamicImage::fliph).collect();
+            views.extend(flipped);
+        }
+        views
+    }
+}
+
+fn upscaled(scale: f32) -> u32 {
+    (SIZE as f32 * scale).round() as u32
+}
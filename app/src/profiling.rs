@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use burn::tensor::{backend::Backend, Data, Tensor};
+use serde::{Deserialize, Serialize};
+
+use crate::model::normalizer::Normalizer;
+use crate::model::preprocessing::Preprocessing;
+use crate::model::squeezed_classifier::{decode_image, image_to_tensor};
+use crate::model::squeezenet::Classifier;
+
+/// The steps between an image file and a predicted label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Decode,
+    Resize,
+    ToTensor,
+    Normalize,
+    Forward,
+    Argmax,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 6] = [
+        Stage::Decode,
+        Stage::Resize,
+        Stage::ToTensor,
+        Stage::Normalize,
+        Stage::Forward,
+        Stage::Argmax,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Decode => "decode",
+            Stage::Resize => "resize",
+            Stage::ToTensor => "to_tensor",
+            Stage::Normalize => "normalize",
+            Stage::Forward => "forward",
+            Stage::Argmax => "argmax",
+        }
+    }
+}
+
+/// Accumulated wall-clock time per stage.
+#[derive(Debug, Clone, Default)]
+pub struct StageTimings {
+    totals: [Duration; 6],
+    counts: [u32; 6],
+}
+
+impl StageTimings {
+    /// Runs `f` and adds its duration to `stage`.
+    pub fn time<T>(&mut self, stage: Stage, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(stage, start.elapsed());
+        result
+    }
+
+    pub fn record(&mut self, stage: Stage, duration: Duration) {
+        self.totals[stage as usize] += duration;
+        self.counts[stage as usize] += 1;
+    }
+
+    /// Mean duration of one run of `stage` in milliseconds, 0 if it never ran.
+    pub fn mean_ms(&self, stage: Stage) -> f64 {
+        let count = self.counts[stage as usize];
+        if count == 0 {
+            0.0
+        } else {
+            self.totals[stage as usize].as_secs_f64() * 1000.0 / count as f64
+        }
+    }
+
+    /// Sum of the mean duration of every stage.
+    pub fn pipeline_ms(&self) -> f64 {
+        Stage::ALL.iter().map(|stage| self.mean_ms(*stage)).sum()
+    }
+}
+
+impl fmt::Display for StageTimings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for stage in Stage::ALL {
+            write!(f, "{}: {:.2} ms  ", stage.name(), self.mean_ms(stage))?;
+        }
+        write!(f, "total: {:.2} ms", self.pipeline_ms())
+    }
+}
+
+/// Runs the whole pipeline on `paths` in batches of `batch_size` and times every stage.
+///
+/// Images are resized with `preprocessing`, the same as the model sees them elsewhere.
+/// Decode, resize and tensor conversion are timed per image, the rest per batch.
+/// Incomplete trailing batches are skipped so all batches have the same size.
+pub fn profile_pipeline<B: Backend>(
+    model: &Classifier<B>,
+    preprocessing: &Preprocessing,
+    paths: &[String],
+    batch_size: usize,
+) -> StageTimings
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    let normalizer = Normalizer::<B>::new();
+    let mut timings = StageTimings::default();
+
+    for batch in paths.chunks_exact(batch_size.max(1)) {
+        let mut images: Vec<Tensor<B, 4>> = Vec::with_capacity(batch.len());
+        for path in batch {
+            let decoded = timings.time(Stage::Decode, || decode_image(path));
+            let resized = timings.time(Stage::Resize, || preprocessing.apply(&decoded));
+            images.push(timings.time(Stage::ToTensor, || image_to_tensor(&resized)));
+        }
+
+        let batch = timings.time(Stage::Normalize, || {
+            let batch = normalizer.normalize(Tensor::cat(images, 0));
+            B::sync(&batch.device());
+            batch
+        });
+        let output = timings.time(Stage::Forward, || {
+            let output = model.forward(batch);
+            B::sync(&output.device());
+            output
+        });
+        timings.time(Stage::Argmax, || output.argmax(1).into_data());
+    }
+
+    timings
+}
+
+/// Timings of one backend at one batch size, in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchEntry {
+    pub backend: String,
+    pub batch_size: usize,
+    pub images: usize,
+    /// Mean milliseconds per image for decode/resize/to_tensor, per batch for the rest.
+    pub stages_ms: BTreeMap<String, f64>,
+    pub images_per_second: f64,
+}
+
+impl BenchEntry {
+    pub fn new(backend: &str, batch_size: usize, images: usize, timings: &StageTimings) -> Self {
+        let stages_ms = Stage::ALL
+            .iter()
+            .map(|stage| (stage.name().to_string(), timings.mean_ms(*stage)))
+            .collect();
+
+        let per_image_ms = timings.mean_ms(Stage::Decode)
+            + timings.mean_ms(Stage::Resize)
+            + timings.mean_ms(Stage::ToTensor)
+            + (timings.mean_ms(Stage::Normalize)
+                + timings.mean_ms(Stage::Forward)
+                + timings.mean_ms(Stage::Argmax))
+                / batch_size.max(1) as f64;
+
+        Self {
+            backend: backend.to_string(),
+            batch_size,
+            images,
+            stages_ms,
+            images_per_second: if per_image_ms > 0.0 {
+                1000.0 / per_image_ms
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// A set of benchmark results, tagged with the commit they were measured on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub commit: Option<String>,
+    pub created_unix_secs: u64,
+    pub entries: Vec<BenchEntry>,
+}
+
+impl BenchReport {
+    pub fn new(entries: Vec<BenchEntry>) -> Self {
+        let commit = std::process::Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+
+        Self {
+            commit,
+            created_unix_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+            entries,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
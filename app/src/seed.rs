@@ -0,0 +1,44 @@
+//! One seed for everything random in a training run.
+//!
+//! The backend RNG (head initialisation, dropout) is seeded directly. Host-side randomness
+//! (dataset shuffling, splits, batch order, hyperparameter sampling) draws from separate
+//! streams derived from the same seed, so adding a new consumer never shifts the numbers
+//! an existing one sees.
+use burn::tensor::backend::Backend;
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Independent host-side random streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngStream {
+    /// Order of the samples returned by [`crate::data::dataset::load_split`].
+    Shuffle,
+    /// Validation splits and cross-validation folds.
+    Split,
+    /// Batch order of every training epoch.
+    Epochs,
+    /// Random hyperparameter search.
+    Sweep,
+}
+
+impl RngStream {
+    fn salt(&self) -> u64 {
+        match self {
+            RngStream::Shuffle => 0x5348_5546_464c_4531,
+            RngStream::Split => 0x5350_4c49_5431_3233,
+            RngStream::Epochs => 0x4550_4f43_4853_3132,
+            RngStream::Sweep => 0x5357_4545_5031_3233,
+        }
+    }
+}
+
+/// A generator for `stream`, fully determined by `seed`.
+pub fn stream_rng(seed: u64, stream: RngStream) -> StdRng {
+    StdRng::seed_from_u64(seed ^ stream.salt())
+}
+
+/// Seeds the backend RNG used for parameter initialisation and dropout masks.
+///
+/// Call it before building the model; the RNG is global to the backend.
+pub fn seed_backend<B: Backend>(seed: u64) {
+    B::seed(seed);
+}
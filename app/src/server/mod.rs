@@ -0,0 +1,277 @@
+//! HTTP front-end for [`HotNotDogClassifier`](crate::model::squeezed_classifier::HotNotDogClassifier).
+//!
+//! | Method | Path        | Body                                                   |
+//! |--------|-------------|--------------------------------------------------------|
+//! | GET    | `/health`   |                                                        |
+//! | GET    | `/version`  |                                                        |
+//! | POST   | `/predict`  | raw image bytes, or multipart with an `image` part     |
+//! | POST   | `/feedback` | multipart with `image` and `label` parts, or raw bytes |
+//! |        |             | with `?label=hot_dog` / `?label=not_hot_dog`           |
+//!
+//! All responses are JSON. `/predict` answers with the label `Unsure` and `"unsure": true`
+//! when the served checkpoint has a calibrated out-of-distribution threshold and the
+//! image falls below it.
+pub mod multipart;
+pub mod worker;
+
+use std::io::Read;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use burn::tensor::{
+    backend::{AutodiffBackend, Backend},
+    Data,
+};
+use image::DynamicImage;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::model::preprocessing::Preprocessing;
+use crate::server::worker::Worker;
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Largest number of images run through the model in one forward pass.
+    pub max_batch: usize,
+    /// How long the worker waits for more predictions before running a batch.
+    pub batch_window: Duration,
+    /// Threads accepting HTTP requests.
+    pub http_threads: usize,
+    /// Requests with larger bodies are rejected.
+    pub max_body_bytes: usize,
+    /// How uploads are resized for a fresh model. A checkpoint brings its own.
+    pub preprocessing: Preprocessing,
+    /// Classifier to serve instead of the untrained head, e.g. the output of the
+    /// `train` binary. Its calibrated threshold enables `unsure` answers.
+    pub checkpoint: Option<PathBuf>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_batch: 8,
+            batch_window: Duration::from_millis(5),
+            http_threads: 4,
+            max_body_bytes: 16 * 1024 * 1024,
+            preprocessing: Preprocessing::default(),
+            checkpoint: None,
+        }
+    }
+}
+
+/// A running server. Dropping it stops the HTTP threads and the worker.
+pub struct InferenceServer {
+    http: Arc<tiny_http::Server>,
+    addr: SocketAddr,
+    stopping: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl InferenceServer {
+    /// Binds to `addr` (use port 0 for any free port) and starts serving.
+    pub fn start<B: AutodiffBackend>(addr: &str, config: ServerConfig) -> Result<Self, String>
+    where
+        Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+    {
+        let http = Arc::new(tiny_http::Server::http(addr).map_err(|error| error.to_string())?);
+        let addr = http
+            .server_addr()
+            .to_ip()
+            .ok_or("server is not listening on an IP address")?;
+        let worker = Arc::new(Worker::spawn::<B>(
+            config.max_batch,
+            config.batch_window,
+            config.preprocessing,
+            config.checkpoint.clone(),
+        )?);
+        let stopping = Arc::new(AtomicBool::new(false));
+
+        let threads = (0..config.http_threads.max(1))
+            .map(|_| {
+                let http = http.clone();
+                let worker = worker.clone();
+                let stopping = stopping.clone();
+                let max_body_bytes = config.max_body_bytes;
+                thread::spawn(move || {
+                    while let Ok(request) = http.recv() {
+                        if stopping.load(Ordering::SeqCst) {
+                            break;
+                        }
+                        handle(&worker, request, max_body_bytes);
+                    }
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            http,
+            addr,
+            stopping,
+            threads,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Blocks until the HTTP threads exit.
+    pub fn join(mut self) {
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for InferenceServer {
+    fn drop(&mut self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        // Each call wakes exactly one thread blocked in `recv`.
+        for _ in &self.threads {
+            self.http.unblock();
+        }
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn handle(worker: &Worker, mut request: Request, max_body_bytes: usize) {
+    let (status, body) = route(worker, &mut request, max_body_bytes);
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(
+            Header::from_bytes("Content-Type", "application/json")
+                .expect("static header is valid"),
+        );
+    let _ = request.respond(response);
+}
+
+fn route(worker: &Worker, request: &mut Request, max_body_bytes: usize) -> (u16, Value) {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+
+    match (request.method(), path) {
+        (Method::Get, "/health") => (200, json!({ "status": "ok" })),
+        (Method::Get, "/version") => (
+            200,
+            json!({
+                "model_version": worker.model_version(),
+                "crate_version": env!("CARGO_PKG_VERSION"),
+            }),
+        ),
+        (Method::Post, "/predict") => match read_upload(request, max_body_bytes) {
+            Ok(upload) => match worker.predict(upload.image) {
+                Ok(prediction) => (
+                    200,
+                    json!({
+                        "label": prediction.label,
+                        "class_index": prediction.class_index,
+                        "probabilities": prediction.probabilities,
+                        "unsure": prediction.unsure,
+                        "ood_score": prediction.ood_score,
+                        "model_version": prediction.model_version,
+                    }),
+                ),
+                Err(error) => (500, json!({ "error": error })),
+            },
+            Err(error) => (400, json!({ "error": error })),
+        },
+        (Method::Post, "/feedback") => {
+            let upload = match read_upload(request, max_body_bytes) {
+                Ok(upload) => upload,
+                Err(error) => return (400, json!({ "error": error })),
+            };
+            let label = upload.label.or_else(|| query_param(query, "label"));
+            let Some(is_hot_dog) = label.as_deref().and_then(parse_label) else {
+                return (
+                    400,
+                    json!({ "error": "feedback needs a label of hot_dog or not_hot_dog" }),
+                );
+            };
+
+            match worker.feedback(upload.image, is_hot_dog) {
+                Ok(feedback) => (
+                    200,
+                    json!({
+                        "loss": feedback.loss,
+                        "model_version": feedback.model_version,
+                    }),
+                ),
+                Err(error) => (500, json!({ "error": error })),
+            }
+        }
+        (_, "/health" | "/version" | "/predict" | "/feedback") => {
+            (405, json!({ "error": "method not allowed" }))
+        }
+        _ => (404, json!({ "error": format!("no route for {path}") })),
+    }
+}
+
+struct Upload {
+    image: DynamicImage,
+    label: Option<String>,
+}
+
+/// Reads the image (and an optional `label` form field) from a request body.
+fn read_upload(request: &mut Request, max_body_bytes: usize) -> Result<Upload, String> {
+    let content_type = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Content-Type"))
+        .map(|header| header.value.as_str().to_string());
+
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(max_body_bytes as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|error| format!("failed to read body: {error}"))?;
+    if body.len() > max_body_bytes {
+        return Err(format!("body larger than {max_body_bytes} bytes"));
+    }
+
+    let (image_bytes, label) = match content_type.as_deref().and_then(multipart::boundary) {
+        Some(boundary) => {
+            let parts = multipart::parse(&body, &boundary)?;
+            let label = parts
+                .iter()
+                .find(|part| part.name.as_deref() == Some("label"))
+                .map(|part| String::from_utf8_lossy(&part.data).trim().to_string());
+            let image = parts
+                .into_iter()
+                .find(|part| part.name.as_deref() == Some("image") || part.filename.is_some())
+                .ok_or("multipart body has no image part")?;
+            (image.data, label)
+        }
+        None => (body, None),
+    };
+
+    if image_bytes.is_empty() {
+        return Err("empty image".to_string());
+    }
+    let image = image::load_from_memory(&image_bytes)
+        .map_err(|error| format!("could not decode image: {error}"))?;
+
+    Ok(Upload { image, label })
+}
+
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+fn parse_label(label: &str) -> Option<bool> {
+    match label.to_ascii_lowercase().as_str() {
+        "hot_dog" | "hotdog" | "1" | "true" => Some(true),
+        "not_hot_dog" | "nothotdog" | "0" | "false" => Some(false),
+        _ => None,
+    }
+}
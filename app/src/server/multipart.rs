@@ -0,0 +1,86 @@
+/// One part of a `multipart/form-data` body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub name: Option<String>,
+    pub filename: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// Extracts the boundary from a `multipart/form-data` content type.
+pub fn boundary(content_type: &str) -> Option<String> {
+    let mut params = content_type.split(';').map(str::trim);
+    if !params.next()?.eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+
+    params.find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case("boundary")
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Splits a `multipart/form-data` body into its parts.
+pub fn parse(body: &[u8], boundary: &str) -> Result<Vec<Part>, String> {
+    let delimiter = format!("--{boundary}").into_bytes();
+    let mut parts = Vec::new();
+
+    let mut rest = match find(body, &delimiter) {
+        Some(start) => &body[start + delimiter.len()..],
+        None => return Err("multipart boundary not found".to_string()),
+    };
+
+    loop {
+        // `--` after a delimiter closes the body.
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+        rest = rest.strip_prefix(b"\r\n").unwrap_or(rest);
+
+        let end = find(rest, &delimiter).ok_or("unterminated multipart body")?;
+        let part = &rest[..end];
+        let part = part.strip_suffix(b"\r\n").unwrap_or(part);
+        parts.push(parse_part(part)?);
+
+        rest = &rest[end + delimiter.len()..];
+    }
+}
+
+fn parse_part(part: &[u8]) -> Result<Part, String> {
+    let header_end = find(part, b"\r\n\r\n").ok_or("multipart part without headers")?;
+    let headers = String::from_utf8_lossy(&part[..header_end]);
+
+    let mut name = None;
+    let mut filename = None;
+    for line in headers.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        if !key.trim().eq_ignore_ascii_case("content-disposition") {
+            continue;
+        }
+        for param in value.split(';').map(str::trim) {
+            if let Some((key, value)) = param.split_once('=') {
+                let value = value.trim_matches('"').to_string();
+                match key.trim() {
+                    "name" => name = Some(value),
+                    "filename" => filename = Some(value),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    Ok(Part {
+        name,
+        filename,
+        data: part[header_end + 4..].to_vec(),
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
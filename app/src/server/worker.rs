@@ -0,0 +1,229 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use burn::tensor::{
+    backend::{AutodiffBackend, Backend},
+    Data, Int, Tensor,
+};
+use image::DynamicImage;
+
+use crate::model::preprocessing::Preprocessing;
+use crate::model::squeezed_classifier::{image_to_tensor, HotNotDogClassifier};
+
+/// Result of one prediction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prediction {
+    /// A class label, or `Unsure` for out-of-distribution images.
+    pub label: &'static str,
+    pub class_index: usize,
+    pub probabilities: Vec<f32>,
+    /// `None` when the model has no calibrated out-of-distribution threshold.
+    pub ood_score: Option<f32>,
+    pub unsure: bool,
+    pub model_version: u64,
+}
+
+/// Result of one online training step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feedback {
+    pub loss: f32,
+    pub model_version: u64,
+}
+
+enum Job {
+    Predict {
+        image: DynamicImage,
+        reply: Sender<Prediction>,
+    },
+    Feedback {
+        image: DynamicImage,
+        is_hot_dog: bool,
+        reply: Sender<Feedback>,
+    },
+}
+
+/// Owns the classifier on its own thread and serves prediction and feedback jobs.
+///
+/// Predictions arriving within `batch_window` of each other are run as one batch of at
+/// most `max_batch` images. Feedback jobs are applied in arrival order, so a prediction
+/// queued after a feedback always sees the updated model.
+pub struct Worker {
+    jobs: Option<Sender<Job>>,
+    model_version: Arc<AtomicU64>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    /// Starts the worker with the classifier from `checkpoint`, or a fresh one using
+    /// `preprocessing`. Fails if the checkpoint cannot be loaded.
+    pub fn spawn<B: AutodiffBackend>(
+        max_batch: usize,
+        batch_window: Duration,
+        preprocessing: Preprocessing,
+        checkpoint: Option<PathBuf>,
+    ) -> Result<Self, String>
+    where
+        Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let (ready, started) = mpsc::channel();
+        let model_version = Arc::new(AtomicU64::new(0));
+        let version = model_version.clone();
+
+        let handle = thread::spawn(move || {
+            // The model is created on the worker so it never has to cross threads.
+            let model = match checkpoint {
+                Some(path) => HotNotDogClassifier::<B>::load_checkpoint(&path).map_err(|error| {
+                    format!("could not load checkpoint {}: {error}", path.display())
+                }),
+                None => Ok(HotNotDogClassifier::<B>::new().with_preprocessing(preprocessing)),
+            };
+            let mut model = match model {
+                Ok(model) => {
+                    let _ = ready.send(Ok(()));
+                    model
+                }
+                Err(error) => {
+                    let _ = ready.send(Err(error));
+                    return;
+                }
+            };
+            run::<B>(&mut model, receiver, max_batch.max(1), batch_window, &version);
+        });
+
+        started
+            .recv()
+            .map_err(|_| "inference worker stopped".to_string())??;
+
+        Ok(Self {
+            jobs: Some(sender),
+            model_version,
+            handle: Some(handle),
+        })
+    }
+
+    /// Number of feedback steps applied so far.
+    pub fn model_version(&self) -> u64 {
+        self.model_version.load(Ordering::SeqCst)
+    }
+
+    pub fn predict(&self, image: DynamicImage) -> Result<Prediction, String> {
+        let (reply, response) = mpsc::channel();
+        self.send(Job::Predict { image, reply })?;
+        response.recv().map_err(|_| "inference worker stopped".to_string())
+    }
+
+    pub fn feedback(&self, image: DynamicImage, is_hot_dog: bool) -> Result<Feedback, String> {
+        let (reply, response) = mpsc::channel();
+        self.send(Job::Feedback {
+            image,
+            is_hot_dog,
+            reply,
+        })?;
+        response.recv().map_err(|_| "inference worker stopped".to_string())
+    }
+
+    fn send(&self, job: Job) -> Result<(), String> {
+        self.jobs
+            .as_ref()
+            .ok_or("inference worker stopped")?
+            .send(job)
+            .map_err(|_| "inference worker stopped".to_string())
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        // Closing the channel ends the worker loop.
+        drop(self.jobs.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn run<B: AutodiffBackend>(
+    model: &mut HotNotDogClassifier<B>,
+    receiver: Receiver<Job>,
+    max_batch: usize,
+    batch_window: Duration,
+    version: &AtomicU64,
+) where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    let mut pending: Vec<(DynamicImage, Sender<Prediction>)> = Vec::new();
+
+    while let Ok(job) = receiver.recv() {
+        let deadline = Instant::now() + batch_window;
+        let mut next = Some(job);
+
+        while let Some(job) = next.take() {
+            match job {
+                Job::Predict { image, reply } => pending.push((image, reply)),
+                Job::Feedback {
+                    image,
+                    is_hot_dog,
+                    reply,
+                } => {
+                    predict_pending(model, &mut pending, version);
+                    let image: Tensor<B, 4> =
+                        image_to_tensor(&model.preprocessing().apply(&image));
+                    let label =
+                        Tensor::<B, 1, Int>::from_data(Data::from([is_hot_dog as i64]).convert());
+                    let loss = model.train(image, label);
+                    let model_version = version.fetch_add(1, Ordering::SeqCst) + 1;
+                    let _ = reply.send(Feedback {
+                        loss,
+                        model_version,
+                    });
+                }
+            }
+
+            if pending.len() >= max_batch {
+                predict_pending(model, &mut pending, version);
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            next = match receiver.recv_timeout(remaining) {
+                Ok(job) => Some(job),
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => None,
+            };
+        }
+
+        predict_pending(model, &mut pending, version);
+    }
+}
+
+fn predict_pending<B: AutodiffBackend>(
+    model: &HotNotDogClassifier<B>,
+    pending: &mut Vec<(DynamicImage, Sender<Prediction>)>,
+    version: &AtomicU64,
+) where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    if pending.is_empty() {
+        return;
+    }
+
+    let images: Vec<Tensor<B, 4>> = pending
+        .iter()
+        .map(|(image, _)| image_to_tensor(&model.preprocessing().apply(image)))
+        .collect();
+    let verdicts = model.predict_verdicts(Tensor::cat(images, 0));
+    let model_version = version.load(Ordering::SeqCst);
+
+    for ((_, reply), verdict) in pending.drain(..).zip(verdicts) {
+        let _ = reply.send(Prediction {
+            label: verdict.label,
+            class_index: verdict.class_index,
+            probabilities: verdict.probabilities,
+            ood_score: verdict.ood_score,
+            unsure: verdict.unsure,
+            model_version,
+        });
+    }
+}
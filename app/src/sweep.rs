@@ -0,0 +1,316 @@
+//! Hyperparameter search over [`OptimizerSettings`], batch size and freeze depth.
+//!
+//! Every trial is trained with [`fit`] and gets its own directory under the results
+//! directory:
+//!
+//! ```text
+//! <results>/trial-000/config.json    the sampled hyperparameters
+//! <results>/trial-000/metrics.json   history, best epoch and stop reason, or the error
+//! <results>/trial-000/best.bin       best checkpoint of the trial
+//! <results>/leaderboard.json         all trials, best first
+//! ```
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use burn::tensor::{
+    backend::{AutodiffBackend, Backend},
+    Data,
+};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::data::dataset::HotNotDogsData;
+use crate::model::preprocessing::Preprocessing;
+use crate::model::squeezed_classifier::{HotNotDogClassifier, OptimizerSettings};
+use crate::seed::{stream_rng, RngStream};
+use crate::training::{fit, EpochMetrics, StopReason, TrainingConfig, TrainingError};
+
+/// Candidate values for every hyperparameter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchSpace {
+    pub learning_rates: Vec<f64>,
+    pub momentums: Vec<f64>,
+    pub weight_decays: Vec<f64>,
+    pub batch_sizes: Vec<usize>,
+    /// Number of leading convolution layers kept frozen.
+    pub freeze_depths: Vec<usize>,
+}
+
+impl Default for SearchSpace {
+    fn default() -> Self {
+        Self {
+            learning_rates: vec![0.1, 0.03, 0.01],
+            momentums: vec![0.0, 0.9],
+            weight_decays: vec![0.0, 1e-4],
+            batch_sizes: vec![8, 16],
+            freeze_depths: vec![0, 26],
+        }
+    }
+}
+
+impl SearchSpace {
+    /// Every combination of the candidate values.
+    pub fn grid(&self) -> Vec<TrialConfig> {
+        let mut trials = Vec::new();
+        for &learning_rate in &self.learning_rates {
+            for &momentum in &self.momentums {
+                for &weight_decay in &self.weight_decays {
+                    for &batch_size in &self.batch_sizes {
+                        for &freeze_depth in &self.freeze_depths {
+                            trials.push(TrialConfig {
+                                id: trials.len(),
+                                optimizer: OptimizerSettings {
+                                    learning_rate,
+                                    momentum,
+                                    weight_decay,
+                                },
+                                batch_size,
+                                freeze_depth,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        trials
+    }
+
+    /// `count` combinations, each value drawn uniformly from its candidates.
+    pub fn random(&self, count: usize, seed: u64) -> Vec<TrialConfig> {
+        let mut rng = stream_rng(seed, RngStream::Sweep);
+        (0..count)
+            .filter_map(|id| {
+                Some(TrialConfig {
+                    id,
+                    optimizer: OptimizerSettings {
+                        learning_rate: *self.learning_rates.choose(&mut rng)?,
+                        momentum: *self.momentums.choose(&mut rng)?,
+                        weight_decay: *self.weight_decays.choose(&mut rng)?,
+                    },
+                    batch_size: *self.batch_sizes.choose(&mut rng)?,
+                    freeze_depth: *self.freeze_depths.choose(&mut rng)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// How trials are picked from the [`SearchSpace`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    Grid,
+    Random { trials: usize },
+}
+
+/// Hyperparameters of one trial.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrialConfig {
+    pub id: usize,
+    pub optimizer: OptimizerSettings,
+    pub batch_size: usize,
+    pub freeze_depth: usize,
+}
+
+impl TrialConfig {
+    pub fn dir(&self, results: &Path) -> PathBuf {
+        results.join(format!("trial-{:03}", self.id))
+    }
+}
+
+impl fmt::Display for TrialConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "lr {:<7} momentum {:<4} weight decay {:<7} batch {:<3} frozen {:<2}",
+            self.optimizer.learning_rate,
+            self.optimizer.momentum,
+            self.optimizer.weight_decay,
+            self.batch_size,
+            self.freeze_depth
+        )
+    }
+}
+
+/// Outcome of one trial, written to `metrics.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrialResult {
+    pub config: TrialConfig,
+    pub history: Vec<EpochMetrics>,
+    pub best: Option<EpochMetrics>,
+    /// `None` if the trial failed.
+    pub stop_reason: Option<StopReason>,
+    /// Why the trial failed. The sweep carries on with the next trial.
+    pub error: Option<String>,
+    pub duration: Duration,
+}
+
+impl TrialResult {
+    fn failed(config: TrialConfig, error: TrainingError, duration: Duration) -> Self {
+        Self {
+            config,
+            history: Vec::new(),
+            best: None,
+            stop_reason: None,
+            error: Some(error.to_string()),
+            duration,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SweepError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for SweepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SweepError::Io(error) => write!(f, "I/O error: {error}"),
+            SweepError::Json(error) => write!(f, "Could not write results: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SweepError {}
+
+impl From<std::io::Error> for SweepError {
+    fn from(error: std::io::Error) -> Self {
+        SweepError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for SweepError {
+    fn from(error: serde_json::Error) -> Self {
+        SweepError::Json(error)
+    }
+}
+
+/// All trials of a sweep, best first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub trials: Vec<TrialResult>,
+}
+
+impl Leaderboard {
+    /// Sorts by validation accuracy, then by validation loss. Trials without a
+    /// completed epoch, failed ones included, come last.
+    pub fn new(mut trials: Vec<TrialResult>) -> Self {
+        trials.sort_by(|a, b| match (&a.best, &b.best) {
+            (Some(a), Some(b)) => b
+                .val_accuracy
+                .total_cmp(&a.val_accuracy)
+                .then(a.val_loss.total_cmp(&b.val_loss)),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        Self { trials }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SweepError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Leaderboard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<5} {:<6} {:>9} {:>9} {:>7}  config",
+            "rank", "trial", "val acc", "val loss", "epochs"
+        )?;
+        for (rank, trial) in self.trials.iter().enumerate() {
+            let (accuracy, loss) = match &trial.best {
+                Some(best) => (
+                    format!("{:.1}%", best.val_accuracy * 100.0),
+                    format!("{:.4}", best.val_loss),
+                ),
+                None => ("-".to_string(), "-".to_string()),
+            };
+            write!(
+                f,
+                "{:<5} {:<6} {:>9} {:>9} {:>7}  {}",
+                rank + 1,
+                trial.config.id,
+                accuracy,
+                loss,
+                trial.history.len(),
+                trial.config
+            )?;
+            match &trial.error {
+                Some(error) => writeln!(f, "  failed: {error}")?,
+                None => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Trains one fresh classifier per trial and ranks them.
+///
+/// `base` supplies everything the search space does not cover (epochs, patience,
+/// monitored metric, seed); its checkpoint directory is replaced by the trial's.
+/// A trial whose training fails is recorded with its error and ranked last; only
+/// failing to write the results stops the sweep.
+pub fn run_sweep<B: AutodiffBackend>(
+    space: &SearchSpace,
+    strategy: Strategy,
+    train: &[HotNotDogsData],
+    validation: &[HotNotDogsData],
+    base: &TrainingConfig,
+    preprocessing: Preprocessing,
+    results: &Path,
+) -> Result<Leaderboard, SweepError>
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    let trials = match strategy {
+        Strategy::Grid => space.grid(),
+        Strategy::Random { trials } => space.random(trials, base.seed),
+    };
+    std::fs::create_dir_all(results)?;
+
+    let mut outcomes = Vec::with_capacity(trials.len());
+    for trial in trials {
+        println!("Trial {}: {trial}", trial.id);
+        let dir = trial.dir(results);
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("config.json"), serde_json::to_string_pretty(&trial)?)?;
+
+        let config = TrainingConfig {
+            batch_size: trial.batch_size,
+            checkpoint_dir: dir.clone(),
+            ..base.clone()
+        };
+        // Same seed for every trial, so they only differ in their hyperparameters.
+        let mut model = HotNotDogClassifier::<B>::new_seeded(base.seed)
+            .with_frozen_layers(trial.freeze_depth)
+            .with_optimizer(trial.optimizer)
+            .with_preprocessing(preprocessing);
+
+        let start = Instant::now();
+        let outcome = match fit(&mut model, train, validation, &config) {
+            Ok(report) => TrialResult {
+                config: trial,
+                history: report.history,
+                best: report.best,
+                stop_reason: Some(report.stop_reason),
+                error: None,
+                duration: start.elapsed(),
+            },
+            Err(error) => {
+                println!("Trial {} failed: {error}", trial.id);
+                TrialResult::failed(trial, error, start.elapsed())
+            }
+        };
+        std::fs::write(dir.join("metrics.json"), serde_json::to_string_pretty(&outcome)?)?;
+        outcomes.push(outcome);
+    }
+
+    let leaderboard = Leaderboard::new(outcomes);
+    leaderboard.save(&results.join("leaderboard.json"))?;
+    Ok(leaderboard)
+}
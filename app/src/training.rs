@@ -0,0 +1,303 @@
+//! Batch training of [`HotNotDogClassifier`] with early stopping.
+//!
+//! After every epoch the classifier is scored on a held-out validation set. Each
+//! improvement of the monitored metric is written to `best.bin` in the checkpoint
+//! directory, and training stops once `patience` epochs pass without one.
+use std::fmt;
+use std::path::PathBuf;
+
+use burn::tensor::{
+    backend::{AutodiffBackend, Backend},
+    Data, Int, Shape, Tensor,
+};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::data::dataset::HotNotDogsData;
+use crate::model::squeezed_classifier::{CheckpointError, HotNotDogClassifier};
+use crate::seed::{stream_rng, RngStream};
+
+/// Validation metric that decides which epoch is best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Monitor {
+    /// Lower is better.
+    Loss,
+    /// Higher is better.
+    Accuracy,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrainingConfig {
+    pub batch_size: usize,
+    pub max_epochs: usize,
+    /// Epochs without improvement before training stops.
+    pub patience: usize,
+    /// Smallest change of the monitored metric that counts as an improvement.
+    pub min_delta: f32,
+    pub monitor: Monitor,
+    /// Where `best.bin` and its metadata are written.
+    pub checkpoint_dir: PathBuf,
+    /// Seed for the per-epoch shuffle.
+    pub seed: u64,
+}
+
+impl Default for TrainingConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 8,
+            max_epochs: 20,
+            patience: 3,
+            min_delta: 1e-4,
+            monitor: Monitor::Loss,
+            checkpoint_dir: PathBuf::from("artifacts/checkpoints"),
+            seed: 42,
+        }
+    }
+}
+
+impl TrainingConfig {
+    pub fn best_checkpoint(&self) -> PathBuf {
+        self.checkpoint_dir.join("best.bin")
+    }
+}
+
+/// Metrics of one epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EpochMetrics {
+    pub epoch: usize,
+    pub train_loss: f32,
+    pub val_loss: f32,
+    pub val_accuracy: f32,
+}
+
+impl EpochMetrics {
+    fn score(&self, monitor: Monitor) -> f32 {
+        match monitor {
+            Monitor::Loss => self.val_loss,
+            Monitor::Accuracy => self.val_accuracy,
+        }
+    }
+}
+
+impl fmt::Display for EpochMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "epoch {:>3}: train loss {:.4}, val loss {:.4}, val accuracy {:.1}%",
+            self.epoch,
+            self.train_loss,
+            self.val_loss,
+            self.val_accuracy * 100.0
+        )
+    }
+}
+
+/// Why the training loop ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StopReason {
+    /// The monitored metric did not improve for `patience` epochs.
+    EarlyStopped { epochs_without_improvement: usize },
+    /// `max_epochs` was reached.
+    MaxEpochs,
+    /// The validation loss became NaN or infinite.
+    Diverged { epoch: usize },
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StopReason::EarlyStopped {
+                epochs_without_improvement,
+            } => write!(
+                f,
+                "stopped early, no improvement for {epochs_without_improvement} epochs"
+            ),
+            StopReason::MaxEpochs => write!(f, "reached the maximum number of epochs"),
+            StopReason::Diverged { epoch } => write!(f, "validation loss diverged in epoch {epoch}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TrainingReport {
+    pub history: Vec<EpochMetrics>,
+    /// Best epoch, `None` if no epoch finished.
+    pub best: Option<EpochMetrics>,
+    pub stop_reason: StopReason,
+    pub checkpoint: PathBuf,
+}
+
+impl fmt::Display for TrainingReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Training {}.", self.stop_reason)?;
+        match &self.best {
+            Some(best) => write!(f, "Best {best}, saved to {}", self.checkpoint.display()),
+            None => write!(f, "No epoch completed, nothing was saved."),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TrainingError {
+    /// The training or validation set is empty.
+    EmptySet(&'static str),
+    /// A setting that cannot work, e.g. fewer than two cross-validation folds.
+    Argument { name: &'static str, reason: String },
+    Checkpoint(CheckpointError),
+}
+
+impl fmt::Display for TrainingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrainingError::EmptySet(set) => write!(f, "The {set} set is empty"),
+            TrainingError::Argument { name, reason } => write!(f, "Invalid {name}: {reason}"),
+            TrainingError::Checkpoint(error) => write!(f, "Checkpoint error: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for TrainingError {}
+
+impl From<CheckpointError> for TrainingError {
+    fn from(error: CheckpointError) -> Self {
+        TrainingError::Checkpoint(error)
+    }
+}
+
+/// Trains `model` on `train` until the validation metric stops improving.
+///
+/// On return `model` holds the weights of the best epoch, not the last one.
+pub fn fit<B: AutodiffBackend>(
+    model: &mut HotNotDogClassifier<B>,
+    train: &[HotNotDogsData],
+    validation: &[HotNotDogsData],
+    config: &TrainingConfig,
+) -> Result<TrainingReport, TrainingError>
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    if train.is_empty() {
+        return Err(TrainingError::EmptySet("training"));
+    }
+    if validation.is_empty() {
+        return Err(TrainingError::EmptySet("validation"));
+    }
+
+    let checkpoint = config.best_checkpoint();
+    let batch_size = config.batch_size.max(1);
+    let mut rng = stream_rng(config.seed, RngStream::Epochs);
+    let mut order: Vec<&HotNotDogsData> = train.iter().collect();
+
+    let mut history = Vec::new();
+    let mut best: Option<EpochMetrics> = None;
+    let mut epochs_without_improvement = 0;
+    let mut stop_reason = StopReason::MaxEpochs;
+
+    for epoch in 1..=config.max_epochs {
+        order.shuffle(&mut rng);
+
+        let mut train_loss = 0.0;
+        for batch in order.chunks(batch_size) {
+            let (images, labels) = batch_tensors(model, batch);
+            train_loss += model.train_batch(images, labels) * batch.len() as f32;
+        }
+
+        let (val_loss, val_accuracy) = validate(model, validation, batch_size);
+        let metrics = EpochMetrics {
+            epoch,
+            train_loss: train_loss / train.len() as f32,
+            val_loss,
+            val_accuracy,
+        };
+        println!("{metrics}");
+        history.push(metrics);
+
+        if !val_loss.is_finite() {
+            stop_reason = StopReason::Diverged { epoch };
+            break;
+        }
+
+        if improves(&metrics, best.as_ref(), config) {
+            model.save_checkpoint(&checkpoint)?;
+            best = Some(metrics);
+            epochs_without_improvement = 0;
+        } else {
+            epochs_without_improvement += 1;
+            if epochs_without_improvement >= config.patience {
+                stop_reason = StopReason::EarlyStopped {
+                    epochs_without_improvement,
+                };
+                break;
+            }
+        }
+    }
+
+    if best.is_some() {
+        model.restore_weights(&checkpoint)?;
+    }
+
+    Ok(TrainingReport {
+        history,
+        best,
+        stop_reason,
+        checkpoint,
+    })
+}
+
+/// Mean loss and accuracy of `model` on `samples`.
+pub fn validate<B: AutodiffBackend>(
+    model: &HotNotDogClassifier<B>,
+    samples: &[HotNotDogsData],
+    batch_size: usize,
+) -> (f32, f32)
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    let mut loss = 0.0;
+    let mut correct = 0;
+    for batch in samples.chunks(batch_size.max(1)) {
+        let images = batch
+            .iter()
+            .map(|sample| model.load_image(&sample.image_path))
+            .collect();
+        let labels: Vec<usize> = batch.iter().map(|sample| sample.label as usize).collect();
+        let (batch_loss, batch_correct) = model.evaluate(Tensor::cat(images, 0), &labels);
+        loss += batch_loss * batch.len() as f32;
+        correct += batch_correct;
+    }
+
+    let count = samples.len().max(1) as f32;
+    (loss / count, correct as f32 / count)
+}
+
+fn improves(metrics: &EpochMetrics, best: Option<&EpochMetrics>, config: &TrainingConfig) -> bool {
+    let Some(best) = best else {
+        return true;
+    };
+    let (current, best) = (metrics.score(config.monitor), best.score(config.monitor));
+    match config.monitor {
+        Monitor::Loss => current < best - config.min_delta,
+        Monitor::Accuracy => current > best + config.min_delta,
+    }
+}
+
+fn batch_tensors<B: AutodiffBackend>(
+    model: &HotNotDogClassifier<B>,
+    batch: &[&HotNotDogsData],
+) -> (Tensor<B, 4>, Tensor<B, 1, Int>)
+where
+    Data<<B as Backend>::FloatElem, 3>: From<[[[f32; 224]; 224]; 3]>,
+{
+    let images = batch
+        .iter()
+        .map(|sample| model.load_image(&sample.image_path))
+        .collect();
+    let labels = Tensor::from_data(
+        Data::new(
+            batch.iter().map(|sample| sample.label as i64).collect(),
+            Shape::new([batch.len()]),
+        )
+        .convert(),
+    );
+    (Tensor::cat(images, 0), labels)
+}
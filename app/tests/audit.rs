@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+
+use image::{imageops::FilterType, DynamicImage, GrayImage, ImageFormat, Luma};
+
+use hotnotdog::data::audit::{audit, quarantine, AuditConfig, DuplicateKind, FileIssue};
+use hotnotdog::data::config::DatasetConfig;
+
+/// 144x128 image of 16 pixel blocks with pseudo random brightness, so different seeds
+/// give unrelated difference hashes.
+fn blocks(seed: u32) -> DynamicImage {
+    DynamicImage::ImageLuma8(GrayImage::from_fn(144, 128, |x, y| {
+        let block = (y / 16) * 9 + x / 16;
+        let mixed = (block ^ seed.wrapping_mul(0x9e37_79b9)).wrapping_mul(0x85eb_ca6b);
+        Luma([(mixed >> 24) as u8])
+    }))
+}
+
+fn save(image: &DynamicImage, path: &Path) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    image.save_with_format(path, ImageFormat::Png).unwrap();
+}
+
+/// A small dataset with one of every problem the audit looks for.
+fn dataset(root: &Path) -> DatasetConfig {
+    let _ = std::fs::remove_dir_all(root);
+    let train_hot = root.join("train/hot_dog");
+    let train_not = root.join("train/not_hot_dog");
+    let test_not = root.join("test/not_hot_dog");
+
+    save(&blocks(1), &train_hot.join("a.png"));
+    std::fs::copy(train_hot.join("a.png"), train_hot.join("a_copy.png")).unwrap();
+    std::fs::copy(train_hot.join("a.png"), test_not.join("leak.png")).unwrap();
+
+    save(&blocks(2), &train_not.join("b.png"));
+    let upscaled = blocks(2).resize_exact(288, 256, FilterType::Nearest);
+    save(&upscaled, &train_not.join("b_large.png"));
+
+    save(&blocks(3), &train_not.join("disguised.jpg"));
+    save(
+        &blocks(4).resize_exact(16, 16, FilterType::Triangle),
+        &train_hot.join("tiny.png"),
+    );
+    std::fs::write(train_hot.join("broken.jpg"), b"not an image").unwrap();
+    std::fs::write(train_not.join("notes.txt"), b"source: somewhere").unwrap();
+    save(&blocks(5), &test_not.join("c.png"));
+
+    DatasetConfig {
+        root: root.to_path_buf(),
+        ..DatasetConfig::default()
+    }
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("hotnotdog_{name}_{}", std::process::id()))
+}
+
+fn has_issue(issues: &[FileIssue], path: &Path, matches: fn(&FileIssue) -> bool) -> bool {
+    issues
+        .iter()
+        .any(|issue| issue.path() == path && matches(issue))
+}
+
+#[test]
+fn audit_finds_broken_files_duplicates_and_leaks() {
+    let root = temp_dir("audit");
+    let dataset = dataset(&root);
+    let report = audit(&dataset, &AuditConfig::default()).unwrap();
+    assert_eq!(report.files_scanned, 10);
+
+    let train_hot = root.join("train/hot_dog");
+    let train_not = root.join("train/not_hot_dog");
+    assert!(has_issue(
+        &report.issues,
+        &train_hot.join("broken.jpg"),
+        |issue| { matches!(issue, FileIssue::Undecodable { .. }) }
+    ));
+    assert!(has_issue(
+        &report.issues,
+        &train_not.join("disguised.jpg"),
+        |issue| {
+            matches!(
+                issue,
+                FileIssue::WrongExtension { actual, extension, .. }
+                    if actual == "Png" && extension == "png"
+            )
+        }
+    ));
+    assert_eq!(
+        report.rename_suggestions(),
+        vec![(
+            train_not.join("disguised.jpg"),
+            train_not.join("disguised.png")
+        )]
+    );
+    assert!(has_issue(
+        &report.issues,
+        &train_not.join("notes.txt"),
+        |issue| { matches!(issue, FileIssue::IgnoredExtension { .. }) }
+    ));
+    assert!(has_issue(
+        &report.issues,
+        &train_hot.join("tiny.png"),
+        |issue| {
+            matches!(
+                issue,
+                FileIssue::Tiny {
+                    width: 16,
+                    height: 16,
+                    ..
+                }
+            )
+        }
+    ));
+
+    assert_eq!(report.duplicates.len(), 2);
+    let exact = &report.duplicates[0];
+    assert_eq!(exact.kind, DuplicateKind::Exact);
+    assert_eq!(
+        exact.paths,
+        vec![
+            root.join("test/not_hot_dog/leak.png"),
+            train_hot.join("a.png"),
+            train_hot.join("a_copy.png"),
+        ]
+    );
+    assert!(exact.leaks());
+    assert!(exact.conflicting_labels);
+
+    let near = &report.duplicates[1];
+    assert_eq!(near.kind, DuplicateKind::Near);
+    assert_eq!(
+        near.paths,
+        vec![train_not.join("b.png"), train_not.join("b_large.png")]
+    );
+    assert!(!near.leaks());
+
+    std::fs::remove_dir_all(&root).ok();
+}
+
+#[test]
+fn quarantine_keeps_the_test_copy_of_a_leak() {
+    let root = temp_dir("audit_quarantine");
+    let dataset = dataset(&root);
+    let report = audit(&dataset, &AuditConfig::default()).unwrap();
+
+    let target = temp_dir("audit_quarantined");
+    let _ = std::fs::remove_dir_all(&target);
+    let moved = quarantine(&report, &target, false).unwrap();
+    let moved: Vec<PathBuf> = moved.into_iter().map(|(_, to)| to).collect();
+
+    assert!(root.join("test/not_hot_dog/leak.png").is_file());
+    assert!(!root.join("train/hot_dog/a.png").exists());
+    assert!(moved.contains(&target.join("train/hot_dog/a.png")));
+    assert!(moved.contains(&target.join("train/hot_dog/broken.jpg")));
+    // Near-duplicates stay unless asked for.
+    assert!(root.join("train/not_hot_dog/b_large.png").is_file());
+    // Images that decode stay, whatever their name or size.
+    assert!(root.join("train/not_hot_dog/disguised.jpg").is_file());
+    assert!(root.join("train/hot_dog/tiny.png").is_file());
+    assert_eq!(moved.len(), 3);
+
+    let again = audit(&dataset, &AuditConfig::default()).unwrap();
+    assert!(again.duplicates.iter().all(|group| !group.leaks()));
+
+    std::fs::remove_dir_all(&root).ok();
+    std::fs::remove_dir_all(&target).ok();
+}
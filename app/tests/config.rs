@@ -0,0 +1,80 @@
+use std::path::{Path, PathBuf};
+
+use hotnotdog::data::config::DatasetConfig;
+use hotnotdog::data::dataset::DatasetError;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// A config file in its own directory, so relative roots resolve against it.
+fn config_file(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hotnotdog_config_{name}_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("hotnotdog.toml");
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn flags_override_the_config_file_which_overrides_defaults() {
+    let path = config_file(
+        "precedence",
+        "root = \"images\"\nsplit = \"test\"\nseed = 7\nrecursive = false\n",
+    );
+    let path_arg = path.to_str().unwrap();
+
+    let from_file = DatasetConfig::from_args(args(&["--config", path_arg])).unwrap();
+    assert_eq!(from_file.root, path.parent().unwrap().join("images"));
+    assert_eq!(from_file.split, "test");
+    assert_eq!(from_file.seed, 7);
+    assert!(!from_file.recursive);
+    // Fields the file doesn't list keep their defaults.
+    assert_eq!(
+        from_file.hot_dog_dirs,
+        DatasetConfig::default().hot_dog_dirs
+    );
+
+    let overridden = DatasetConfig::from_args(args(&[
+        "--config",
+        path_arg,
+        "--split",
+        "valid",
+        "--seed",
+        "3",
+        "--data-root",
+        "elsewhere",
+    ]))
+    .unwrap();
+    assert_eq!(overridden.split, "valid");
+    assert_eq!(overridden.seed, 3);
+    // A root from the command line is taken as given, relative to the working directory.
+    assert_eq!(overridden.root, Path::new("elsewhere"));
+    assert!(!overridden.recursive);
+
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn the_default_root_is_relative_to_the_working_directory() {
+    assert_eq!(
+        DatasetConfig::default().root,
+        Path::new("artifacts/seefood_imgs")
+    );
+}
+
+#[test]
+fn flags_without_a_value_are_rejected() {
+    let missing = DatasetConfig::from_args(args(&["--seed"])).unwrap_err();
+    assert!(matches!(missing, DatasetError::Argument { flag, .. } if flag == "--seed"));
+
+    // The next flag is not taken as the root.
+    let followed_by_flag =
+        DatasetConfig::from_args(args(&["--data-root", "--seed", "3"])).unwrap_err();
+    assert!(
+        matches!(followed_by_flag, DatasetError::Argument { flag, .. } if flag == "--data-root")
+    );
+
+    let not_a_number = DatasetConfig::from_args(args(&["--seed", "many"])).unwrap_err();
+    assert!(matches!(not_a_number, DatasetError::Argument { flag, .. } if flag == "--seed"));
+}
@@ -0,0 +1,20 @@
+use burn::backend::{Autodiff, NdArray};
+
+use hotnotdog::data::config::DatasetConfig;
+use hotnotdog::data::dataset::load_data;
+use hotnotdog::model::squeezed_classifier::{load_image, HotNotDogClassifier};
+
+type Backend = Autodiff<NdArray>;
+
+#[test]
+fn repeated_predictions_on_one_image_are_identical() {
+    let sample = load_data(&DatasetConfig::default()).unwrap().remove(0);
+    let model = HotNotDogClassifier::<Backend>::new();
+    let image = load_image::<Backend>(&sample.image_path);
+
+    let first = model.predict_probabilities(image.clone());
+    for _ in 0..5 {
+        assert_eq!(model.predict_probabilities(image.clone()), first);
+        assert_eq!(model.predict(image.clone()), model.predict(image.clone()));
+    }
+}
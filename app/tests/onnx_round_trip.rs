@@ -0,0 +1,150 @@
+use std::path::PathBuf;
+
+use burn::backend::NdArray;
+use burn::tensor::{Distribution, Tensor};
+
+use hotnotdog::data::config::DatasetConfig;
+use hotnotdog::data::dataset::load_data;
+use hotnotdog::model::normalizer::Normalizer;
+use hotnotdog::model::onnx::{export_onnx, import_onnx, OnnxError, OnnxExportConfig};
+use hotnotdog::model::squeezed_classifier::load_image_with;
+use hotnotdog::model::squeezenet::{Classifier, Model};
+
+type Backend = NdArray;
+
+fn max_abs_difference(a: Tensor<Backend, 2>, b: Tensor<Backend, 2>) -> f32 {
+    (a - b).abs().max().into_scalar()
+}
+
+/// A file name no other test, or other run of this test binary, writes to.
+fn temp_onnx(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("hotnotdog_{name}_{}.onnx", std::process::id()))
+}
+
+/// tract, an ONNX runtime that shares no code with the exporter.
+mod onnx_runtime {
+    use std::path::Path;
+
+    use tract_onnx::prelude::*;
+
+    /// Logits of the graph at `path` for one `[1, 3, 224, 224]` image.
+    pub fn logits(path: &Path, image: Vec<f32>) -> Vec<f32> {
+        let model = tract_onnx::onnx()
+            .model_for_path(path)
+            .unwrap()
+            .with_input_fact(0, f32::fact([1, 3, 224, 224]).into())
+            .unwrap()
+            .into_optimized()
+            .unwrap()
+            .into_runnable()
+            .unwrap();
+        let input = tract_ndarray::Array4::from_shape_vec((1, 3, 224, 224), image).unwrap();
+        let outputs = model.run(tvec!(Tensor::from(input).into())).unwrap();
+        outputs[0]
+            .to_array_view::<f32>()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect()
+    }
+}
+
+#[test]
+fn exported_classifier_reimports_with_identical_logits() {
+    let squeezenet = Model::<Backend>::from_embedded();
+    let model = Classifier::<Backend>::new_from_squeezenet(&squeezenet);
+    let path = temp_onnx("round_trip");
+
+    export_onnx(&model, &path, OnnxExportConfig::default()).unwrap();
+    let imported = import_onnx::<Backend>(&path).unwrap();
+    assert!(!imported.baked_normalization);
+
+    let input = Normalizer::<Backend>::new()
+        .normalize(Tensor::random([2, 3, 224, 224], Distribution::Default));
+    let expected = model.forward(input.clone());
+    let actual = imported.model.forward(input);
+
+    assert!(max_abs_difference(expected, actual) < 1e-5);
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn baked_normalization_is_detected_on_import() {
+    let squeezenet = Model::<Backend>::from_embedded();
+    let model = Classifier::<Backend>::new_from_squeezenet(&squeezenet);
+    let path = temp_onnx("round_trip_normalized");
+
+    let config = OnnxExportConfig {
+        bake_normalization: true,
+    };
+    export_onnx(&model, &path, config).unwrap();
+    let imported = import_onnx::<Backend>(&path).unwrap();
+
+    assert!(imported.baked_normalization);
+    std::fs::remove_file(&path).ok();
+}
+
+#[test]
+fn onnx_runtime_agrees_with_burn_on_a_dataset_image() {
+    let squeezenet = Model::<Backend>::from_embedded();
+    let model = Classifier::<Backend>::new_from_squeezenet(&squeezenet);
+    let config = DatasetConfig::default();
+    let sample = load_data(&config).unwrap().remove(0);
+    let image = load_image_with::<Backend>(&sample.image_path, &config.preprocessing);
+
+    let normalized = Normalizer::<Backend>::new().normalize(image.clone());
+    let expected = model
+        .forward(normalized.clone())
+        .into_data()
+        .convert::<f32>()
+        .value;
+    let tolerance = 1e-3
+        * expected
+            .iter()
+            .fold(1.0f32, |max, logit| max.max(logit.abs()));
+
+    for bake_normalization in [false, true] {
+        let path = temp_onnx(&format!("runtime_{bake_normalization}"));
+        export_onnx(&model, &path, OnnxExportConfig { bake_normalization }).unwrap();
+
+        // A baked graph takes the raw image, the plain one the normalized image.
+        let input = if bake_normalization {
+            &image
+        } else {
+            &normalized
+        };
+        let actual = onnx_runtime::logits(&path, input.to_data().convert::<f32>().value);
+
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(&expected) {
+            assert!(
+                (actual - expected).abs() <= tolerance,
+                "baked {bake_normalization}: runtime gave {actual}, burn {expected}"
+            );
+        }
+        std::fs::remove_file(&path).ok();
+    }
+}
+
+#[test]
+fn graphs_with_other_nodes_are_rejected() {
+    let squeezenet = Model::<Backend>::from_embedded();
+    let model = Classifier::<Backend>::new_from_squeezenet(&squeezenet);
+    let path = temp_onnx("round_trip_altered");
+    export_onnx(&model, &path, OnnxExportConfig::default()).unwrap();
+
+    // Same length, so the protobuf stays well-formed and only the op type changes.
+    let mut bytes = std::fs::read(&path).unwrap();
+    let relu = bytes
+        .windows(4)
+        .position(|window| window == b"Relu")
+        .unwrap();
+    bytes[relu..relu + 4].copy_from_slice(b"Tanh");
+    std::fs::write(&path, bytes).unwrap();
+
+    assert!(matches!(
+        import_onnx::<Backend>(&path),
+        Err(OnnxError::Format(_))
+    ));
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,105 @@
+use burn::backend::{Autodiff, NdArray};
+use burn::tensor::{Data, Int, Shape, Tensor};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use hotnotdog::data::config::DatasetConfig;
+use hotnotdog::data::dataset::{load_data, load_split};
+use hotnotdog::evaluation::calibrate_ood;
+use hotnotdog::model::label::{LABELS_DOG, UNSURE_LABEL};
+use hotnotdog::model::ood::{OodDetector, OodMethod};
+use hotnotdog::model::squeezed_classifier::HotNotDogClassifier;
+
+type Backend = Autodiff<NdArray>;
+
+#[test]
+fn threshold_keeps_the_requested_coverage() {
+    let scores: Vec<f32> = (0..100).map(|score| score as f32).collect();
+    let detector = OodDetector::from_scores(OodMethod::default(), scores, 0.95).unwrap();
+
+    assert_eq!(detector.threshold, 5.0);
+    assert_eq!(detector.coverage, 0.95);
+    assert!(OodDetector::from_scores(OodMethod::MaxSoftmax, Vec::new(), 0.95).is_none());
+}
+
+#[test]
+fn scores_prefer_confident_logits() {
+    for method in [
+        OodMethod::MaxSoftmax,
+        OodMethod::Energy { temperature: 1.0 },
+    ] {
+        assert!(method.score(&[8.0, -2.0]) > method.score(&[0.1, 0.0]));
+    }
+}
+
+#[test]
+fn detector_turns_predictions_unsure() {
+    let sample = load_data(&DatasetConfig::default()).unwrap().remove(0);
+    let model = HotNotDogClassifier::<Backend>::new();
+    let image = model.load_image(&sample.image_path);
+
+    let verdict = model.predict_verdicts(image.clone()).remove(0);
+    assert!(!verdict.unsure);
+    assert!(verdict.ood_score.is_none());
+    assert!(LABELS_DOG.contains(&model.predict(image.clone())));
+
+    let strict = OodDetector {
+        method: OodMethod::default(),
+        threshold: f32::INFINITY,
+        coverage: 0.0,
+    };
+    let model = model.with_ood_detector(strict);
+    let verdict = model.predict_verdicts(image.clone()).remove(0);
+    assert!(verdict.unsure);
+    assert!(verdict.ood_score.unwrap().is_finite());
+    assert_eq!(model.predict(image), UNSURE_LABEL);
+
+    let path = std::env::temp_dir().join(format!("hotnotdog_ood_{}.bin", std::process::id()));
+    model.save_checkpoint(&path).unwrap();
+    let restored = HotNotDogClassifier::<Backend>::load_checkpoint(&path).unwrap();
+    assert_eq!(restored.ood_detector(), Some(&strict));
+}
+
+#[test]
+fn noise_and_flat_gray_fall_below_a_threshold_calibrated_on_the_dataset() {
+    let config = DatasetConfig::default();
+    let samples = load_split(&config, "train").unwrap();
+    let train = &samples[..16];
+
+    // A few steps on real images, so the scores mean what they do for a trained head.
+    let mut model = HotNotDogClassifier::<Backend>::new_seeded(config.seed);
+    for _ in 0..3 {
+        for batch in train.chunks(8) {
+            let images = batch
+                .iter()
+                .map(|sample| model.load_image(&sample.image_path))
+                .collect();
+            let labels = Data::new(
+                batch.iter().map(|sample| sample.label as i64).collect(),
+                Shape::new([batch.len()]),
+            );
+            model.train_batch(
+                Tensor::cat(images, 0),
+                Tensor::<Backend, 1, Int>::from_data(labels.convert()),
+            );
+        }
+    }
+
+    let detector = calibrate_ood(&model, train, OodMethod::default(), 0.95, 8).unwrap();
+    let model = model.with_ood_detector(detector);
+
+    let gray = Tensor::<Backend, 4>::ones([1, 3, 224, 224]).mul_scalar(0.5);
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let noise: Vec<f32> = (0..3 * 224 * 224).map(|_| rng.gen()).collect();
+    let noise = Tensor::<Backend, 4>::from_data(
+        Data::new(noise, Shape::new([1, 3, 224, 224])).convert(),
+    );
+    for (name, image) in [("flat gray", gray), ("uniform noise", noise)] {
+        let verdict = model.predict_verdicts(image).remove(0);
+        assert!(
+            verdict.unsure,
+            "{name} scored {:?}, threshold {}",
+            verdict.ood_score,
+            detector.threshold
+        );
+    }
+}
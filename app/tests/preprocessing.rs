@@ -0,0 +1,79 @@
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+
+use hotnotdog::data::config::DatasetConfig;
+use hotnotdog::model::preprocessing::{Preprocessing, ResizeFilter, ResizeMode};
+
+/// A 400x200 image, red on the left half and blue on the right.
+fn wide_image() -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(400, 200, |x, _| {
+        if x < 200 {
+            Rgb([255, 0, 0])
+        } else {
+            Rgb([0, 0, 255])
+        }
+    }))
+}
+
+#[test]
+fn every_mode_produces_the_model_input_size() {
+    for resize in [
+        ResizeMode::Exact,
+        ResizeMode::CenterCrop { resize: 256 },
+        ResizeMode::Letterbox { fill: [0, 0, 0] },
+    ] {
+        let preprocessing = Preprocessing {
+            resize,
+            filter: ResizeFilter::Nearest,
+        };
+        assert_eq!(preprocessing.apply(&wide_image()).dimensions(), (224, 224));
+    }
+}
+
+#[test]
+fn letterbox_pads_instead_of_stretching() {
+    let preprocessing = Preprocessing {
+        resize: ResizeMode::Letterbox { fill: [0, 255, 0] },
+        filter: ResizeFilter::Nearest,
+    };
+    let input = preprocessing.apply(&wide_image()).to_rgb8();
+
+    // 400x200 fits as 224x112, leaving 56 rows of padding above and below.
+    assert_eq!(input.get_pixel(112, 10), &Rgb([0, 255, 0]));
+    assert_eq!(input.get_pixel(10, 112), &Rgb([255, 0, 0]));
+    assert_eq!(input.get_pixel(213, 112), &Rgb([0, 0, 255]));
+}
+
+#[test]
+fn center_crop_keeps_the_middle() {
+    let input = Preprocessing {
+        resize: ResizeMode::CenterCrop { resize: 224 },
+        filter: ResizeFilter::Nearest,
+    }
+    .apply(&wide_image())
+    .to_rgb8();
+
+    // The shorter side becomes 224, so the crop spans the middle half of the width.
+    assert_eq!(input.get_pixel(0, 0), &Rgb([255, 0, 0]));
+    assert_eq!(input.get_pixel(223, 0), &Rgb([0, 0, 255]));
+}
+
+#[test]
+fn flags_override_preprocessing() {
+    let config = DatasetConfig::from_args(
+        ["--resize", "center_crop:288", "--filter", "triangle"]
+            .into_iter()
+            .map(String::from),
+    )
+    .unwrap();
+    assert_eq!(
+        config.preprocessing,
+        Preprocessing {
+            resize: ResizeMode::CenterCrop { resize: 288 },
+            filter: ResizeFilter::Triangle,
+        }
+    );
+
+    assert!(
+        DatasetConfig::from_args(["--resize", "squash"].into_iter().map(String::from)).is_err()
+    );
+}
@@ -0,0 +1,241 @@
+use std::path::PathBuf;
+
+use burn::backend::NdArray;
+use burn::module::{Module, ModuleVisitor, ParamId};
+use burn::tensor::{backend::Backend as BackendTrait, Data, Distribution, Shape, Tensor};
+
+use hotnotdog::model::low_precision::LowPrecisionClassifier;
+use hotnotdog::model::quantization::{
+    compare_precisions, export_classifier, load_classifier, Int8Weights, Precision,
+    QuantizationError,
+};
+use hotnotdog::model::squeezenet::Classifier;
+
+type Backend = NdArray;
+
+/// Every parameter of a classifier, flattened, in module traversal order.
+struct Parameters(Vec<Vec<f32>>);
+
+impl<B: BackendTrait> ModuleVisitor<B> for Parameters {
+    fn visit<const D: usize>(&mut self, _id: &ParamId, tensor: &Tensor<B, D>) {
+        self.0.push(tensor.to_data().convert::<f32>().value);
+    }
+}
+
+fn parameters(model: &Classifier<Backend>) -> Vec<Vec<f32>> {
+    let mut visitor = Parameters(Vec::new());
+    model.visit(&mut visitor);
+    visitor.0
+}
+
+/// A fixed, normalized-looking input image.
+fn image() -> Tensor<Backend, 4> {
+    let values: Vec<f32> = (0..3 * 224 * 224)
+        .map(|index| (index * 37 % 101) as f32 / 50.0 - 1.0)
+        .collect();
+    Tensor::from_data(Data::new(values, Shape::new([1, 3, 224, 224])).convert())
+}
+
+/// Squared error of `output` relative to the squared `reference`.
+fn relative_error(reference: &[f32], output: &[f32]) -> f32 {
+    let energy: f32 = reference.iter().map(|value| value * value).sum();
+    let error: f32 = reference
+        .iter()
+        .zip(output)
+        .map(|(reference, output)| (reference - output).powi(2))
+        .sum();
+    error / energy.max(1e-12)
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "hotnotdog_quantization_{name}_{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn int8_weights_are_within_half_a_step_of_the_originals() {
+    let model = Classifier::<Backend>::new();
+    let original = parameters(&model);
+    let weights = Int8Weights::quantize(&model, 1.0);
+    assert_eq!(weights.tensors.len(), original.len());
+
+    let dequantized = parameters(&weights.dequantize_into(model.clone()).unwrap());
+    for ((tensor, original), dequantized) in weights.tensors.iter().zip(&original).zip(&dequantized)
+    {
+        assert_eq!(tensor.shape.iter().product::<usize>(), original.len());
+        // Without clipping every weight is rounded to the nearest step.
+        let bound = tensor.scale / 2.0 + 1e-6;
+        for (value, restored) in original.iter().zip(dequantized) {
+            assert!(
+                (value - restored).abs() <= bound,
+                "{value} came back as {restored}, step {}",
+                tensor.scale
+            );
+        }
+    }
+}
+
+#[test]
+fn saved_int8_weights_load_unchanged_and_are_smaller() {
+    let dir = temp_dir("save_load");
+    let model = Classifier::<Backend>::new();
+    let weights = Int8Weights::quantize(&model, 0.99);
+
+    let path = dir.join("weights.bin");
+    weights.save(&path).unwrap();
+    let loaded = Int8Weights::load(&path).unwrap();
+    assert_eq!(loaded.clip, weights.clip);
+    assert_eq!(loaded.tensors.len(), weights.tensors.len());
+    for (loaded, saved) in loaded.tensors.iter().zip(&weights.tensors) {
+        assert_eq!(loaded.shape, saved.shape);
+        assert_eq!(loaded.scale, saved.scale);
+        assert_eq!(loaded.values, saved.values);
+    }
+
+    let full = export_classifier(&model, &dir, Precision::Full, &[]).unwrap();
+    let int8 = export_classifier(&model, &dir, Precision::Int8, &[]).unwrap();
+    let full_bytes = std::fs::metadata(&full).unwrap().len();
+    let int8_bytes = std::fs::metadata(&int8).unwrap().len();
+    assert!(
+        int8_bytes * 3 < full_bytes,
+        "{int8_bytes} vs {full_bytes} bytes"
+    );
+
+    // The reloaded classifier is a float model with the dequantized weights.
+    let reloaded = parameters(&load_classifier::<Backend>(&int8, Precision::Int8).unwrap());
+    let expected = parameters(
+        &Int8Weights::load(&int8)
+            .unwrap()
+            .dequantize_into(Classifier::new())
+            .unwrap(),
+    );
+    assert_eq!(reloaded, expected);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn calibration_keeps_the_logits_close_to_f32() {
+    let model = Classifier::<Backend>::new();
+    let calibration: Vec<Tensor<Backend, 4>> = (0..2)
+        .map(|_| Tensor::random([1, 3, 224, 224], Distribution::Default))
+        .collect();
+
+    let weights = Int8Weights::calibrate(&model, &calibration);
+    assert!(
+        (0.95..=1.0).contains(&weights.clip),
+        "clip {}",
+        weights.clip
+    );
+
+    let quantized = weights.dequantize_into(model.clone()).unwrap();
+    let batch = Tensor::cat(calibration, 0);
+    let reference = model
+        .forward(batch.clone())
+        .into_data()
+        .convert::<f32>()
+        .value;
+    let output = quantized.forward(batch).into_data().convert::<f32>().value;
+
+    let energy: f32 = reference.iter().map(|value| value * value).sum();
+    let error: f32 = reference
+        .iter()
+        .zip(&output)
+        .map(|(reference, output)| (reference - output).powi(2))
+        .sum();
+    assert!(
+        error <= 1e-2 * energy + 1e-8,
+        "squared error {error} against squared logits {energy}"
+    );
+}
+
+#[test]
+fn mismatched_int8_files_are_rejected() {
+    let dir = temp_dir("mismatch");
+    let model = Classifier::<Backend>::new();
+
+    let mut weights = Int8Weights::quantize(&model, 1.0);
+    weights.tensors.pop();
+    assert!(matches!(
+        weights.dequantize_into(model.clone()),
+        Err(QuantizationError::Format(_))
+    ));
+
+    let mut weights = Int8Weights::quantize(&model, 1.0);
+    weights.tensors.swap(0, 1);
+    assert!(matches!(
+        weights.dequantize_into(model),
+        Err(QuantizationError::Format(_))
+    ));
+
+    weights
+        .save(&dir.join(Precision::Int8.file_name()))
+        .unwrap();
+    assert!(matches!(
+        LowPrecisionClassifier::load::<Backend>(
+            &dir.join(Precision::Int8.file_name()),
+            Precision::Int8
+        ),
+        Err(QuantizationError::Format(_))
+    ));
+
+    let path = dir.join("not_int8.bin");
+    std::fs::write(&path, b"not an int8 checkpoint").unwrap();
+    assert!(matches!(
+        Int8Weights::load(&path),
+        Err(QuantizationError::Format(_))
+    ));
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn every_precision_runs_the_classifier_in_its_own_arithmetic() {
+    let model = Classifier::<Backend>::new();
+    let reference = model.forward(image()).into_data().convert::<f32>().value;
+
+    for (precision, tolerance) in [
+        (Precision::Full, 1e-6),
+        (Precision::Half, 1e-4),
+        (Precision::Int8, 5e-2),
+    ] {
+        let classifier = LowPrecisionClassifier::new(&model, precision);
+        assert_eq!(classifier.precision(), precision);
+        let logits = classifier.forward(image());
+        assert_eq!(logits.len(), 1);
+        let error = relative_error(&reference, &logits[0]);
+        assert!(
+            error <= tolerance,
+            "{precision} logits {:?} against {reference:?}, relative error {error}",
+            logits[0]
+        );
+    }
+}
+
+#[test]
+fn the_comparison_times_every_precision() {
+    let dir = temp_dir("compare");
+    let model = Classifier::<Backend>::new();
+    let images = vec![image().add_scalar(1.0).div_scalar(2.0)];
+
+    let comparison = compare_precisions(&model, &dir, &images, &images, &[0]).unwrap();
+    let precisions: Vec<Precision> = comparison
+        .reports
+        .iter()
+        .map(|report| report.precision)
+        .collect();
+    assert_eq!(precisions, Precision::ALL);
+    assert!(comparison.backend_latency_ms > 0.0);
+    for report in &comparison.reports {
+        assert!(report.mean_latency_ms > 0.0, "{report}");
+        assert!(report.file_bytes > 0, "{report}");
+    }
+    assert_eq!(comparison.reports[0].agreement, 1.0);
+    assert!((comparison.reports[0].speedup - 1.0).abs() < 1e-9);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
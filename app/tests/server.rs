@@ -0,0 +1,173 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+
+use burn::backend::{Autodiff, NdArray};
+use serde_json::Value;
+
+use hotnotdog::data::config::DatasetConfig;
+use hotnotdog::data::dataset::load_data;
+use hotnotdog::server::{InferenceServer, ServerConfig};
+
+type Backend = Autodiff<NdArray>;
+
+const BOUNDARY: &str = "hotnotdog-test-boundary";
+
+fn start() -> InferenceServer {
+    InferenceServer::start::<Backend>("127.0.0.1:0", ServerConfig::default()).unwrap()
+}
+
+fn sample_image() -> Vec<u8> {
+    let sample = load_data(&DatasetConfig::default()).unwrap().remove(0);
+    std::fs::read(sample.image_path).unwrap()
+}
+
+/// Sends one request over a fresh connection and returns the status and JSON body.
+fn request(
+    addr: SocketAddr,
+    method: &str,
+    path: &str,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> (u16, Value) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let mut head = format!(
+        "{method} {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        body.len()
+    );
+    if let Some(content_type) = content_type {
+        head.push_str(&format!("Content-Type: {content_type}\r\n"));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes()).unwrap();
+    stream.write_all(body).unwrap();
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+    let response = String::from_utf8(response).unwrap();
+
+    let status = response[9..12].parse().unwrap();
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+fn multipart(image: &[u8], label: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(
+        format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"label\"\r\n\r\n{label}\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(
+        format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"dog.jpg\"\r\n\
+             Content-Type: image/jpeg\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(image);
+    body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+    body
+}
+
+#[test]
+fn health_and_version() {
+    let server = start();
+
+    let (status, body) = request(server.addr(), "GET", "/health", None, b"");
+    assert_eq!(status, 200);
+    assert_eq!(body["status"], "ok");
+
+    let (status, body) = request(server.addr(), "GET", "/version", None, b"");
+    assert_eq!(status, 200);
+    assert_eq!(body["model_version"], 0);
+}
+
+#[test]
+fn predict_accepts_raw_bytes_and_multipart() {
+    let server = start();
+    let image = sample_image();
+
+    let (status, raw) = request(server.addr(), "POST", "/predict", Some("image/jpeg"), &image);
+    assert_eq!(status, 200);
+    assert!(raw["label"] == "HotDog" || raw["label"] == "NotHotDog");
+    assert_eq!(raw["probabilities"].as_array().unwrap().len(), 2);
+    // The untrained head has no calibrated threshold, so it is never unsure.
+    assert_eq!(raw["unsure"], false);
+
+    let content_type = format!("multipart/form-data; boundary={BOUNDARY}");
+    let (status, form) = request(
+        server.addr(),
+        "POST",
+        "/predict",
+        Some(&content_type),
+        &multipart(&image, "hot_dog"),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(form["probabilities"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn predict_rejects_bad_requests() {
+    let server = start();
+
+    let (status, _) = request(server.addr(), "POST", "/predict", None, b"not an image");
+    assert_eq!(status, 400);
+
+    let (status, _) = request(server.addr(), "GET", "/predict", None, b"");
+    assert_eq!(status, 405);
+
+    let (status, _) = request(server.addr(), "GET", "/nowhere", None, b"");
+    assert_eq!(status, 404);
+}
+
+#[test]
+fn feedback_updates_the_model_version() {
+    let server = start();
+    let image = sample_image();
+    let content_type = format!("multipart/form-data; boundary={BOUNDARY}");
+
+    let (status, body) = request(
+        server.addr(),
+        "POST",
+        "/feedback",
+        Some(&content_type),
+        &multipart(&image, "hot_dog"),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body["model_version"], 1);
+    assert!(body["loss"].as_f64().unwrap().is_finite());
+
+    let (status, _) = request(server.addr(), "POST", "/feedback?label=maybe", None, &image);
+    assert_eq!(status, 400);
+
+    let (status, body) = request(server.addr(), "POST", "/feedback?label=not_hot_dog", None, &image);
+    assert_eq!(status, 200);
+    assert_eq!(body["model_version"], 2);
+
+    let (_, body) = request(server.addr(), "GET", "/version", None, b"");
+    assert_eq!(body["model_version"], 2);
+}
+
+#[test]
+fn concurrent_predictions_are_batched_and_all_answered() {
+    let server = start();
+    let addr = server.addr();
+    let image = sample_image();
+
+    let clients: Vec<_> = (0..6)
+        .map(|_| {
+            let image = image.clone();
+            thread::spawn(move || request(addr, "POST", "/predict", None, &image))
+        })
+        .collect();
+
+    let responses: Vec<_> = clients.into_iter().map(|client| client.join().unwrap()).collect();
+    for (status, body) in &responses {
+        assert_eq!(*status, 200);
+        assert!(body["label"] == "HotDog" || body["label"] == "NotHotDog");
+        // Batched or not, inference mode makes every answer for one image the same.
+        assert_eq!(body["label"], responses[0].1["label"]);
+    }
+}
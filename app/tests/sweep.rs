@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+
+use burn::backend::{Autodiff, NdArray};
+
+use hotnotdog::model::preprocessing::Preprocessing;
+use hotnotdog::sweep::{run_sweep, SearchSpace, Strategy, TrialConfig};
+use hotnotdog::training::TrainingConfig;
+
+type Backend = Autodiff<NdArray>;
+
+fn space() -> SearchSpace {
+    SearchSpace {
+        learning_rates: vec![0.1, 0.01],
+        momentums: vec![0.0, 0.9],
+        weight_decays: vec![0.0],
+        batch_sizes: vec![4, 8, 16],
+        freeze_depths: vec![0, 26],
+    }
+}
+
+/// The hyperparameters of a trial as comparable integers.
+fn key(trial: &TrialConfig) -> (u64, u64, u64, usize, usize) {
+    (
+        trial.optimizer.learning_rate.to_bits(),
+        trial.optimizer.momentum.to_bits(),
+        trial.optimizer.weight_decay.to_bits(),
+        trial.batch_size,
+        trial.freeze_depth,
+    )
+}
+
+#[test]
+fn grid_holds_every_combination_once() {
+    let space = space();
+    let grid = space.grid();
+
+    assert_eq!(grid.len(), 2 * 2 * 3 * 2);
+    assert_eq!(
+        grid.iter().map(|trial| trial.id).collect::<Vec<_>>(),
+        (0..grid.len()).collect::<Vec<_>>()
+    );
+    let unique: HashSet<_> = grid.iter().map(key).collect();
+    assert_eq!(unique.len(), grid.len());
+    for trial in &grid {
+        assert!(space
+            .learning_rates
+            .contains(&trial.optimizer.learning_rate));
+        assert!(space.momentums.contains(&trial.optimizer.momentum));
+        assert!(space.batch_sizes.contains(&trial.batch_size));
+        assert!(space.freeze_depths.contains(&trial.freeze_depth));
+    }
+}
+
+#[test]
+fn random_sampling_is_determined_by_the_seed() {
+    let space = space();
+    let first = space.random(20, 7);
+
+    assert_eq!(first.len(), 20);
+    assert_eq!(first, space.random(20, 7));
+    assert_ne!(first, space.random(20, 8));
+    let grid: HashSet<_> = space.grid().iter().map(key).collect();
+    assert!(first.iter().all(|trial| grid.contains(&key(trial))));
+}
+
+#[test]
+fn failing_trials_are_recorded_and_the_sweep_continues() {
+    let results = std::env::temp_dir().join(format!("hotnotdog_sweep_{}", std::process::id()));
+    let space = SearchSpace {
+        learning_rates: vec![0.1, 0.01],
+        momentums: vec![0.0],
+        weight_decays: vec![0.0],
+        batch_sizes: vec![4],
+        freeze_depths: vec![26],
+    };
+
+    // Nothing to train on, so every trial fails.
+    let leaderboard = run_sweep::<Backend>(
+        &space,
+        Strategy::Grid,
+        &[],
+        &[],
+        &TrainingConfig::default(),
+        Preprocessing::default(),
+        &results,
+    )
+    .unwrap();
+
+    assert_eq!(leaderboard.trials.len(), 2);
+    for trial in &leaderboard.trials {
+        assert!(trial.error.is_some());
+        assert!(trial.best.is_none() && trial.stop_reason.is_none());
+        assert!(trial.config.dir(&results).join("metrics.json").is_file());
+    }
+    assert!(results.join("leaderboard.json").is_file());
+
+    std::fs::remove_dir_all(&results).ok();
+}
@@ -0,0 +1,103 @@
+use burn::backend::{Autodiff, NdArray};
+
+use hotnotdog::cross_validation::cross_validate;
+use hotnotdog::data::config::DatasetConfig;
+use hotnotdog::data::dataset::{load_split, split_validation, stratified_folds, HotNotDogsData};
+use hotnotdog::model::squeezed_classifier::{HotNotDogClassifier, OptimizerSettings};
+use hotnotdog::training::{fit, StopReason, TrainingConfig, TrainingError};
+
+type Backend = Autodiff<NdArray>;
+
+fn hot_dog_share(samples: &[HotNotDogsData]) -> f32 {
+    samples.iter().filter(|sample| sample.label).count() as f32 / samples.len() as f32
+}
+
+#[test]
+fn validation_split_is_stratified_and_disjoint() {
+    let config = DatasetConfig::default();
+    let samples = load_split(&config, "train").unwrap();
+    let (train, validation) = split_validation(&samples, 0.2, config.seed);
+
+    assert_eq!(train.len() + validation.len(), samples.len());
+    assert!(validation
+        .iter()
+        .all(|held_out| !train.iter().any(|sample| sample.image_path == held_out.image_path)));
+    assert!((hot_dog_share(&train) - hot_dog_share(&validation)).abs() < 0.05);
+
+    let (_, again) = split_validation(&samples, 0.2, config.seed);
+    assert_eq!(validation, again);
+}
+
+#[test]
+fn folds_partition_the_split_with_balanced_classes() {
+    let config = DatasetConfig::default();
+    let samples = load_split(&config, "train").unwrap();
+    let folds = stratified_folds(&samples, 5, config.seed);
+
+    assert_eq!(folds.len(), 5);
+    assert_eq!(folds.iter().map(Vec::len).sum::<usize>(), samples.len());
+    let mut paths: Vec<&str> = folds
+        .iter()
+        .flatten()
+        .map(|sample| sample.image_path.as_str())
+        .collect();
+    paths.sort();
+    paths.dedup();
+    assert_eq!(paths.len(), samples.len());
+
+    let overall = hot_dog_share(&samples);
+    for fold in &folds {
+        assert!((hot_dog_share(fold) - overall).abs() < 0.05);
+    }
+}
+
+#[test]
+fn fit_keeps_the_best_epoch_and_reports_why_it_stopped() {
+    let dataset = DatasetConfig::default();
+    let samples: Vec<HotNotDogsData> = load_split(&dataset, "train").unwrap();
+    let (train, validation) = split_validation(&samples[..12], 0.34, dataset.seed);
+
+    let config = TrainingConfig {
+        batch_size: 4,
+        max_epochs: 3,
+        patience: 1,
+        checkpoint_dir: std::env::temp_dir()
+            .join(format!("hotnotdog_training_{}", std::process::id())),
+        ..TrainingConfig::default()
+    };
+    let mut model = HotNotDogClassifier::<Backend>::new_seeded(dataset.seed);
+    let report = fit(&mut model, &train, &validation, &config).unwrap();
+
+    let best = report.best.unwrap();
+    assert!(report.checkpoint.is_file());
+    assert!(report
+        .history
+        .iter()
+        .all(|epoch| epoch.val_loss >= best.val_loss - config.min_delta));
+    match report.stop_reason {
+        StopReason::MaxEpochs => assert_eq!(report.history.len(), config.max_epochs),
+        StopReason::EarlyStopped {
+            epochs_without_improvement,
+        } => assert_eq!(epochs_without_improvement, config.patience),
+        StopReason::Diverged { .. } => panic!("training diverged"),
+    }
+    std::fs::remove_dir_all(&config.checkpoint_dir).ok();
+}
+
+#[test]
+fn cross_validation_needs_two_folds() {
+    let dataset = DatasetConfig::default();
+    let samples = load_split(&dataset, "train").unwrap();
+
+    for k in [0, 1] {
+        let result = cross_validate::<Backend>(
+            &samples,
+            k,
+            0.2,
+            &TrainingConfig::default(),
+            OptimizerSettings::default(),
+            dataset.preprocessing,
+        );
+        assert!(matches!(result, Err(TrainingError::Argument { .. })));
+    }
+}
@@ -0,0 +1,50 @@
+use burn::backend::{Autodiff, NdArray};
+
+use hotnotdog::data::config::DatasetConfig;
+use hotnotdog::data::dataset::load_data;
+use hotnotdog::model::preprocessing::Preprocessing;
+use hotnotdog::model::squeezed_classifier::{
+    decode_image, image_to_tensor, resize_image, HotNotDogClassifier,
+};
+use hotnotdog::model::tta::{CropMode, TestTimeAugmentation};
+
+type Backend = Autodiff<NdArray>;
+
+#[test]
+fn views_match_the_configuration() {
+    let sample = load_data(&DatasetConfig::default()).unwrap().remove(0);
+    let image = decode_image(&sample.image_path);
+
+    for tta in [
+        TestTimeAugmentation::default(),
+        TestTimeAugmentation::flips(),
+        TestTimeAugmentation::full(),
+        TestTimeAugmentation {
+            horizontal_flip: false,
+            crops: CropMode::Center,
+            scales: vec![1.0, 1.3],
+        },
+    ] {
+        for preprocessing in [Preprocessing::default(), Preprocessing::imagenet()] {
+            let views = tta.views(&image, &preprocessing);
+            assert_eq!(views.len(), tta.view_count());
+            assert!(views
+                .iter()
+                .all(|view| view.width() == 224 && view.height() == 224));
+        }
+    }
+}
+
+#[test]
+fn single_view_matches_plain_prediction() {
+    let sample = load_data(&DatasetConfig::default()).unwrap().remove(0);
+    let image = decode_image(&sample.image_path);
+    let model = HotNotDogClassifier::<Backend>::new();
+
+    let tta = model.predict_tta(&image, &TestTimeAugmentation::default());
+    let plain = model.predict_probabilities(image_to_tensor(&resize_image(&image)));
+
+    assert_eq!(tta, plain[0]);
+    let averaged = model.predict_tta(&image, &TestTimeAugmentation::full());
+    assert!((averaged.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+}
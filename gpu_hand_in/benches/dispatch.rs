@@ -0,0 +1,102 @@
+//! Dispatch overhead of a small kernel with and without the kernel cache.
+//!
+//! Runs on the software (fallback) adapter when there is one, so the numbers do not
+//! depend on the GPU of the machine:
+//!
+//! ```text
+//! cargo bench --bench dispatch -- [--iterations 200] [--elements 1024]
+//! ```
+use std::time::{Duration, Instant};
+
+use gpu_hand_in::shaders;
+use gpu_hand_in::{
+    initialize_fallback_gpu, initialize_gpu, run_compute_shader, GPUHandles, GPUVector, Uniform,
+};
+
+fn main() {
+    env_logger::init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let iterations: usize = flag(&args, "--iterations")
+        .map(|value| value.parse().expect("--iterations expects a number"))
+        .unwrap_or(200);
+    let elements: usize = flag(&args, "--elements")
+        .map(|value| value.parse().expect("--elements expects a number"))
+        .unwrap_or(1024);
+
+    let handles: GPUHandles = match pollster::block_on(initialize_fallback_gpu()) {
+        Some(handles) => handles,
+        None => {
+            println!("No fallback adapter, using the default one.");
+            pollster::block_on(initialize_gpu()).expect("Was unable to find a compatible GPU!")
+        }
+    };
+    println!(
+        "Adapter: {} ({:?}, {:?})",
+        handles.adapter_info.name, handles.adapter_info.device_type, handles.adapter_info.backend
+    );
+
+    let uniform: Uniform = Uniform::new(&handles, elements, 0, 0, 0);
+    let input_a: GPUVector = GPUVector::new(&handles, vec![1.0; elements], "input_a", false);
+    let input_b: GPUVector = GPUVector::new(&handles, vec![2.0; elements], "input_b", false);
+    let mut output: GPUVector = GPUVector::new(&handles, vec![0.0; elements], "output", true);
+    let mut dispatch = |handles: &GPUHandles| {
+        run_compute_shader(
+            handles,
+            32,
+            elements.div_ceil(32) as u32,
+            1,
+            1,
+            shaders::VECTOR_ADD,
+            "vector_add",
+            &uniform,
+            &input_a,
+            &input_b,
+            &mut output,
+        )
+        .unwrap_or_else(|error| panic!("{error}"))
+    };
+
+    // Warm up the driver before timing anything.
+    dispatch(&handles);
+
+    // Emptying the cache before every dispatch recompiles like every call used to.
+    let uncached: Duration = time(iterations, || {
+        handles.kernels.clear();
+        dispatch(&handles);
+    });
+    let cached: Duration = time(iterations, || dispatch(&handles));
+
+    let per_dispatch = |total: Duration| total / iterations as u32;
+    println!("{iterations} dispatches of vector_add over {elements} elements");
+    println!(
+        "  recompiled every time: {:?} per dispatch",
+        per_dispatch(uncached)
+    );
+    println!(
+        "  cached kernel:         {:?} per dispatch",
+        per_dispatch(cached)
+    );
+    println!(
+        "  speedup:               {:.1}x",
+        uncached.as_secs_f64() / cached.as_secs_f64()
+    );
+    println!(
+        "  cache hits {}, misses {}",
+        handles.kernels.hits(),
+        handles.kernels.misses()
+    );
+}
+
+fn time(iterations: usize, mut run: impl FnMut()) -> Duration {
+    let start: Instant = Instant::now();
+    for _ in 0..iterations {
+        run();
+    }
+    start.elapsed()
+}
+
+fn flag(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1).cloned())
+}
@@ -0,0 +1,96 @@
+//! Chains two kernels on the GPU: `left = a + b`, then `output = left * right`.
+//!
+//! Both kernels are submitted before anything is read back, and `left` never leaves
+//! the GPU. Only the product is downloaded, asynchronously.
+//!
+//! ```text
+//! cargo run --release --example chained
+//! ```
+use gpu_hand_in::shaders;
+use gpu_hand_in::utility::{are_vectors_equivalent, mean_square_error};
+use gpu_hand_in::{initialize_gpu, GPUHandles, GPUVector, KernelError, KernelInvocation, Uniform};
+
+fn add_then_multiply_cpu(
+    a: &[f32],
+    b: &[f32],
+    right: &[f32],
+    rows: usize,
+    inner: usize,
+    columns: usize,
+) -> Vec<f32> {
+    let left: Vec<f32> = a.iter().zip(b).map(|(a, b)| a + b).collect();
+    let mut output: Vec<f32> = vec![0.0; rows * columns];
+    for row in 0..rows {
+        for column in 0..columns {
+            output[row * columns + column] = (0..inner)
+                .map(|index| left[row * inner + index] * right[index * columns + column])
+                .sum();
+        }
+    }
+    output
+}
+
+async fn add_then_multiply(handles: &GPUHandles) -> Result<bool, KernelError> {
+    let (rows, inner, columns): (usize, usize, usize) = (64, 48, 80);
+    let a: Vec<f32> = (0..rows * inner).map(|x| (x % 13) as f32 * 0.5).collect();
+    let b: Vec<f32> = (0..rows * inner).map(|x| (x % 5) as f32 - 2.0).collect();
+    let right: Vec<f32> = (0..inner * columns)
+        .map(|x| (x % 7) as f32 * 0.25)
+        .collect();
+    let ground_truth: Vec<f32> = add_then_multiply_cpu(&a, &b, &right, rows, inner, columns);
+
+    let add_uniform: Uniform = Uniform::new(handles, rows * inner, 0, 0, 0);
+    let multiply_uniform: Uniform = Uniform::new(handles, columns, rows, inner, 0);
+    let a: GPUVector = GPUVector::new(handles, a, "a", false);
+    let b: GPUVector = GPUVector::new(handles, b, "b", false);
+    let right: GPUVector = GPUVector::new(handles, right, "right", false);
+    // No staging buffers, neither is read back by its kernel.
+    let mut left: GPUVector = GPUVector::zeros(handles, &[rows, inner], "left", false);
+    let mut output: GPUVector = GPUVector::zeros(handles, &[rows, columns], "output", false);
+
+    KernelInvocation::new(handles, shaders::VECTOR_ADD, "vector_add")?
+        .uniform(&add_uniform)
+        .read(&a)
+        .read(&b)
+        .read_write(&mut left)
+        .threads((rows * inner) as u32, 1, 1)
+        .submit()?;
+
+    // The queue runs submissions in order, so this sees the finished sum.
+    let multiplied = KernelInvocation::new(
+        handles,
+        shaders::MATRIX_MULTIPLICATION_NAIVE,
+        "matmul_naive",
+    )?
+    .uniform(&multiply_uniform)
+    .read(&left)
+    .read(&right)
+    .read_write(&mut output)
+    .threads(columns as u32, rows as u32, 1)
+    .submit()?;
+
+    multiplied.done(handles).await;
+    let data: &[f32] = output.download_async(handles).await?;
+
+    println!(
+        "add then multiply MSE: {}",
+        mean_square_error(&ground_truth, data)
+    );
+    let success: bool = are_vectors_equivalent(&ground_truth, data);
+    println!("add then multiply success: {}!", success);
+    Ok(success)
+}
+
+fn main() {
+    // Initialize the env_logger to get usueful messages from wgpu.
+    env_logger::init();
+
+    // Use pollster::block_on to block on async functions.
+    let handles: GPUHandles =
+        pollster::block_on(initialize_gpu()).expect("Was unable to find a compatible GPU!");
+    println!("Running on {}", handles.adapter_info.name);
+
+    let success: bool =
+        pollster::block_on(add_then_multiply(&handles)).unwrap_or_else(|error| panic!("{error}"));
+    assert!(success);
+}
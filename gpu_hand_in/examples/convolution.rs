@@ -0,0 +1,246 @@
+//! 1D convolution on the GPU in three variants: naive, with workgroup memory and on a
+//! zero padded signal, each timed and checked against the CPU.
+//!
+//! Every combination of the given signal lengths, filter sizes and block sizes is run.
+//! The block size is the workgroup size, specialized into the shaders together with
+//! the workgroup memory for the filter by [`ConvolutionConfig`].
+//!
+//! ```text
+//! cargo run --release --example convolution -- [--signal-length 1000000]
+//!     [--filter-size 19] [--block-size 32,64,128,256] [--repetitions 10]
+//! ```
+use std::time::Duration;
+
+use gpu_hand_in::utility::{are_vectors_equivalent, mean_square_error};
+use gpu_hand_in::{
+    benchmark, initialize_gpu, Convolution, ConvolutionConfig, ConvolutionKernel, GPUHandles,
+    GPUVector, KernelError, Uniform,
+};
+
+// The length of filter is assumed to be oddly number, i.e. 1, 3, 5, 7, 9, 11
+fn convolution_cpu(signal: &[f32], filter: &[f32]) -> Vec<f32> {
+    let filter_offset = filter.len() / 2;
+    let mut output: Vec<f32> = vec![0.0; signal.len()];
+    for (signal_index, output_element) in output.iter_mut().enumerate() {
+        for (filter_index, filter_element) in filter.iter().enumerate() {
+            let offset_signal_index: i64 =
+                signal_index as i64 - filter_offset as i64 + filter_index as i64;
+            if -1 < offset_signal_index && offset_signal_index < signal.len() as i64 {
+                *output_element += signal[offset_signal_index as usize] * filter_element;
+            }
+        }
+    }
+
+    output
+}
+
+fn test_ground_truth() -> bool {
+    let signal: Vec<f32> = vec![1.0, 1.0, 1.0, 1.0, 1.0];
+    let filter: Vec<f32> = vec![0.25, 0.5, -0.25];
+    let ground_truth_output: Vec<f32> = vec![0.25, 0.5, 0.5, 0.5, 0.75];
+
+    let output: Vec<f32> = convolution_cpu(&signal, &filter);
+
+    assert!(output.len() == ground_truth_output.len());
+    for index in 0..ground_truth_output.len() {
+        if 0.00001 < (output[index] - ground_truth_output[index]).abs() {
+            println!("Provided output: {:?}", output);
+            println!("Ground truth output: {:?}", ground_truth_output);
+            return false;
+        }
+    }
+
+    true
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ConvolutionParameters {
+    signal_length: usize,
+    filter_size: usize,
+    block_size: usize,
+}
+
+fn report(
+    name: &str,
+    parameters: ConvolutionParameters,
+    time: Duration,
+    ground_truth: &[f32],
+    data: &[f32],
+) -> bool {
+    let success: bool = are_vectors_equivalent(ground_truth, data);
+    println!(
+        "{:>8} {:>7} {:>6}  {:<7} {:>9.3} ms  MSE {:<12e} success: {}",
+        parameters.signal_length,
+        parameters.filter_size,
+        parameters.block_size,
+        name,
+        time.as_secs_f64() * 1000.0,
+        mean_square_error(ground_truth, data),
+        success
+    );
+    success
+}
+
+fn convolution(
+    handles: &GPUHandles,
+    parameters: ConvolutionParameters,
+    repetitions: usize,
+) -> Result<bool, KernelError> {
+    let ConvolutionParameters {
+        signal_length,
+        filter_size,
+        block_size,
+    } = parameters;
+    assert!(filter_size % 2 == 1, "The filter size has to be odd");
+
+    // Small values, so the comparison with a fixed epsilon is meaningful.
+    let signal: Vec<f32> = (0..signal_length).map(|x| (x % 17) as f32 - 8.0).collect();
+    let filter: Vec<f32> = (0..filter_size)
+        .map(|x| (x % 5) as f32 * 0.1 - 0.2)
+        .collect();
+    let ground_truth: Vec<f32> = convolution_cpu(&signal, &filter);
+
+    let convolution: Convolution = Convolution::new(signal_length, filter_size);
+    let config = |kernel: ConvolutionKernel| {
+        ConvolutionConfig::new(kernel)
+            .with_block_size(block_size as u32)
+            .with_max_filter_size(filter_size as u32)
+    };
+
+    let uniform: Uniform = convolution.uniform(handles);
+    let signal_gpu: GPUVector = GPUVector::new(handles, signal.clone(), "signal", false);
+    let filter_gpu: GPUVector = GPUVector::new(handles, filter, "filter", false);
+    let mut success: bool = true;
+
+    // 1) One thread per output element, reading straight from the storage buffers.
+    // 2) The block's part of the signal and the filter are staged in workgroup memory.
+    for (name, kernel) in [
+        ("naive", ConvolutionKernel::Naive),
+        ("shared", ConvolutionKernel::Shared),
+    ] {
+        let mut output: GPUVector = GPUVector::zeros(handles, &[signal_length], "output", true);
+        let time: Duration = benchmark(handles, repetitions, || {
+            convolution
+                .invocation(
+                    handles,
+                    config(kernel),
+                    &uniform,
+                    &signal_gpu,
+                    &filter_gpu,
+                    &mut output,
+                )?
+                .submit()
+        })?;
+        let data: &[f32] = output.download(handles)?;
+        success &= report(name, parameters, time, &ground_truth, data);
+    }
+
+    // 3) Zero padded by (filter_size - 1) / 2 in front and up to whole blocks behind, so
+    // neither the loads nor the output need an if-guard.
+    let padded_length: usize = convolution.padded_signal_length(block_size as u32);
+    let mut signal_padded: Vec<f32> = vec![0.0; (filter_size - 1) / 2];
+    signal_padded.extend_from_slice(&signal);
+    signal_padded.resize(padded_length, 0.0);
+    let signal_gpu_padded: GPUVector =
+        GPUVector::new(handles, signal_padded, "signal padded", false);
+    let mut output_padded: GPUVector = GPUVector::zeros(
+        handles,
+        &[padded_length - (filter_size - 1)],
+        "output",
+        true,
+    );
+    let time: Duration = benchmark(handles, repetitions, || {
+        convolution
+            .invocation(
+                handles,
+                config(ConvolutionKernel::Padded),
+                &uniform,
+                &signal_gpu_padded,
+                &filter_gpu,
+                &mut output_padded,
+            )?
+            .submit()
+    })?;
+    let data: &[f32] = output_padded.download(handles)?;
+    success &= report(
+        "padded",
+        parameters,
+        time,
+        &ground_truth,
+        &data[..signal_length],
+    );
+
+    Ok(success)
+}
+
+fn flag(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1).cloned())
+}
+
+/// A comma separated list of sizes, e.g. `32,64,128`.
+fn sizes(args: &[String], name: &str, default: &[usize]) -> Vec<usize> {
+    match flag(args, name) {
+        Some(value) => value
+            .split(',')
+            .map(|size| {
+                size.trim()
+                    .parse()
+                    .unwrap_or_else(|_| panic!("Invalid {name}: {size}"))
+            })
+            .collect(),
+        None => default.to_vec(),
+    }
+}
+
+fn main() {
+    // Initialize the env_logger to get usueful messages from wgpu.
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let signal_lengths: Vec<usize> = sizes(&args, "--signal-length", &[1000000]);
+    let filter_sizes: Vec<usize> = sizes(&args, "--filter-size", &[19]);
+    let block_sizes: Vec<usize> = sizes(&args, "--block-size", &[32, 64, 128, 256]);
+    let repetitions: usize = sizes(&args, "--repetitions", &[10])[0];
+
+    // A small test to ensure that the convolution_cpu function is actually correct.
+    let ground_truth_is_correct: bool = test_ground_truth();
+    println!(
+        "Convolution ground truth function is correct: {}",
+        ground_truth_is_correct
+    );
+    assert!(ground_truth_is_correct);
+
+    // Use pollster::block_on to block on async functions.
+    let handles: GPUHandles =
+        pollster::block_on(initialize_gpu()).expect("Was unable to find a compatible GPU!");
+    println!("Running on {}", handles.adapter_info.name);
+    let max_block_size: usize = handles
+        .device
+        .limits()
+        .max_compute_invocations_per_workgroup as usize;
+
+    println!("  signal  filter  block  variant       time");
+    let mut success: bool = true;
+    for &signal_length in &signal_lengths {
+        for &filter_size in &filter_sizes {
+            for &block_size in &block_sizes {
+                if max_block_size < block_size {
+                    println!(
+                        "Skipping block size {block_size}, the device allows {max_block_size}"
+                    );
+                    continue;
+                }
+                let parameters: ConvolutionParameters = ConvolutionParameters {
+                    signal_length,
+                    filter_size,
+                    block_size,
+                };
+                success &= convolution(&handles, parameters, repetitions)
+                    .unwrap_or_else(|error| panic!("{error}"));
+            }
+        }
+    }
+    assert!(success);
+}
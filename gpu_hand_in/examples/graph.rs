@@ -0,0 +1,127 @@
+//! Runs a small pipeline as one [`KernelGraph`]: a 1D convolution, a vector add and
+//! a matrix multiplication.
+//!
+//! All three kernels and the read-back of the product go into one submission. The
+//! convolved signal and the sum stay on the GPU.
+//!
+//! ```text
+//! cargo run --release --example graph
+//! ```
+use gpu_hand_in::shaders;
+use gpu_hand_in::utility::{are_vectors_equivalent, mean_square_error};
+use gpu_hand_in::{initialize_gpu, BufferId, GPUHandles, KernelError, KernelGraph, UniformId};
+
+// The length of filter is assumed to be odd.
+fn convolution_cpu(signal: &[f32], filter: &[f32]) -> Vec<f32> {
+    let filter_offset = filter.len() / 2;
+    let mut output: Vec<f32> = vec![0.0; signal.len()];
+    for (signal_index, output_element) in output.iter_mut().enumerate() {
+        for (filter_index, filter_element) in filter.iter().enumerate() {
+            let offset_signal_index: i64 =
+                signal_index as i64 - filter_offset as i64 + filter_index as i64;
+            if -1 < offset_signal_index && offset_signal_index < signal.len() as i64 {
+                *output_element += signal[offset_signal_index as usize] * filter_element;
+            }
+        }
+    }
+
+    output
+}
+
+fn pipeline_cpu(
+    signal: &[f32],
+    filter: &[f32],
+    bias: &[f32],
+    right: &[f32],
+    rows: usize,
+    inner: usize,
+    columns: usize,
+) -> Vec<f32> {
+    let left: Vec<f32> = convolution_cpu(signal, filter)
+        .iter()
+        .zip(bias)
+        .map(|(x, bias)| x + bias)
+        .collect();
+    let mut output: Vec<f32> = vec![0.0; rows * columns];
+    for row in 0..rows {
+        for column in 0..columns {
+            output[row * columns + column] = (0..inner)
+                .map(|index| left[row * inner + index] * right[index * columns + column])
+                .sum();
+        }
+    }
+    output
+}
+
+fn pipeline(handles: &GPUHandles) -> Result<bool, KernelError> {
+    let (rows, inner, columns): (usize, usize, usize) = (64, 48, 80);
+    let element_count: usize = rows * inner;
+    let signal: Vec<f32> = (0..element_count).map(|x| (x % 11) as f32 * 0.5).collect();
+    let filter: Vec<f32> = vec![0.25, 0.5, 1.0, 0.5, 0.25];
+    let bias: Vec<f32> = (0..element_count).map(|x| (x % 3) as f32 - 1.0).collect();
+    let right: Vec<f32> = (0..inner * columns)
+        .map(|x| (x % 7) as f32 * 0.25)
+        .collect();
+    let ground_truth: Vec<f32> =
+        pipeline_cpu(&signal, &filter, &bias, &right, rows, inner, columns);
+
+    let mut graph: KernelGraph = KernelGraph::new(handles);
+    let convolution_uniform: UniformId = graph.uniform(element_count, filter.len(), 0, 0);
+    let add_uniform: UniformId = graph.uniform(element_count, 0, 0, 0);
+    let multiply_uniform: UniformId = graph.uniform(columns, rows, inner, 0);
+
+    let signal: BufferId<f32> = graph.upload(signal);
+    let filter: BufferId<f32> = graph.upload(filter);
+    let bias: BufferId<f32> = graph.upload(bias);
+    let right: BufferId<f32> = graph.upload(right);
+    let convolved: BufferId<f32> = graph.zeros(&[element_count]);
+    let left: BufferId<f32> = graph.zeros(&[rows, inner]);
+    let output: BufferId<f32> = graph.zeros(&[rows, columns]);
+
+    graph
+        .node(shaders::CONVOLUTION_NAIVE, "conv_naive")?
+        .uniform(convolution_uniform)
+        .read(signal)
+        .read(filter)
+        .write(convolved)
+        .threads(element_count as u32, 1, 1)
+        .add()?;
+    graph
+        .node(shaders::VECTOR_ADD, "vector_add")?
+        .uniform(add_uniform)
+        .read(convolved)
+        .read(bias)
+        .write(left)
+        .threads(element_count as u32, 1, 1)
+        .add()?;
+    graph
+        .node(shaders::MATRIX_MULTIPLICATION_NAIVE, "matmul_naive")?
+        .uniform(multiply_uniform)
+        .read(left)
+        .read(right)
+        .write(output)
+        .threads(columns as u32, rows as u32, 1)
+        .add()?;
+    graph.read_back(output);
+
+    graph.run()?;
+
+    let data: &[f32] = &graph.buffer(output).cpu_data;
+    println!("graph MSE: {}", mean_square_error(&ground_truth, data));
+    let success: bool = are_vectors_equivalent(&ground_truth, data);
+    println!("graph success: {}!", success);
+    Ok(success)
+}
+
+fn main() {
+    // Initialize the env_logger to get usueful messages from wgpu.
+    env_logger::init();
+
+    // Use pollster::block_on to block on async functions.
+    let handles: GPUHandles =
+        pollster::block_on(initialize_gpu()).expect("Was unable to find a compatible GPU!");
+    println!("Running on {}", handles.adapter_info.name);
+
+    let success: bool = pipeline(&handles).unwrap_or_else(|error| panic!("{error}"));
+    assert!(success);
+}
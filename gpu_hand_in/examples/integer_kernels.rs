@@ -0,0 +1,86 @@
+//! Integer kernels on typed buffers: a `u32` histogram and an `i32` prefix sum.
+//!
+//! ```text
+//! cargo run --release --example integer_kernels
+//! ```
+use gpu_hand_in::shaders;
+use gpu_hand_in::{initialize_gpu, GPUHandles, GpuBuffer, KernelError, KernelInvocation, Uniform};
+
+fn histogram(handles: &GPUHandles) -> Result<bool, KernelError> {
+    let element_count: usize = 100_000;
+    let bin_count: usize = 16;
+    // A cheap, deterministic spread of values, some of them past the last bin.
+    let values: Vec<u32> = (0..element_count as u32)
+        .map(|index| index.wrapping_mul(2_654_435_761) % 20)
+        .collect();
+
+    let mut ground_truth: Vec<u32> = vec![0; bin_count];
+    for &value in &values {
+        ground_truth[(value as usize).min(bin_count - 1)] += 1;
+    }
+
+    let values: GpuBuffer<u32> = GpuBuffer::new(handles, values, "values", false);
+    let bins: GpuBuffer<u32> = gpu_hand_in::histogram(handles, &values, bin_count)?;
+
+    println!("histogram: {:?}", bins.cpu_data);
+    let success: bool = bins.cpu_data == ground_truth;
+    println!("histogram success: {}!", success);
+    Ok(success)
+}
+
+fn prefix_sum(handles: &GPUHandles) -> Result<bool, KernelError> {
+    let block_size: usize = 256;
+    let element_count: usize = 4 * block_size;
+    let input: Vec<i32> = (0..element_count as i32).map(|x| x % 7 - 3).collect();
+
+    let uniform: Uniform = Uniform::new(handles, element_count, 0, 0, 0);
+    let input_gpu: GpuBuffer<i32> = GpuBuffer::new(handles, input.clone(), "input", false);
+    // One row per block, the kernel scans each row.
+    let mut output: GpuBuffer<i32> = GpuBuffer::zeros(
+        handles,
+        &[element_count / block_size, block_size],
+        "output",
+        true,
+    );
+
+    KernelInvocation::new(handles, shaders::PREFIX_SUM, "prefix_sum")?
+        .uniform(&uniform)
+        .read(&input_gpu)
+        .read_write(&mut output)
+        .threads(element_count as u32, 1, 1)
+        .dispatch()?;
+
+    // Add the totals of the earlier blocks on the CPU.
+    let [blocks, columns] = [output.shape()[0], output.shape()[1]];
+    let mut scanned: Vec<i32> = Vec::with_capacity(element_count);
+    let mut carry: i32 = 0;
+    for row in 0..blocks {
+        let block: &[i32] = &output.cpu_data[row * columns..(row + 1) * columns];
+        scanned.extend(block.iter().map(|value| value + carry));
+        carry += block[columns - 1];
+    }
+
+    let ground_truth: Vec<i32> = input
+        .iter()
+        .scan(0, |sum, value| {
+            *sum += value;
+            Some(*sum)
+        })
+        .collect();
+    let success: bool = scanned == ground_truth;
+    println!("prefix sum success: {}!", success);
+    Ok(success)
+}
+
+fn main() {
+    // Initialize the env_logger to get usueful messages from wgpu.
+    env_logger::init();
+
+    // Use pollster::block_on to block on async functions.
+    let handles: GPUHandles =
+        pollster::block_on(initialize_gpu()).expect("Was unable to find a compatible GPU!");
+    println!("Running on {}", handles.adapter_info.name);
+
+    assert!(histogram(&handles).unwrap_or_else(|error| panic!("{error}")));
+    assert!(prefix_sum(&handles).unwrap_or_else(|error| panic!("{error}")));
+}
@@ -0,0 +1,333 @@
+//! Matrix multiplication on the GPU with every [`GemmKernel`] (naive, tiled in
+//! workgroup memory and register blocked) plus the tiled kernel on matrices zero padded
+//! to the tile size, each timed and checked against the CPU.
+//!
+//! A sweep over random shapes and transposes checks the kernels first. With `--tune`
+//! every tile size the device allows is timed too, and the fastest configuration for
+//! the shape is cached in the given file and run as well.
+//!
+//! ```text
+//! cargo run --release --example matrix_multiplication -- [--rows 1710] [--inner 241]
+//!     [--columns 3512] [--transpose-left] [--transpose-right] [--repetitions 3]
+//!     [--sweep 20] [--seed 1] [--tune autotune.txt]
+//! ```
+use std::time::Duration;
+
+use gpu_hand_in::shaders;
+use gpu_hand_in::utility::{are_vectors_equivalent, mean_square_error};
+use gpu_hand_in::{
+    benchmark, initialize_gpu, Autotuner, GPUHandles, GPUVector, Gemm, GemmConfig, GemmKernel,
+    KernelError, KernelInvocation, Uniform,
+};
+
+fn matrix_multiplication_cpu(
+    left_matrix: &[f32],
+    right_matrix: &[f32],
+    outer_dimension_left_length: usize,
+    inner_dimension_length: usize,
+    outer_dimension_right_length: usize,
+) -> Vec<f32> {
+    let mut output: Vec<f32> =
+        vec![0.0; outer_dimension_left_length * outer_dimension_right_length];
+    for row_output in 0..outer_dimension_left_length {
+        for column_output in 0..outer_dimension_right_length {
+            for inner_dimension in 0..inner_dimension_length {
+                output[row_output * outer_dimension_right_length + column_output] += left_matrix
+                    [row_output * inner_dimension_length + inner_dimension]
+                    * right_matrix[inner_dimension * outer_dimension_right_length + column_output];
+            }
+        }
+    }
+    output
+}
+
+fn test_ground_truth() -> bool {
+    let outer_dimension_left: usize = 4;
+    let inner_dimension: usize = 3;
+    let outer_dimension_right: usize = 3;
+    let left: Vec<f32> = vec![1.0, 0.0, 1.0, 2.0, 1.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0, 2.0];
+    let right: Vec<f32> = vec![1.0, 2.0, 1.0, 2.0, 3.0, 1.0, 4.0, 2.0, 2.0];
+    let ground_truth_output: Vec<f32> =
+        vec![5.0, 4.0, 3.0, 8.0, 9.0, 5.0, 6.0, 5.0, 3.0, 11.0, 9.0, 6.0];
+
+    let output: Vec<f32> = matrix_multiplication_cpu(
+        &left,
+        &right,
+        outer_dimension_left,
+        inner_dimension,
+        outer_dimension_right,
+    );
+
+    assert!(output.len() == ground_truth_output.len());
+    for index in 0..ground_truth_output.len() {
+        if 0.00001 < (output[index] - ground_truth_output[index]).abs() {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn transpose(matrix: &[f32], rows: usize, columns: usize) -> Vec<f32> {
+    let mut output: Vec<f32> = vec![0.0; matrix.len()];
+    for row in 0..rows {
+        for column in 0..columns {
+            output[column * rows + row] = matrix[row * columns + column];
+        }
+    }
+    output
+}
+
+/// xorshift, so a seed always gives the same shapes and values.
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+/// Small integers, so the sums are exact and a fixed epsilon is meaningful.
+fn random_matrix(state: &mut u64, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|_| (next_random(state) % 7) as f32 - 3.0)
+        .collect()
+}
+
+/// The operands of `gemm` as the GPU expects them, and the CPU result.
+fn operands(gemm: &Gemm, state: &mut u64) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+    let left: Vec<f32> = random_matrix(state, gemm.rows * gemm.inner);
+    let right: Vec<f32> = random_matrix(state, gemm.inner * gemm.columns);
+    let ground_truth: Vec<f32> =
+        matrix_multiplication_cpu(&left, &right, gemm.rows, gemm.inner, gemm.columns);
+
+    let left: Vec<f32> = if gemm.transpose_left {
+        transpose(&left, gemm.rows, gemm.inner)
+    } else {
+        left
+    };
+    let right: Vec<f32> = if gemm.transpose_right {
+        transpose(&right, gemm.inner, gemm.columns)
+    } else {
+        right
+    };
+    (left, right, ground_truth)
+}
+
+fn report(name: &str, time: Duration, ground_truth: &[f32], data: &[f32]) -> bool {
+    let success: bool = are_vectors_equivalent(ground_truth, data);
+    println!(
+        "matrix multiplication {:<16} {:>10.3} ms  MSE {:<10e} success: {}",
+        name,
+        time.as_secs_f64() * 1000.0,
+        mean_square_error(ground_truth, data),
+        success
+    );
+    success
+}
+
+/// Checks every kernel on `count` random shapes of up to 300 along each dimension.
+fn sweep(handles: &GPUHandles, count: usize, state: &mut u64) -> Result<bool, KernelError> {
+    let mut success: bool = true;
+    for _ in 0..count {
+        let gemm: Gemm = Gemm::new(
+            1 + (next_random(state) % 300) as usize,
+            1 + (next_random(state) % 300) as usize,
+            1 + (next_random(state) % 300) as usize,
+        )
+        .transpose_left((next_random(state) >> 32).is_multiple_of(2))
+        .transpose_right((next_random(state) >> 32).is_multiple_of(2));
+        let (left, right, ground_truth) = operands(&gemm, state);
+
+        let uniform: Uniform = gemm.uniform(handles);
+        let left: GPUVector = GPUVector::new(handles, left, "left_matrix", false);
+        let right: GPUVector = GPUVector::new(handles, right, "right_matrix", false);
+        let mut failed: Vec<GemmKernel> = Vec::new();
+        for kernel in GemmKernel::ALL {
+            let mut output: GPUVector =
+                GPUVector::zeros(handles, &[gemm.rows, gemm.columns], "output", true);
+            gemm.invocation(handles, kernel, &uniform, &left, &right, &mut output)?
+                .dispatch()?;
+            if !are_vectors_equivalent(&ground_truth, &output.cpu_data) {
+                failed.push(kernel);
+            }
+        }
+        println!(
+            "sweep {}x{}x{} transposed ({}, {}): {}",
+            gemm.rows,
+            gemm.inner,
+            gemm.columns,
+            gemm.transpose_left,
+            gemm.transpose_right,
+            if failed.is_empty() {
+                "ok".to_string()
+            } else {
+                format!("failed {failed:?}")
+            }
+        );
+        success &= failed.is_empty();
+    }
+    Ok(success)
+}
+
+fn matrix_multiplication(
+    handles: &GPUHandles,
+    gemm: Gemm,
+    configs: &[GemmConfig],
+    repetitions: usize,
+    state: &mut u64,
+) -> Result<bool, KernelError> {
+    let (left_matrix, right_matrix, ground_truth) = operands(&gemm, state);
+    let uniform: Uniform = gemm.uniform(handles);
+    let left_matrix_gpu: GPUVector =
+        GPUVector::new(handles, left_matrix.clone(), "left_matrix", false);
+    let right_matrix_gpu: GPUVector =
+        GPUVector::new(handles, right_matrix.clone(), "right_matrix", false);
+    let mut success: bool = true;
+
+    for &config in configs {
+        let mut output: GPUVector =
+            GPUVector::zeros(handles, &[gemm.rows, gemm.columns], "output", true);
+        let time: Duration = benchmark(handles, repetitions, || {
+            gemm.invocation(
+                handles,
+                config,
+                &uniform,
+                &left_matrix_gpu,
+                &right_matrix_gpu,
+                &mut output,
+            )?
+            .submit()
+        })?;
+        let data: &[f32] = output.download(handles)?;
+        success &= report(&config.to_string(), time, &ground_truth, data);
+    }
+
+    // The padded kernel has no if-guards, so every dimension is zero padded to a
+    // multiple of the tile size. It does not transpose.
+    if gemm.transpose_left || gemm.transpose_right {
+        return Ok(success);
+    }
+    let tile_size: usize = 16;
+    let rows_padded: usize = gemm.rows.next_multiple_of(tile_size);
+    let inner_padded: usize = gemm.inner.next_multiple_of(tile_size);
+    let columns_padded: usize = gemm.columns.next_multiple_of(tile_size);
+
+    let mut left_matrix_padded: Vec<f32> = vec![0.0; rows_padded * inner_padded];
+    for row in 0..gemm.rows {
+        left_matrix_padded[row * inner_padded..row * inner_padded + gemm.inner]
+            .copy_from_slice(&left_matrix[row * gemm.inner..(row + 1) * gemm.inner]);
+    }
+    let mut right_matrix_padded: Vec<f32> = vec![0.0; inner_padded * columns_padded];
+    for row in 0..gemm.inner {
+        right_matrix_padded[row * columns_padded..row * columns_padded + gemm.columns]
+            .copy_from_slice(&right_matrix[row * gemm.columns..(row + 1) * gemm.columns]);
+    }
+
+    let uniform_padded: Uniform =
+        Uniform::new(handles, columns_padded, rows_padded, inner_padded, 0);
+    let left_matrix_padded_gpu: GPUVector =
+        GPUVector::new(handles, left_matrix_padded, "left_matrix", false);
+    let right_matrix_padded_gpu: GPUVector =
+        GPUVector::new(handles, right_matrix_padded, "right_matrix", false);
+    let mut output_gpu_padded: GPUVector =
+        GPUVector::zeros(handles, &[rows_padded, columns_padded], "output", true);
+    let time: Duration = benchmark(handles, repetitions, || {
+        KernelInvocation::new(
+            handles,
+            shaders::MATRIX_MULTIPLICATION_PADDED,
+            "matmul_padded",
+        )?
+        .uniform(&uniform_padded)
+        .read(&left_matrix_padded_gpu)
+        .read(&right_matrix_padded_gpu)
+        .read_write(&mut output_gpu_padded)
+        .workgroups(
+            (columns_padded / tile_size) as u32,
+            (rows_padded / tile_size) as u32,
+            1,
+        )
+        .submit()
+    })?;
+    let data: &[f32] = output_gpu_padded.download(handles)?;
+    let data_padded: Vec<f32> = data
+        .chunks(columns_padded)
+        .take(gemm.rows)
+        .flat_map(|row| &row[..gemm.columns])
+        .copied()
+        .collect();
+    success &= report("padded", time, &ground_truth, &data_padded);
+
+    Ok(success)
+}
+
+fn flag(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1).cloned())
+}
+
+fn number(args: &[String], name: &str, default: usize) -> usize {
+    flag(args, name).map_or(default, |value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid {name}: {value}"))
+    })
+}
+
+fn main() {
+    // Initialize the env_logger to get usueful messages from wgpu.
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // Use big data dimensions to make sure the cost of transferring
+    // doesn't dominate the time spent in the function.
+    let gemm: Gemm = Gemm::new(
+        number(&args, "--rows", 1710),
+        number(&args, "--inner", 241),
+        number(&args, "--columns", 3512),
+    )
+    .transpose_left(args.iter().any(|arg| arg == "--transpose-left"))
+    .transpose_right(args.iter().any(|arg| arg == "--transpose-right"));
+    let repetitions: usize = number(&args, "--repetitions", 3);
+    let sweep_count: usize = number(&args, "--sweep", 20);
+    // xorshift never leaves 0.
+    let mut state: u64 = number(&args, "--seed", 1).max(1) as u64;
+
+    // A small test to ensure that the matrix_multiplication_cpu function is actually correct.
+    let ground_truth_is_correct: bool = test_ground_truth();
+    println!(
+        "Matrix multiplication ground truth function is correct: {}",
+        ground_truth_is_correct
+    );
+    assert!(ground_truth_is_correct);
+
+    // Use pollster::block_on to block on async functions.
+    let handles: GPUHandles =
+        pollster::block_on(initialize_gpu()).expect("Was unable to find a compatible GPU!");
+    println!("Running on {}", handles.adapter_info.name);
+
+    let swept: bool =
+        sweep(&handles, sweep_count, &mut state).unwrap_or_else(|error| panic!("{error}"));
+
+    // Every kernel with the sizes it is written with, plus the tuned one.
+    let mut configs: Vec<GemmConfig> = GemmKernel::ALL.map(GemmConfig::from).to_vec();
+    if let Some(cache) = flag(&args, "--tune") {
+        let mut autotuner: Autotuner = Autotuner::open(&cache)
+            .unwrap_or_else(|error| panic!("Could not read {cache}: {error}"));
+        let tuned: GemmConfig = gemm
+            .tune(&handles, &mut autotuner)
+            .unwrap_or_else(|error| panic!("{error}"));
+        println!("Fastest configuration: {tuned}");
+        if !configs.contains(&tuned) {
+            configs.push(tuned);
+        }
+    }
+
+    println!(
+        "matrix multiplication {}x{}x{}:",
+        gemm.rows, gemm.inner, gemm.columns
+    );
+    let success: bool = matrix_multiplication(&handles, gemm, &configs, repetitions, &mut state)
+        .unwrap_or_else(|error| panic!("{error}"));
+    assert!(swept && success);
+}
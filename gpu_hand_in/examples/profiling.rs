@@ -0,0 +1,142 @@
+//! Profiles a memory bound kernel (vector addition) and a compute bound one (matrix
+//! multiplication), reporting upload, compute and download separately.
+//!
+//! The device's timestamp queries are used where it has them, the wall clock otherwise.
+//! Both are shown when timestamps are available.
+//!
+//! ```text
+//! cargo run --release --example profiling -- [--elements 2000000] [--size 1024]
+//! ```
+use gpu_hand_in::shaders;
+use gpu_hand_in::utility::are_vectors_equivalent;
+use gpu_hand_in::{
+    initialize_gpu, GPUHandles, GPUVector, Gemm, GemmKernel, KernelError, KernelInvocation,
+    Profiler, Uniform,
+};
+
+fn profile_vector_add(
+    handles: &GPUHandles,
+    profiler: &mut Profiler,
+    element_count: usize,
+) -> Result<bool, KernelError> {
+    let a: Vec<f32> = (0..element_count).map(|x| (x % 101) as f32).collect();
+    let b: Vec<f32> = (0..element_count).map(|x| (x % 37) as f32 * 0.5).collect();
+    let ground_truth: Vec<f32> = a.iter().zip(&b).map(|(a, b)| a + b).collect();
+
+    let uniform: Uniform = Uniform::new(handles, element_count, 0, 0, 0);
+    let mut a_gpu: GPUVector = GPUVector::zeros(handles, &[element_count], "a", false);
+    let mut b_gpu: GPUVector = GPUVector::zeros(handles, &[element_count], "b", false);
+    let mut output: GPUVector = GPUVector::zeros(handles, &[element_count], "output", true);
+
+    profiler.upload(&mut a_gpu, a)?;
+    profiler.upload(&mut b_gpu, b)?;
+    let invocation: KernelInvocation =
+        KernelInvocation::new(handles, shaders::VECTOR_ADD, "vector_add")?
+            .uniform(&uniform)
+            .read(&a_gpu)
+            .read(&b_gpu)
+            .read_write(&mut output)
+            .threads(element_count as u32, 1, 1);
+    // One add per element, two reads and a write.
+    profiler.compute(
+        invocation,
+        element_count as f64,
+        (3 * element_count * std::mem::size_of::<f32>()) as u64,
+    )?;
+    let data: &[f32] = profiler.download(&mut output)?;
+    Ok(are_vectors_equivalent(&ground_truth, data))
+}
+
+fn profile_matrix_multiplication(
+    handles: &GPUHandles,
+    profiler: &mut Profiler,
+    size: usize,
+) -> Result<bool, KernelError> {
+    let gemm: Gemm = Gemm::new(size, size, size);
+    // The identity on the right, so the product is the left matrix.
+    let left: Vec<f32> = (0..size * size).map(|x| (x % 13) as f32).collect();
+    let right: Vec<f32> = (0..size * size)
+        .map(|x| if x / size == x % size { 1.0 } else { 0.0 })
+        .collect();
+    let ground_truth: Vec<f32> = left.clone();
+
+    let uniform: Uniform = gemm.uniform(handles);
+    let mut left_gpu: GPUVector = GPUVector::zeros(handles, &[size, size], "left", false);
+    let mut right_gpu: GPUVector = GPUVector::zeros(handles, &[size, size], "right", false);
+    let mut output: GPUVector = GPUVector::zeros(handles, &[size, size], "output", true);
+
+    profiler.upload(&mut left_gpu, left)?;
+    profiler.upload(&mut right_gpu, right)?;
+    let invocation: KernelInvocation = gemm.invocation(
+        handles,
+        GemmKernel::RegisterBlocked,
+        &uniform,
+        &left_gpu,
+        &right_gpu,
+        &mut output,
+    )?;
+    profiler.compute(invocation, gemm.flops(), gemm.accessed_bytes())?;
+    let data: &[f32] = profiler.download(&mut output)?;
+    Ok(are_vectors_equivalent(&ground_truth, data))
+}
+
+fn flag(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1).cloned())
+}
+
+fn number(args: &[String], name: &str, default: usize) -> usize {
+    flag(args, name).map_or(default, |value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid {name}: {value}"))
+    })
+}
+
+fn main() {
+    // Initialize the env_logger to get usueful messages from wgpu.
+    env_logger::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let element_count: usize = number(&args, "--elements", 2_000_000);
+    let size: usize = number(&args, "--size", 1024);
+
+    // Use pollster::block_on to block on async functions.
+    let handles: GPUHandles =
+        pollster::block_on(initialize_gpu()).expect("Was unable to find a compatible GPU!");
+    println!("Running on {}", handles.adapter_info.name);
+
+    // Compare with the wall clock if the default is timestamps.
+    let mut wall_clock: Vec<bool> = vec![false];
+    if handles.supports_timestamps() {
+        wall_clock.push(true);
+    }
+    let new_profiler = |wall_clock: bool| {
+        if wall_clock {
+            Profiler::wall_clock(&handles)
+        } else {
+            Profiler::new(&handles)
+        }
+    };
+
+    let mut success: bool = true;
+    for wall_clock in wall_clock {
+        let mut profiler: Profiler = new_profiler(wall_clock);
+        success &= profile_vector_add(&handles, &mut profiler, element_count)
+            .unwrap_or_else(|error| panic!("{error}"));
+        println!(
+            "\nvector add of {element_count} elements, {}",
+            profiler.profile()
+        );
+
+        let mut profiler: Profiler = new_profiler(wall_clock);
+        success &= profile_matrix_multiplication(&handles, &mut profiler, size)
+            .unwrap_or_else(|error| panic!("{error}"));
+        println!(
+            "\nmatrix multiplication {size}x{size}x{size}, {}",
+            profiler.profile()
+        );
+    }
+    assert!(success);
+}
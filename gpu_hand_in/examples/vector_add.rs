@@ -0,0 +1,81 @@
+//! Adds two vectors on the GPU and checks the result against the CPU.
+//!
+//! ```text
+//! cargo run --release --example vector_add
+//! ```
+use gpu_hand_in::shaders;
+use gpu_hand_in::utility::{are_vectors_equivalent, mean_square_error};
+use gpu_hand_in::{initialize_gpu, run_compute_shader, GPUHandles, GPUVector, Uniform};
+
+fn vector_add_cpu(input_a: &[f32], input_b: &[f32]) -> Vec<f32> {
+    assert!(input_a.len() == input_b.len());
+
+    input_a.iter().zip(input_b).map(|(a, b)| a + b).collect()
+}
+
+fn vector_add(handles: &GPUHandles) -> bool {
+    // Setup our CPU-side data
+    let element_count: usize = 100;
+    let input_a: Vec<f32> = (0..element_count).map(|element| element as f32).collect();
+    let input_b: Vec<f32> = (0..element_count)
+        .map(|element| element as f32 * 0.1)
+        .collect();
+    let output: Vec<f32> = vec![0.0; element_count];
+
+    let ground_truth: Vec<f32> = vector_add_cpu(&input_a, &input_b);
+
+    // Create our uniform for telling the shader how big the vectors are.
+    let uniform: Uniform = Uniform::new(handles, element_count, 0, 0, 0);
+
+    // Create the GPU vectors.
+    // Note the true at the end of the output vector creation.
+    // This will result in a staging_buffer being created, which we
+    // can read from on the CPU.
+    let input_a: GPUVector = GPUVector::new(handles, input_a, "input_a", false);
+    let input_b: GPUVector = GPUVector::new(handles, input_b, "input_b", false);
+    let mut output: GPUVector = GPUVector::new(handles, output, "output", true);
+
+    // We will use 32 threads in a work group/warp
+    // We are doing this in 1 dimension, but could do it in
+    // up to 3 dimensions.
+    let block_size_x: usize = 32;
+    let launch_blocks_x: u32 = element_count.div_ceil(block_size_x) as u32;
+    let block_size_y: usize = 1;
+    let launch_blocks_y: u32 = 1;
+
+    run_compute_shader(
+        handles,
+        block_size_x,
+        launch_blocks_x,
+        block_size_y,
+        launch_blocks_y,
+        shaders::VECTOR_ADD,
+        "vector_add",
+        &uniform,
+        &input_a,
+        &input_b,
+        &mut output,
+    )
+    .unwrap_or_else(|error| panic!("{error}"));
+
+    println!(
+        "vector_add MSE: {}",
+        mean_square_error(&ground_truth, &output.cpu_data)
+    );
+    let success: bool = are_vectors_equivalent(&ground_truth, &output.cpu_data);
+    println!("vector_add success: {}!", success);
+
+    success
+}
+
+fn main() {
+    // Initialize the env_logger to get usueful messages from wgpu.
+    env_logger::init();
+
+    // Use pollster::block_on to block on async functions.
+    let handles: GPUHandles =
+        pollster::block_on(initialize_gpu()).expect("Was unable to find a compatible GPU!");
+    println!("Running on {}", handles.adapter_info.name);
+
+    assert!(vector_add(&handles));
+}
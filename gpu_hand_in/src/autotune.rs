@@ -0,0 +1,167 @@
+//! Picks the fastest of several kernel configurations by timing them, and remembers the
+//! winner per adapter and problem in a file so the next run skips the benchmark.
+//!
+//! The cache is plain text, one `adapter<TAB>problem<TAB>configuration` per line.
+//! Configurations are stored with [`Display`] and read back with [`FromStr`].
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::context::GPUHandles;
+use crate::error::KernelError;
+use crate::future::Submission;
+
+/// Runs `dispatch` once to compile the kernel and warm up, then `repetitions` times back
+/// to back and returns the mean wall-clock time per run, waiting for the GPU to finish.
+pub fn benchmark(
+    handles: &GPUHandles,
+    repetitions: usize,
+    mut dispatch: impl FnMut() -> Result<Submission, KernelError>,
+) -> Result<Duration, KernelError> {
+    dispatch()?.wait(handles);
+
+    let start: Instant = Instant::now();
+    let mut last: Option<Submission> = None;
+    for _ in 0..repetitions {
+        last = Some(dispatch()?);
+    }
+    if let Some(submission) = last {
+        submission.wait(handles);
+    }
+    Ok(start.elapsed() / repetitions.max(1) as u32)
+}
+
+#[derive(Debug, Default)]
+pub struct Autotuner {
+    /// Where the winners are saved, `None` keeps them in memory only.
+    path: Option<PathBuf>,
+    winners: HashMap<(String, String), String>,
+}
+
+impl Autotuner {
+    /// An autotuner without a cache file.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the winners saved at `path`, if it exists. Lines that don't parse are
+    /// skipped.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path: PathBuf = path.as_ref().to_path_buf();
+        let mut winners: HashMap<(String, String), String> = HashMap::new();
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                for line in contents.lines() {
+                    let fields: Vec<&str> = line.split('\t').collect();
+                    if let [adapter, problem, config] = fields[..] {
+                        winners.insert(
+                            (adapter.to_string(), problem.to_string()),
+                            config.to_string(),
+                        );
+                    }
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error),
+        }
+        Ok(Self {
+            path: Some(path),
+            winners,
+        })
+    }
+
+    /// The adapter part of the cache key. Winners of one GPU say nothing about another.
+    pub fn adapter_key(handles: &GPUHandles) -> String {
+        format!(
+            "{} ({:?}, {:?})",
+            handles.adapter_info.name,
+            handles.adapter_info.backend,
+            handles.adapter_info.device_type
+        )
+    }
+
+    /// The cached winner for `problem`, if it is still one of `candidates`.
+    pub fn cached<C>(&self, handles: &GPUHandles, problem: &str, candidates: &[C]) -> Option<C>
+    where
+        C: Copy + PartialEq + FromStr,
+    {
+        let config: &String = self
+            .winners
+            .get(&(Self::adapter_key(handles), problem.to_string()))?;
+        let config: C = config.parse().ok()?;
+        candidates.contains(&config).then_some(config)
+    }
+
+    /// The candidate `benchmark` reports fastest for `problem`, from the cache when
+    /// possible. Candidates that fail, e.g. because the device can't compile them, are
+    /// skipped. Fails only if all of them do.
+    ///
+    /// Panics if there are no candidates.
+    pub fn tune<C>(
+        &mut self,
+        handles: &GPUHandles,
+        problem: &str,
+        candidates: &[C],
+        mut benchmark: impl FnMut(C) -> Result<Duration, KernelError>,
+    ) -> Result<C, KernelError>
+    where
+        C: Copy + PartialEq + Display + FromStr,
+    {
+        assert!(!candidates.is_empty(), "Nothing to tune for {problem}");
+        if let Some(config) = self.cached(handles, problem, candidates) {
+            log::info!("{problem}: using cached {config}");
+            return Ok(config);
+        }
+
+        let mut best: Option<(C, Duration)> = None;
+        let mut first_error: Option<KernelError> = None;
+        for &config in candidates {
+            match benchmark(config) {
+                Ok(time) => {
+                    log::info!("{problem}: {config} took {time:?}");
+                    if best.is_none_or(|(_, best_time)| time < best_time) {
+                        best = Some((config, time));
+                    }
+                }
+                Err(error) => {
+                    log::warn!("{problem}: {config} failed: {error}");
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+        let Some((config, _)) = best else {
+            return Err(first_error.expect("every candidate failed"));
+        };
+
+        self.winners.insert(
+            (Self::adapter_key(handles), problem.to_string()),
+            config.to_string(),
+        );
+        // Losing the cache only costs a benchmark next time.
+        if let Err(error) = self.save() {
+            log::warn!("Could not save the autotuning cache: {error}");
+        }
+        Ok(config)
+    }
+
+    /// Writes every winner to the cache file, if there is one.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut lines: Vec<String> = self
+            .winners
+            .iter()
+            .map(|((adapter, problem), config)| format!("{adapter}\t{problem}\t{config}"))
+            .collect();
+        lines.sort();
+        fs::write(path, lines.join("\n") + "\n")
+    }
+}
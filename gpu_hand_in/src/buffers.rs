@@ -0,0 +1,386 @@
+use bytemuck::Pod;
+use wgpu::{util::DeviceExt, Buffer, CommandEncoder};
+
+use crate::context::GPUHandles;
+use crate::error::KernelError;
+use crate::future::gpu_channel;
+
+/// A storage buffer of `T` on the GPU with a CPU-side copy of its data.
+///
+/// `T` can be any [`Pod`] type whose layout matches the WGSL element type: `f32`,
+/// `u32`, `i32`, [`F16`] on devices with `SHADER_F16`, or a `#[repr(C)]` struct.
+pub struct GpuBuffer<T: Pod> {
+    // Our initial, cpu-side data
+    pub cpu_data: Vec<T>,
+
+    // The staging buffer which we back to the CPU with. It represents
+    // memory CPU side. In a more complex setup we might have a staging
+    // buffer GPU side, before transferring from the staging buffer
+    // to the storage buffer which is accesible.
+    // We only need the staging buffer if we transfer the data back to the CPU
+    pub staging_buffer: Option<Buffer>,
+
+    // The buffer that will be used for our vector addition compute shader
+    // The transfer from our data vector is hidden by
+    // create_buffer_init(). If we wanted more control and better performance
+    // we would do this ourselves by using staging buffers and perhaps
+    // asynchronous transfers.
+    pub storage_buffer: Buffer,
+
+    shape: Vec<usize>,
+}
+
+/// The `f32` vectors all the demos work with.
+pub type GPUVector = GpuBuffer<f32>;
+
+impl<T: Pod> GpuBuffer<T> {
+    /// Uploads `cpu_data` as a one dimensional buffer. Pass `output_buffer` for buffers
+    /// that are read back after a dispatch, which gives them a staging buffer.
+    pub fn new(handles: &GPUHandles, cpu_data: Vec<T>, label: &str, output_buffer: bool) -> Self {
+        let size: u64 = std::mem::size_of_val(cpu_data.as_slice()) as wgpu::BufferAddress;
+
+        // If we want to retrieve the GPU results to the CPU we
+        // create the staging buffer, but don't actually copy anything in there yet.
+        // Note that we give the storage buffer hints to how this buffer will be used.
+        let staging_buffer: Option<Buffer> = if !output_buffer {
+            None
+        } else {
+            Some(handles.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }))
+        };
+
+        let storage_buffer: Buffer =
+            handles
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(label),
+                    contents: bytemuck::cast_slice(&cpu_data),
+                    usage: wgpu::BufferUsages::STORAGE
+                        | wgpu::BufferUsages::COPY_DST
+                        | wgpu::BufferUsages::COPY_SRC,
+                });
+
+        let shape: Vec<usize> = vec![cpu_data.len()];
+        GpuBuffer {
+            cpu_data,
+            staging_buffer,
+            storage_buffer,
+            shape,
+        }
+    }
+
+    /// A buffer of `shape` filled with zeroes.
+    pub fn zeros(handles: &GPUHandles, shape: &[usize], label: &str, output_buffer: bool) -> Self {
+        let len: usize = shape.iter().product();
+        Self::new(handles, vec![T::zeroed(); len], label, output_buffer).with_shape(shape)
+    }
+
+    /// Attaches a shape, e.g. `[rows, columns]`, to the data.
+    ///
+    /// # Panics
+    ///
+    /// If the shape does not hold exactly `len()` elements.
+    pub fn with_shape(mut self, shape: &[usize]) -> Self {
+        assert_eq!(
+            shape.iter().product::<usize>(),
+            self.len(),
+            "Shape {shape:?} does not fit {} elements",
+            self.len()
+        );
+        self.shape = shape.to_vec();
+        self
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn len(&self) -> usize {
+        self.cpu_data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cpu_data.is_empty()
+    }
+
+    /// Replaces the data on both sides.
+    ///
+    /// # Panics
+    ///
+    /// If `data` has a different length, the GPU buffer cannot grow.
+    pub fn upload(&mut self, handles: &GPUHandles, data: Vec<T>) {
+        assert_eq!(
+            data.len(),
+            self.len(),
+            "Uploads must keep the buffer length"
+        );
+        handles
+            .queue
+            .write_buffer(&self.storage_buffer, 0, bytemuck::cast_slice(&data));
+        self.cpu_data = data;
+    }
+
+    /// Copies the GPU data into `cpu_data` and returns it. Buffers created without
+    /// `output_buffer` get a temporary staging buffer.
+    pub fn download(&mut self, handles: &GPUHandles) -> Result<&[T], KernelError> {
+        pollster::block_on(self.download_async(handles))
+    }
+
+    /// Same as [`Self::download`] without blocking. The copy is queued behind
+    /// everything submitted before, so it waits for the kernels writing this buffer.
+    pub async fn download_async(&mut self, handles: &GPUHandles) -> Result<&[T], KernelError> {
+        let temporary: bool = self.ensure_staging_buffer(handles);
+
+        let mut encoder: CommandEncoder = handles
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.transfer_from_gpu_to_cpu_mut(&mut encoder);
+        handles.queue.submit(Some(encoder.finish()));
+
+        let result: Result<(), KernelError> = read_back(handles, &mut [self]).await;
+        if temporary {
+            self.staging_buffer = None;
+        }
+        result?;
+        Ok(&self.cpu_data)
+    }
+
+    /// Creates a staging buffer if there is none, returning whether it did.
+    pub(crate) fn ensure_staging_buffer(&mut self, handles: &GPUHandles) -> bool {
+        if self.staging_buffer.is_some() {
+            return false;
+        }
+        self.staging_buffer = Some(handles.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: self.storage_buffer.size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        }));
+        true
+    }
+
+    // For a bit more nuance to staging buffers and copy to copy
+    // https://www.reddit.com/r/wgpu/comments/13zqe1u/can_someone_please_explain_to_me_the_whole_buffer/
+    pub fn transfer_from_gpu_to_cpu_mut(&mut self, encoder: &mut CommandEncoder) {
+        // We copy from the shader-visible GPU storage buffer
+        // to the CPU-visible staging buffer.
+        if let Some(staging_buffer) = &self.staging_buffer {
+            encoder.copy_buffer_to_buffer(
+                &self.storage_buffer,
+                0,
+                staging_buffer,
+                0,
+                std::mem::size_of_val(self.cpu_data.as_slice()) as u64,
+            );
+        }
+    }
+}
+
+/// A [`GpuBuffer`] with its element type erased, so buffers of different types can be
+/// bound to one kernel.
+pub trait StorageBuffer {
+    fn storage_buffer(&self) -> &Buffer;
+
+    fn staging_buffer(&self) -> Option<&Buffer>;
+
+    fn element_size(&self) -> usize;
+
+    /// Adds the copy from the storage to the staging buffer to `encoder`.
+    fn enqueue_readback(&mut self, encoder: &mut CommandEncoder);
+
+    /// Replaces the CPU data with the contents of the mapped staging buffer, then
+    /// unmaps it.
+    fn read_mapped(&mut self);
+}
+
+impl<T: Pod> StorageBuffer for GpuBuffer<T> {
+    fn storage_buffer(&self) -> &Buffer {
+        &self.storage_buffer
+    }
+
+    fn staging_buffer(&self) -> Option<&Buffer> {
+        self.staging_buffer.as_ref()
+    }
+
+    fn element_size(&self) -> usize {
+        std::mem::size_of::<T>()
+    }
+
+    fn enqueue_readback(&mut self, encoder: &mut CommandEncoder) {
+        self.transfer_from_gpu_to_cpu_mut(encoder);
+    }
+
+    fn read_mapped(&mut self) {
+        if let Some(staging_buffer) = &self.staging_buffer {
+            // We actually receive this data as raw bytes &[u8] so we
+            // recast it to T.
+            self.cpu_data =
+                bytemuck::cast_slice(&staging_buffer.slice(..).get_mapped_range()).to_vec();
+            staging_buffer.unmap();
+        }
+    }
+}
+
+/// Maps the staging buffers of `buffers` once the submitted work is done and reads them
+/// into their CPU data.
+pub(crate) async fn read_back(
+    handles: &GPUHandles,
+    buffers: &mut [&mut dyn StorageBuffer],
+) -> Result<(), KernelError> {
+    // Get ready to receive the data from the GPU, one future per buffer.
+    let mapped: Vec<_> = buffers
+        .iter()
+        .filter_map(|buffer| buffer.staging_buffer())
+        .map(|staging_buffer| {
+            let (sender, future) = gpu_channel(handles);
+            staging_buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, sender);
+            future
+        })
+        .collect();
+
+    for future in mapped {
+        future.await?;
+    }
+    for buffer in buffers.iter_mut() {
+        buffer.read_mapped();
+    }
+    Ok(())
+}
+
+/// A half precision float as stored on the GPU, for kernels that `enable f16;`.
+/// Converts to and from `f32` on the CPU, rounding to nearest even.
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct F16(pub u16);
+
+impl F16 {
+    pub fn from_f32(value: f32) -> Self {
+        let bits: u32 = value.to_bits();
+        let sign: u16 = ((bits >> 16) & 0x8000) as u16;
+        let exponent: i32 = ((bits >> 23) & 0xff) as i32;
+        let mantissa: u32 = bits & 0x007f_ffff;
+
+        if exponent == 0xff {
+            // Infinity stays infinity, NaN stays a (quiet) NaN.
+            let nan: u16 = if mantissa != 0 { 0x0200 } else { 0 };
+            return F16(sign | 0x7c00 | nan);
+        }
+
+        let half_exponent: i32 = exponent - 127 + 15;
+        if half_exponent >= 0x1f {
+            return F16(sign | 0x7c00);
+        }
+        if half_exponent <= 0 {
+            // Subnormal, or too small and flushed to zero.
+            if half_exponent < -10 {
+                return F16(sign);
+            }
+            let mantissa: u32 = mantissa | 0x0080_0000;
+            let shift: u32 = (14 - half_exponent) as u32;
+            return F16(sign | round_shift(mantissa, shift) as u16);
+        }
+
+        // Rounding may carry into the exponent, which is still the right result.
+        let rounded: u32 = round_shift((half_exponent as u32) << 23 | mantissa, 13);
+        F16(sign | rounded as u16)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        let sign: u32 = ((self.0 & 0x8000) as u32) << 16;
+        let exponent: u32 = ((self.0 >> 10) & 0x1f) as u32;
+        let mantissa: u32 = (self.0 & 0x03ff) as u32;
+
+        let bits: u32 = match (exponent, mantissa) {
+            (0, 0) => sign,
+            (0, _) => {
+                // Subnormal: normalize the mantissa.
+                let shift: u32 = mantissa.leading_zeros() - 21;
+                let mantissa: u32 = (mantissa << shift) & 0x03ff;
+                sign | (113 - shift) << 23 | mantissa << 13
+            }
+            (0x1f, _) => sign | 0x7f80_0000 | mantissa << 13,
+            _ => sign | (exponent + 112) << 23 | mantissa << 13,
+        };
+        f32::from_bits(bits)
+    }
+}
+
+/// `value >> shift`, rounded to nearest with ties to even.
+fn round_shift(value: u32, shift: u32) -> u32 {
+    let half: u32 = 1 << (shift - 1);
+    let remainder: u32 = value & ((1 << shift) - 1);
+    let truncated: u32 = value >> shift;
+    if remainder > half || (remainder == half && truncated & 1 == 1) {
+        truncated + 1
+    } else {
+        truncated
+    }
+}
+
+impl From<f32> for F16 {
+    fn from(value: f32) -> Self {
+        F16::from_f32(value)
+    }
+}
+
+impl From<F16> for f32 {
+    fn from(value: F16) -> Self {
+        value.to_f32()
+    }
+}
+
+// We create this struct to send global information (a uniform in graphics API parlance)
+// to all threads. If this were a 2 dimensional example we could also send
+// more dimensional information or whatever else we could think of.
+// In general, we will need to reduce things to be closer to raw memory
+// when we transfer data to be outside of Rust, which anything on the GPU is.
+// It has no notion of the memory layout of Rust.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct UniformElements {
+    pub data: [u32; 4],
+}
+
+pub struct Uniform {
+    pub storage_buffer: Buffer,
+}
+
+impl Uniform {
+    pub fn new(
+        handles: &GPUHandles,
+        argument_0: usize,
+        argument_1: usize,
+        argument_2: usize,
+        argument_3: usize,
+    ) -> Self {
+        let elements: UniformElements = UniformElements {
+            data: [
+                argument_0 as u32,
+                argument_1 as u32,
+                argument_2 as u32,
+                argument_3 as u32,
+            ],
+        };
+
+        // The storage buffer to actually run our shader on.
+        // The data transfer is handled by create_buffer_init.
+        let storage_buffer: Buffer =
+            handles
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Uniform"),
+                    contents: bytemuck::cast_slice(&elements.data),
+                    usage: wgpu::BufferUsages::UNIFORM
+                        | wgpu::BufferUsages::COPY_DST
+                        | wgpu::BufferUsages::COPY_SRC,
+                });
+
+        Self { storage_buffer }
+    }
+}
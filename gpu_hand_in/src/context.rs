@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use wgpu::{Adapter, AdapterInfo, Device, Instance, Queue, RequestAdapterOptions};
+
+use crate::error::KernelError;
+use crate::future::Poller;
+use crate::kernels::{Kernel, KernelCache};
+
+// Try hovering your mouse over these types and see
+// what the messages are!
+pub struct GPUHandles {
+    /// Dropped first, so the device is released on the thread dropping the handles.
+    pub(crate) poller: Poller,
+    pub queue: Queue,
+    /// Shared with the thread that polls it for [`GpuFuture`](crate::GpuFuture)s.
+    pub device: Arc<Device>,
+    pub adapter: Adapter,
+    pub adapter_info: AdapterInfo,
+    /// Kernels compiled on this device.
+    pub kernels: KernelCache,
+}
+
+impl GPUHandles {
+    /// `entry_point` of `shader`, compiled on first use.
+    pub fn kernel(&self, shader: &str, entry_point: &str) -> Result<Arc<Kernel>, KernelError> {
+        self.kernels
+            .get_or_compile(&self.device, shader, entry_point)
+    }
+
+    /// Whether kernels may `enable f16;` and bind [`F16`](crate::F16) buffers.
+    pub fn supports_f16(&self) -> bool {
+        self.device.features().contains(wgpu::Features::SHADER_F16)
+    }
+
+    /// Whether the [`Profiler`](crate::Profiler) can time work on the GPU itself.
+    pub fn supports_timestamps(&self) -> bool {
+        self.device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+    }
+}
+
+fn create_instance() -> Instance {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        dx12_shader_compiler: Default::default(),
+    })
+}
+
+// We request an adapter with high performace. In the case of both
+// an integrated and a dedicated GPU, it should prefer the dedicated
+// GPU. We don't require a compatible surface, which is what would
+// allows us to present to screen. We are not doing graphics
+// so we don't need it.
+fn adapter_options(force_fallback_adapter: bool) -> RequestAdapterOptions<'static> {
+    RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        compatible_surface: None,
+        force_fallback_adapter,
+    }
+}
+
+/// Checks whether the system has an adapter we can compute on.
+pub async fn self_test() -> bool {
+    log::info!("Performing self test to check system for compatibility.");
+    let instance: Instance = create_instance();
+
+    // `request_adapter` instantiates the general connection to the GPU
+    let adapter_option: Option<Adapter> = instance.request_adapter(&adapter_options(false)).await;
+
+    match adapter_option {
+        Some(adapter) => {
+            let info: AdapterInfo = adapter.get_info();
+            log::info!("Found GPU: {:?}", info);
+            true
+        }
+        None => {
+            log::warn!("Failed to find a usable GPU.");
+            false
+        }
+    }
+}
+
+/// Opens the device and queue of the preferred adapter, or `None` if there is no
+/// usable adapter.
+pub async fn initialize_gpu() -> Option<GPUHandles> {
+    request_handles(false).await
+}
+
+/// Same as [`initialize_gpu`] on the software (fallback) adapter, which gives
+/// comparable numbers on machines without a GPU.
+pub async fn initialize_fallback_gpu() -> Option<GPUHandles> {
+    request_handles(true).await
+}
+
+async fn request_handles(force_fallback_adapter: bool) -> Option<GPUHandles> {
+    let instance: Instance = create_instance();
+
+    // `request_adapter` instantiates the general connection to the GPU
+    let adapter: Adapter = instance
+        .request_adapter(&adapter_options(force_fallback_adapter))
+        .await?;
+
+    // `request_device` instantiates the feature specific connection to the GPU, defining some parameters,
+    //  `features` being the available features. Half precision and timestamp queries are
+    //  turned on where the adapter has them.
+    let (device, queue): (Device, Queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: adapter.features()
+                    & (wgpu::Features::SHADER_F16 | wgpu::Features::TIMESTAMP_QUERY),
+                limits: wgpu::Limits::default(),
+            },
+            None,
+        )
+        .await
+        .ok()?;
+
+    let adapter_info: AdapterInfo = adapter.get_info();
+    let device: Arc<Device> = Arc::new(device);
+
+    Some(GPUHandles {
+        queue,
+        poller: Poller::spawn(device.clone()),
+        device,
+        adapter,
+        adapter_info,
+        kernels: KernelCache::default(),
+    })
+}
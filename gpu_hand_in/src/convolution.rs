@@ -1,177 +1,163 @@
+//! 1D convolution `output[i] = sum_j signal[i + j - filter_size / 2] * filter[j]` of a
+//! signal with an odd-length filter, zero outside the signal.
+use crate::buffers::{GPUVector, Uniform};
+use crate::context::GPUHandles;
+use crate::error::KernelError;
+use crate::invocation::KernelInvocation;
+use crate::shaders;
+
+/// The convolution kernels of [`shaders`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConvolutionKernel {
+    /// One thread per output element.
+    Naive,
+    /// The block's part of the signal and the filter staged in workgroup memory.
+    Shared,
+    /// As `Shared` on a zero padded signal, without bounds checks.
+    Padded,
+}
 
-use crate::{utility::{GPUHandles, mean_square_error, are_vectors_equivalent, Uniform, run_compute_shader}, gpu_vector::GPUVector};
-// The length of filter is assumed to be oddly number, i.e. 1, 3, 5, 7, 9, 11
-fn convolution_cpu(signal: &Vec<f32>, filter: &Vec<f32>) -> Vec<f32> {
-    let filter_offset = filter.len() / 2;
-    let mut output: Vec<f32> = vec![0.0; signal.len()];
-    for signal_index in 0..signal.len() {
-        for filter_index in 0..filter.len() {
-            let offset_signal_index: i64 = signal_index as i64 - filter_offset as i64 + filter_index as i64;
-            if -1 < offset_signal_index && offset_signal_index < signal.len() as i64 {
-                output[signal_index] += signal[offset_signal_index as usize] * filter[filter_index];
-            }
+impl ConvolutionKernel {
+    pub fn shader(self) -> &'static str {
+        match self {
+            ConvolutionKernel::Naive => shaders::CONVOLUTION_NAIVE,
+            ConvolutionKernel::Shared => shaders::CONVOLUTION_SHARED,
+            ConvolutionKernel::Padded => shaders::CONVOLUTION_PADDED,
         }
     }
 
-    output
+    pub fn entry_point(self) -> &'static str {
+        match self {
+            ConvolutionKernel::Naive => "conv_naive",
+            ConvolutionKernel::Shared => "conv_shared",
+            ConvolutionKernel::Padded => "conv_padded",
+        }
+    }
 }
 
-fn test_ground_truth() -> bool {
-    let signal: Vec<f32> = vec![ 1.0, 1.0, 1.0, 1.0, 1.0];
-    let filter: Vec<f32> = vec![ 0.25, 0.5, -0.25];
-    let ground_truth_output: Vec<f32> = vec![ 0.25, 0.5, 0.5, 0.5, 0.75];
+/// A [`ConvolutionKernel`] with its sizes, specialized into the shader when the
+/// pipeline is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConvolutionConfig {
+    pub kernel: ConvolutionKernel,
+    /// Threads per workgroup, each computing one output.
+    pub block_size: u32,
+    /// The longest filter the workgroup memory has room for, not used by
+    /// [`ConvolutionKernel::Naive`].
+    pub max_filter_size: u32,
+}
+
+impl ConvolutionConfig {
+    /// The sizes the shaders are written with.
+    pub fn new(kernel: ConvolutionKernel) -> Self {
+        Self {
+            kernel,
+            block_size: 32,
+            max_filter_size: 33,
+        }
+    }
+
+    pub fn with_block_size(mut self, block_size: u32) -> Self {
+        self.block_size = block_size;
+        self
+    }
 
-    let output: Vec<f32> = convolution_cpu(&signal, &filter);
+    pub fn with_max_filter_size(mut self, max_filter_size: u32) -> Self {
+        self.max_filter_size = max_filter_size;
+        self
+    }
 
-    assert!(output.len() == ground_truth_output.len());
-    for index in 0..ground_truth_output.len() {
-        if 0.00001 < (output[index] - ground_truth_output[index]).abs() {
-            println!("Provided output: {:?}", output);
-            println!("Ground truth output: {:?}", ground_truth_output);
-            return false;
+    /// Signal elements a workgroup stages: its block plus half a filter on either side.
+    pub fn shared_size(&self) -> u32 {
+        self.block_size + self.max_filter_size - 1
+    }
+
+    /// The kernel's shader with the sizes and the constants derived from them filled in.
+    pub fn shader(&self) -> Result<String, KernelError> {
+        match self.kernel {
+            ConvolutionKernel::Naive => {
+                shaders::specialize(self.kernel.shader(), &[("BLOCK_SIZE", self.block_size)])
+            }
+            ConvolutionKernel::Shared | ConvolutionKernel::Padded => shaders::specialize(
+                self.kernel.shader(),
+                &[
+                    ("BLOCK_SIZE", self.block_size),
+                    ("MAX_FILTER_SIZE", self.max_filter_size),
+                    ("SHARED_SIZE", self.shared_size()),
+                ],
+            ),
         }
     }
 
-    true
+    /// Whether a filter of `filter_size` fits in the workgroup memory. The shaders clamp
+    /// indices past it, which would give wrong results instead of an error.
+    pub fn check_filter_size(&self, filter_size: usize) -> Result<(), KernelError> {
+        if self.kernel != ConvolutionKernel::Naive && filter_size > self.max_filter_size as usize {
+            return Err(KernelError::FilterTooLarge {
+                filter_size,
+                max_filter_size: self.max_filter_size,
+            });
+        }
+        Ok(())
+    }
 }
 
-pub fn convolution(handles: &GPUHandles) -> bool {
-    // A small test to ensure that the convolution_cpu function is actually correct.
-    let ground_truth_is_correct: bool = test_ground_truth();
-    println!("Convolution ground truth function is correct: {}", ground_truth_is_correct);
-    assert!(ground_truth_is_correct);
-
-
-    let data_element_count: usize = 1000000;
-    let filter_size: usize = 19;
-    let signal: Vec<f32> = (0..data_element_count).map(|x| x as f32 * 1.0).collect();
-    let filter: Vec<f32> = (0..filter_size).map(|x| x as f32 * -0.1).collect();
-
-    let ground_truth: Vec<f32> = convolution_cpu(&signal, &filter);
-
-    
-
-    //
-    // 1) Do 1D convolution on the GPU, don't use shared memory.
-    // Make sure to keep the filter and signal large enough to offset the cost of data transfer
-    //
-    // 2) Make a new version of 1D convolution which uses shared memory.
-    // See which is the fastest, is it the signal in shared memory, is it the filter in
-    // shared memory, is it both?
-    // What happens when you set the block size to different multiples of 32? Why do you think that is?
-    //
-    // 3) Make another version using a zero padded version of the original signal. Do not use any if's
-    // inside the inner for-loop. This zero padding is (filter_size - 1) / 2 on each side of the
-    // signal. What happens if you increase the padding with 0's to ensure that the signal is
-    // always a multiple of your block size? HINT - You should be able to remove the outer if-guard.
-    // 
-    // HINT - You need a run_compute_shader() call per type of compute shader.
-    // Figure out what the arguments are supposed to be (see vector_add.rs) and
-    // call the correct shader function in the correct shader file.
-    //
-
-    //
-    // YOUR CODE HERE
-
-    let uniform: Uniform = Uniform::new(handles, data_element_count, filter_size, 0, 0);
-    
-    let signal_gpu: GPUVector = GPUVector::new(&handles, signal.clone(), "signal", false);
-    let filter_gpu: GPUVector = GPUVector::new(&handles, filter.clone(), "filter", false);
-    let mut output_gpu_naive: GPUVector = GPUVector::new(&handles, vec![0.0; data_element_count], "output", true);
-    let mut output_gpu_shared: GPUVector = GPUVector::new(&handles, vec![0.0; data_element_count], "output", true);
-    let mut output_gpu_padded: GPUVector = GPUVector::new(&handles, vec![0.0; data_element_count], "output", true);
-
-    let block_size_x: usize = 32;
-    let launch_blocks_x: u32 = ((data_element_count + block_size_x - 1) / block_size_x) as u32;
-    let block_size_y: usize = 1;
-    let launch_blocks_y: u32 = 1;
-    let shader_file_naive: &'static str = include_str!("convolution_naive.wgsl");
-    let shader_function_naive: &str = "conv_naive";
-
-    run_compute_shader(
-        handles,
-        block_size_x, 
-        launch_blocks_x,
-        block_size_y,
-        launch_blocks_y,
-        shader_file_naive,
-        shader_function_naive,
-        &uniform,
-        &signal_gpu,
-        &filter_gpu,
-        &mut output_gpu_naive,
-    );
-
-    let shader_file_shared: &'static str = include_str!("convolution_shared.wgsl");
-    let shader_function_shared: &str = "conv_shared";
-
-
-    run_compute_shader(
-        handles,
-        block_size_x, 
-        launch_blocks_x,
-        block_size_y,
-        launch_blocks_y,
-        shader_file_shared,
-        shader_function_shared,
-        &uniform,
-        &signal_gpu,
-        &filter_gpu,
-        &mut output_gpu_shared,
-    );
-
-    let shader_file_padded: &'static str = include_str!("convolution_padded.wgsl");
-    let shader_function_padded: &str = "conv_padded";
-
-
-    //zero padding  (filter_size - 1) / 2 on each side of the
-    // signal.
-
-    let mut signal_padded: Vec<f32> = (0..data_element_count).map(|x| x as f32 * 1.0).collect();
-    // add padding
-
-    let padding: usize = (filter_size - 1) / 2;
-    for i in 0..padding {
-        signal_padded.insert(0, 0.0);
-        signal_padded.push(0.0);
+impl From<ConvolutionKernel> for ConvolutionConfig {
+    fn from(kernel: ConvolutionKernel) -> Self {
+        ConvolutionConfig::new(kernel)
     }
+}
 
+/// The shape of one convolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Convolution {
+    pub signal_length: usize,
+    /// Odd, so the filter is centered on the output element.
+    pub filter_size: usize,
+}
+
+impl Convolution {
+    pub fn new(signal_length: usize, filter_size: usize) -> Self {
+        Self {
+            signal_length,
+            filter_size,
+        }
+    }
 
-    let signal_gpu_padded: GPUVector = GPUVector::new(&handles, signal_padded, "signal", false);
-
-    run_compute_shader(
-        handles,
-        block_size_x, 
-        launch_blocks_x,
-        block_size_y,
-        launch_blocks_y,
-        shader_file_padded,
-        shader_function_padded,
-        &uniform,
-        &signal_gpu_padded,
-        &filter_gpu,
-        &mut output_gpu_padded,
-    );
-
-    let data_naive: Vec<f32> =  output_gpu_naive.cpu_data; // Remove this and replace with your own data
-    let data_shared: Vec<f32> = output_gpu_shared.cpu_data;// Remove this and replace with your own data
-    let data_padded: Vec<f32> =output_gpu_padded.cpu_data; // Remove this and replace with your own data
-    //
-
-    // Naive
-    println!("convolution naive MSE: {}", mean_square_error(&ground_truth, &data_naive));
-    let success: bool = are_vectors_equivalent(&ground_truth, &data_naive);
-    println!("convolution naive success: {}!", success);
-
-    // Tiled
-    println!("convolution shared MSE: {}", mean_square_error(&ground_truth, &data_shared));
-    let success: bool = are_vectors_equivalent(&ground_truth, &data_shared);
-    println!("convolution shared success: {}!", success);
-
-    // Padded
-    println!("convolution padded MSE: {}", mean_square_error(&ground_truth, &data_padded));
-    let success: bool = are_vectors_equivalent(&ground_truth, &data_padded);
-    println!("convolution padded success: {}!", success);
-
-    success
-}
\ No newline at end of file
+    /// The uniform all [`ConvolutionKernel`]s take: `(signal_length, filter_size, 0, 0)`.
+    pub fn uniform(&self, handles: &GPUHandles) -> Uniform {
+        Uniform::new(handles, self.signal_length, self.filter_size, 0, 0)
+    }
+
+    /// Elements of the signal [`ConvolutionKernel::Padded`] expects: `filter_size / 2`
+    /// zeros in front, then zeros up to whole blocks plus the rest of the filter.
+    pub fn padded_signal_length(&self, block_size: u32) -> usize {
+        self.signal_length.div_ceil(block_size as usize) * block_size as usize + self.filter_size
+            - 1
+    }
+
+    /// Binds the signal, filter and output for `config`, sized to cover the whole
+    /// output. `uniform` has to come from [`Self::uniform`]. Filters longer than
+    /// `config.max_filter_size` are an error.
+    pub fn invocation<'a>(
+        &self,
+        handles: &'a GPUHandles,
+        config: impl Into<ConvolutionConfig>,
+        uniform: &'a Uniform,
+        signal: &'a GPUVector,
+        filter: &'a GPUVector,
+        output: &'a mut GPUVector,
+    ) -> Result<KernelInvocation<'a>, KernelError> {
+        let config: ConvolutionConfig = config.into();
+        config.check_filter_size(self.filter_size)?;
+        let workgroups: usize = self.signal_length.div_ceil(config.block_size as usize);
+        Ok(
+            KernelInvocation::new(handles, &config.shader()?, config.kernel.entry_point())?
+                .uniform(uniform)
+                .read(signal)
+                .read(filter)
+                .read_write(output)
+                .workgroups(workgroups as u32, 1, 1),
+        )
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+
+use crate::reflection::BindingKind;
+
+/// Why a kernel could not be compiled or dispatched.
+#[derive(Debug)]
+pub enum KernelError {
+    /// The WGSL does not parse or validate.
+    Shader {
+        entry_point: String,
+        message: String,
+    },
+    MissingEntryPoint(String),
+    /// [`specialize`](crate::shaders::specialize) was given a constant the shader does
+    /// not declare.
+    UnknownConstant(String),
+    /// Only bind group 0 is bound.
+    UnsupportedGroup {
+        binding: u32,
+        group: u32,
+    },
+    /// Textures and samplers cannot be bound.
+    UnsupportedBinding(u32),
+    ArgumentCount {
+        expected: usize,
+        found: usize,
+    },
+    BindingMismatch {
+        binding: u32,
+        expected: BindingKind,
+        found: BindingKind,
+    },
+    BufferTooSmall {
+        binding: u32,
+        required: u64,
+        found: u64,
+    },
+    /// The buffer's element type has a different size than the shader's array elements.
+    ElementSize {
+        binding: u32,
+        expected: u64,
+        found: u64,
+    },
+    /// Arguments `first` and `second` are the same buffer and one of them writes it,
+    /// which wgpu does not allow within one dispatch.
+    AliasedWrite {
+        first: usize,
+        second: usize,
+    },
+    /// The filter is longer than the workgroup memory of the convolution kernel has room
+    /// for, see [`ConvolutionConfig`](crate::ConvolutionConfig).
+    FilterTooLarge {
+        filter_size: usize,
+        max_filter_size: u32,
+    },
+    /// The kernel or its dispatch needs more than the device's [`wgpu::Limits`] allow.
+    DeviceLimit {
+        limit: &'static str,
+        required: u64,
+        allowed: u64,
+    },
+    /// A [`histogram`](crate::histogram()) needs at least one bin.
+    NoBins,
+    Readback(wgpu::BufferAsyncError),
+}
+
+impl fmt::Display for KernelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KernelError::Shader {
+                entry_point,
+                message,
+            } => write!(f, "Shader of `{entry_point}` is invalid:\n{message}"),
+            KernelError::MissingEntryPoint(name) => {
+                write!(f, "The shader has no compute entry point `{name}`")
+            }
+            KernelError::UnknownConstant(name) => {
+                write!(f, "The shader declares no `const {name}: u32`")
+            }
+            KernelError::UnsupportedGroup { binding, group } => write!(
+                f,
+                "Binding {binding} is in bind group {group}, only group 0 is supported"
+            ),
+            KernelError::UnsupportedBinding(binding) => {
+                write!(f, "Binding {binding} is not a buffer")
+            }
+            KernelError::ArgumentCount { expected, found } => write!(
+                f,
+                "The kernel uses {expected} bindings but {found} arguments were given"
+            ),
+            KernelError::BindingMismatch {
+                binding,
+                expected,
+                found,
+            } => write!(f, "Binding {binding} is {expected} but got {found}"),
+            KernelError::BufferTooSmall {
+                binding,
+                required,
+                found,
+            } => write!(
+                f,
+                "Binding {binding} needs at least {required} bytes but the buffer has {found}"
+            ),
+            KernelError::ElementSize {
+                binding,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Binding {binding} holds elements of {expected} bytes but the buffer's are {found}"
+            ),
+            KernelError::AliasedWrite { first, second } => write!(
+                f,
+                "Arguments {first} and {second} are the same buffer, which cannot be written while bound twice"
+            ),
+            KernelError::FilterTooLarge {
+                filter_size,
+                max_filter_size,
+            } => write!(
+                f,
+                "A filter of {filter_size} taps does not fit the kernel's maximum of {max_filter_size}"
+            ),
+            KernelError::DeviceLimit {
+                limit,
+                required,
+                allowed,
+            } => write!(
+                f,
+                "The kernel needs {required} for `{limit}` but the device allows {allowed}"
+            ),
+            KernelError::NoBins => write!(f, "A histogram needs at least one bin"),
+            KernelError::Readback(error) => write!(f, "Failed to read results back: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for KernelError {}
+
+impl From<wgpu::BufferAsyncError> for KernelError {
+    fn from(error: wgpu::BufferAsyncError) -> Self {
+        KernelError::Readback(error)
+    }
+}
@@ -0,0 +1,186 @@
+//! Futures for work on the GPU.
+//!
+//! wgpu only runs its callbacks when the device is polled. Every [`GPUHandles`] owns a
+//! background thread that does this, calling `device.poll(Maintain::Wait)` while any
+//! [`GpuFuture`] of it is waiting and sleeping on a condition variable otherwise. The
+//! callback stores the result and wakes the future's task, so a pending future costs
+//! nothing on the executor's thread and any executor works, e.g. `pollster::block_on`.
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+
+use wgpu::{Device, SubmissionIndex};
+
+use crate::context::GPUHandles;
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+#[derive(Default)]
+struct PollerState {
+    /// Callbacks handed to wgpu that have not run yet.
+    waiting: usize,
+    stopped: bool,
+}
+
+#[derive(Default)]
+struct PollerShared {
+    state: Mutex<PollerState>,
+    changed: Condvar,
+}
+
+/// The thread polling the device of a [`GPUHandles`]. Dropping it stops and joins the
+/// thread, so the thread's reference to the device is gone once the drop returns.
+pub(crate) struct Poller {
+    shared: Arc<PollerShared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Poller {
+    pub(crate) fn spawn(device: Arc<Device>) -> Self {
+        let shared: Arc<PollerShared> = Arc::default();
+        let polled = shared.clone();
+        let thread: JoinHandle<()> = thread::Builder::new()
+            .name("wgpu device poller".to_string())
+            .spawn(move || loop {
+                {
+                    let mut state = lock(&polled.state);
+                    while state.waiting == 0 && !state.stopped {
+                        state = polled
+                            .changed
+                            .wait(state)
+                            .unwrap_or_else(|error| error.into_inner());
+                    }
+                    if state.stopped {
+                        return;
+                    }
+                }
+                // Blocks until the submitted work is done and runs the callbacks.
+                device.poll(wgpu::Maintain::Wait);
+            })
+            .expect("failed to spawn the device poll thread");
+        Self {
+            shared,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Poller {
+    fn drop(&mut self) {
+        lock(&self.shared.state).stopped = true;
+        self.shared.changed.notify_all();
+        if let Some(thread) = self.thread.take() {
+            // A panic on the poll thread was already reported there.
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Keeps the poller polling until the callback that owns it has run or was dropped.
+struct Waiting(Arc<PollerShared>);
+
+impl Waiting {
+    fn new(poller: &Poller) -> Self {
+        lock(&poller.shared.state).waiting += 1;
+        poller.shared.changed.notify_all();
+        Self(poller.shared.clone())
+    }
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        lock(&self.0.state).waiting -= 1;
+    }
+}
+
+struct Slot<T> {
+    value: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// Resolves to the value a wgpu callback hands over.
+pub struct GpuFuture<'a, T> {
+    slot: Arc<Mutex<Slot<T>>>,
+    handles: PhantomData<&'a GPUHandles>,
+}
+
+/// A callback to give to wgpu and the future that resolves once wgpu called it.
+pub(crate) fn gpu_channel<T: Send + 'static>(
+    handles: &GPUHandles,
+) -> (impl FnOnce(T) + Send + 'static, GpuFuture<'_, T>) {
+    let slot: Arc<Mutex<Slot<T>>> = Arc::new(Mutex::new(Slot {
+        value: None,
+        waker: None,
+    }));
+    let waiting: Waiting = Waiting::new(&handles.poller);
+    let sender = {
+        let slot = slot.clone();
+        move |value: T| {
+            let waker: Option<Waker> = {
+                let mut slot = lock(&slot);
+                slot.value = Some(value);
+                slot.waker.take()
+            };
+            drop(waiting);
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    };
+    (
+        sender,
+        GpuFuture {
+            slot,
+            handles: PhantomData,
+        },
+    )
+}
+
+impl<T> Future for GpuFuture<'_, T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<T> {
+        // The callback takes the waker under the same lock it stores the value under,
+        // so it either sees this waker or we see its value.
+        let mut slot = lock(&self.slot);
+        match slot.value.take() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                slot.waker = Some(context.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Kernels handed to the queue. Later submissions run after this one, so dependent
+/// kernels can be submitted right away without waiting.
+pub struct Submission {
+    index: SubmissionIndex,
+}
+
+impl Submission {
+    pub(crate) fn new(index: SubmissionIndex) -> Self {
+        Self { index }
+    }
+
+    /// Resolves once the GPU has finished everything submitted to the queue so far,
+    /// which includes this submission. Later submissions are not waited for.
+    pub fn done<'a>(&self, handles: &'a GPUHandles) -> GpuFuture<'a, ()> {
+        let (sender, future) = gpu_channel(handles);
+        handles.queue.on_submitted_work_done(move || sender(()));
+        future
+    }
+
+    /// Blocks until the GPU has finished this submission.
+    pub fn wait(self, handles: &GPUHandles) {
+        handles
+            .device
+            .poll(wgpu::Maintain::WaitForSubmissionIndex(self.index));
+    }
+}
@@ -0,0 +1,322 @@
+//! Matrix multiplication `output = op(left) * op(right)` of any shape, where `op`
+//! optionally transposes.
+//!
+//! All matrices are row major. `output` is `rows` x `columns`, `op(left)` is `rows` x
+//! `inner` and `op(right)` is `inner` x `columns`. A transposed operand is stored the
+//! other way around, e.g. `left` as `inner` x `rows`.
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use wgpu::Limits;
+
+use crate::autotune::{benchmark, Autotuner};
+use crate::buffers::{GPUVector, Uniform};
+use crate::context::GPUHandles;
+use crate::error::KernelError;
+use crate::invocation::KernelInvocation;
+use crate::shaders;
+
+/// The kernels able to multiply matrices of any shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GemmKernel {
+    /// One thread per output element.
+    Naive,
+    /// Tiles of both operands staged in workgroup memory.
+    Tiled,
+    /// Tiled, with every thread accumulating a block of outputs in registers.
+    RegisterBlocked,
+}
+
+impl GemmKernel {
+    pub const ALL: [GemmKernel; 3] = [
+        GemmKernel::Naive,
+        GemmKernel::Tiled,
+        GemmKernel::RegisterBlocked,
+    ];
+
+    pub fn shader(self) -> &'static str {
+        match self {
+            GemmKernel::Naive => shaders::MATRIX_MULTIPLICATION_NAIVE,
+            GemmKernel::Tiled => shaders::MATRIX_MULTIPLICATION_TILED,
+            GemmKernel::RegisterBlocked => shaders::MATRIX_MULTIPLICATION_REGISTER,
+        }
+    }
+
+    pub fn entry_point(self) -> &'static str {
+        match self {
+            GemmKernel::Naive => "matmul_naive",
+            GemmKernel::Tiled => "matmul_tiled",
+            GemmKernel::RegisterBlocked => "matmul_register",
+        }
+    }
+}
+
+impl fmt::Display for GemmKernel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name: &str = match self {
+            GemmKernel::Naive => "naive",
+            GemmKernel::Tiled => "tiled",
+            GemmKernel::RegisterBlocked => "register",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for GemmKernel {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        GemmKernel::ALL
+            .into_iter()
+            .find(|kernel| kernel.to_string() == name)
+            .ok_or_else(|| format!("Unknown GEMM kernel `{name}`"))
+    }
+}
+
+/// A [`GemmKernel`] with its tile sizes, specialized into the shader when the pipeline
+/// is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GemmConfig {
+    pub kernel: GemmKernel,
+    /// The workgroup is `tile_size` x `tile_size` threads, the tiled kernels also step
+    /// through the inner dimension by `tile_size`.
+    pub tile_size: u32,
+    /// Outputs per thread along each dimension, only used by
+    /// [`GemmKernel::RegisterBlocked`].
+    pub work: u32,
+}
+
+impl GemmConfig {
+    /// The sizes the shaders are written with.
+    pub fn new(kernel: GemmKernel) -> Self {
+        let work: u32 = match kernel {
+            GemmKernel::RegisterBlocked => 4,
+            GemmKernel::Naive | GemmKernel::Tiled => 1,
+        };
+        Self {
+            kernel,
+            tile_size: 16,
+            work,
+        }
+    }
+
+    pub fn with_tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    pub fn with_work(mut self, work: u32) -> Self {
+        self.work = work;
+        self
+    }
+
+    /// Output elements computed by one workgroup along each dimension.
+    pub fn block_size(&self) -> u32 {
+        self.tile_size * self.work
+    }
+
+    /// The kernel's shader with the sizes and the constants derived from them filled in.
+    pub fn shader(&self) -> Result<String, KernelError> {
+        let tile_size: u32 = self.tile_size;
+        match self.kernel {
+            GemmKernel::Naive => {
+                shaders::specialize(self.kernel.shader(), &[("TILE_SIZE", tile_size)])
+            }
+            GemmKernel::Tiled => shaders::specialize(
+                self.kernel.shader(),
+                &[
+                    ("TILE_SIZE", tile_size),
+                    ("SHARED_SIZE", tile_size * tile_size),
+                ],
+            ),
+            GemmKernel::RegisterBlocked => shaders::specialize(
+                self.kernel.shader(),
+                &[
+                    ("TILE_SIZE", tile_size),
+                    ("WORK", self.work),
+                    ("BLOCK_SIZE", self.block_size()),
+                    ("SHARED_SIZE", self.block_size() * tile_size),
+                    ("ACCUMULATORS", self.work * self.work),
+                ],
+            ),
+        }
+    }
+
+    /// Whether the workgroup and its workgroup memory fit in the device's limits.
+    pub fn fits(&self, limits: &Limits) -> bool {
+        let shared_bytes: u32 = match self.kernel {
+            GemmKernel::Naive => 0,
+            GemmKernel::Tiled | GemmKernel::RegisterBlocked => {
+                2 * self.block_size() * self.tile_size * std::mem::size_of::<f32>() as u32
+            }
+        };
+        self.tile_size * self.tile_size <= limits.max_compute_invocations_per_workgroup
+            && self.tile_size <= limits.max_compute_workgroup_size_x
+            && self.tile_size <= limits.max_compute_workgroup_size_y
+            && shared_bytes <= limits.max_compute_workgroup_storage_size
+    }
+
+    /// Every configuration worth trying that fits in `limits`.
+    pub fn candidates(limits: &Limits) -> Vec<GemmConfig> {
+        let mut candidates: Vec<GemmConfig> = Vec::new();
+        for tile_size in [8, 16, 32] {
+            candidates.push(GemmConfig::new(GemmKernel::Naive).with_tile_size(tile_size));
+            candidates.push(GemmConfig::new(GemmKernel::Tiled).with_tile_size(tile_size));
+            for work in [2, 4, 8] {
+                candidates.push(
+                    GemmConfig::new(GemmKernel::RegisterBlocked)
+                        .with_tile_size(tile_size)
+                        .with_work(work),
+                );
+            }
+        }
+        candidates.retain(|config| config.fits(limits));
+        candidates
+    }
+}
+
+impl From<GemmKernel> for GemmConfig {
+    fn from(kernel: GemmKernel) -> Self {
+        GemmConfig::new(kernel)
+    }
+}
+
+/// `register/16/4`: kernel, tile size and work per thread.
+impl fmt::Display for GemmConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.kernel, self.tile_size, self.work)
+    }
+}
+
+impl FromStr for GemmConfig {
+    type Err = String;
+
+    fn from_str(config: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = config.split('/').collect();
+        let [kernel, tile_size, work] = parts[..] else {
+            return Err(format!("Expected kernel/tile size/work, got `{config}`"));
+        };
+        let size = |value: &str| {
+            value
+                .parse::<u32>()
+                .map_err(|error| format!("Invalid size `{value}`: {error}"))
+        };
+        Ok(Self {
+            kernel: kernel.parse()?,
+            tile_size: size(tile_size)?,
+            work: size(work)?,
+        })
+    }
+}
+
+/// The shape of one matrix multiplication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Gemm {
+    pub rows: usize,
+    pub inner: usize,
+    pub columns: usize,
+    pub transpose_left: bool,
+    pub transpose_right: bool,
+}
+
+impl Gemm {
+    pub fn new(rows: usize, inner: usize, columns: usize) -> Self {
+        Self {
+            rows,
+            inner,
+            columns,
+            transpose_left: false,
+            transpose_right: false,
+        }
+    }
+
+    pub fn transpose_left(mut self, transpose: bool) -> Self {
+        self.transpose_left = transpose;
+        self
+    }
+
+    pub fn transpose_right(mut self, transpose: bool) -> Self {
+        self.transpose_right = transpose;
+        self
+    }
+
+    /// The uniform all [`GemmKernel`]s take: `(columns, rows, inner, transpose bits)`.
+    pub fn uniform(&self, handles: &GPUHandles) -> Uniform {
+        let transpose: usize = self.transpose_left as usize | (self.transpose_right as usize) << 1;
+        Uniform::new(handles, self.columns, self.rows, self.inner, transpose)
+    }
+
+    /// Binds the operands for `config`, e.g. a [`GemmKernel`] with its default sizes,
+    /// sized to cover the whole output. `uniform` has to come from [`Self::uniform`].
+    pub fn invocation<'a>(
+        &self,
+        handles: &'a GPUHandles,
+        config: impl Into<GemmConfig>,
+        uniform: &'a Uniform,
+        left: &'a GPUVector,
+        right: &'a GPUVector,
+        output: &'a mut GPUVector,
+    ) -> Result<KernelInvocation<'a>, KernelError> {
+        let config: GemmConfig = config.into();
+        let block_size: usize = config.block_size() as usize;
+        Ok(
+            KernelInvocation::new(handles, &config.shader()?, config.kernel.entry_point())?
+                .uniform(uniform)
+                .read(left)
+                .read(right)
+                .read_write(output)
+                .workgroups(
+                    self.columns.div_ceil(block_size) as u32,
+                    self.rows.div_ceil(block_size) as u32,
+                    1,
+                ),
+        )
+    }
+
+    /// Floating point operations, a multiply and an add per inner element of every output.
+    pub fn flops(&self) -> f64 {
+        2.0 * self.rows as f64 * self.inner as f64 * self.columns as f64
+    }
+
+    /// Bytes of the operands read plus the output written, each once. Tiling makes the
+    /// real traffic larger, but this is what the kernels can't avoid.
+    pub fn accessed_bytes(&self) -> u64 {
+        let elements: usize =
+            self.rows * self.inner + self.inner * self.columns + self.rows * self.columns;
+        (elements * std::mem::size_of::<f32>()) as u64
+    }
+
+    /// The fastest of [`GemmConfig::candidates`] for this shape on this device, from
+    /// `autotuner`'s cache or by timing each on zeroed matrices.
+    pub fn tune(
+        &self,
+        handles: &GPUHandles,
+        autotuner: &mut Autotuner,
+    ) -> Result<GemmConfig, KernelError> {
+        let key: String = format!(
+            "gemm {}x{}x{} transposed {} {}",
+            self.rows, self.inner, self.columns, self.transpose_left, self.transpose_right
+        );
+        let candidates: Vec<GemmConfig> = GemmConfig::candidates(&handles.device.limits());
+
+        let uniform: Uniform = self.uniform(handles);
+        let left: GPUVector = GPUVector::zeros(handles, &[self.rows, self.inner], "left", false);
+        let right: GPUVector =
+            GPUVector::zeros(handles, &[self.inner, self.columns], "right", false);
+        let mut output: GPUVector =
+            GPUVector::zeros(handles, &[self.rows, self.columns], "output", false);
+
+        autotuner.tune(
+            handles,
+            &key,
+            &candidates,
+            |config| -> Result<Duration, KernelError> {
+                benchmark(handles, 3, || {
+                    self.invocation(handles, config, &uniform, &left, &right, &mut output)?
+                        .submit()
+                })
+            },
+        )
+    }
+}
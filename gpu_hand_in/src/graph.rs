@@ -0,0 +1,333 @@
+//! Several kernels recorded into one command encoder and submitted together.
+//!
+//! The graph owns its buffers, so intermediate results stay on the GPU between
+//! nodes. Only buffers marked with [`KernelGraph::read_back`] are copied to the CPU,
+//! in the same submission as the kernels.
+//!
+//! ```no_run
+//! # use gpu_hand_in::*;
+//! # fn demo(handles: &GPUHandles) -> Result<(), KernelError> {
+//! let mut graph = KernelGraph::new(handles);
+//! let sizes = graph.uniform(4, 0, 0, 0);
+//! let a = graph.upload(vec![1.0f32; 4]);
+//! let sum = graph.zeros::<f32>(&[4]);
+//! let doubled = graph.zeros::<f32>(&[4]);
+//!
+//! graph
+//!     .node(shaders::VECTOR_ADD, "vector_add")?
+//!     .uniform(sizes).read(a).read(a).write(sum).threads(4, 1, 1)
+//!     .add()?;
+//! graph
+//!     .node(shaders::VECTOR_ADD, "vector_add")?
+//!     .uniform(sizes).read(sum).read(sum).write(doubled).threads(4, 1, 1)
+//!     .add()?;
+//! graph.read_back(doubled);
+//!
+//! graph.run()?;
+//! assert_eq!(graph.buffer(doubled).cpu_data, vec![4.0; 4]);
+//! # Ok(())
+//! # }
+//! ```
+use std::any::Any;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use bytemuck::Pod;
+use wgpu::{Buffer, CommandEncoder};
+
+use crate::buffers::{read_back, GpuBuffer, StorageBuffer, Uniform};
+use crate::context::GPUHandles;
+use crate::error::KernelError;
+use crate::kernels::{record_dispatch, Kernel};
+use crate::reflection::{check_workgroups, ArgumentInfo, BindingKind};
+
+/// A buffer owned by a [`KernelGraph`].
+pub struct BufferId<T> {
+    index: usize,
+    element: PhantomData<T>,
+}
+
+impl<T> Clone for BufferId<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for BufferId<T> {}
+
+/// A uniform owned by a [`KernelGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniformId(usize);
+
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    Uniform(usize),
+    Read(usize),
+    Write(usize),
+}
+
+struct Node {
+    kernel: Arc<Kernel>,
+    slots: Vec<Slot>,
+    workgroups: [u32; 3],
+}
+
+/// A [`GpuBuffer`] of any element type, so one graph can hold several.
+trait Resident: StorageBuffer {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn ensure_staging_buffer(&mut self, handles: &GPUHandles);
+    fn as_storage_mut(&mut self) -> &mut dyn StorageBuffer;
+}
+
+impl<T: Pod> Resident for GpuBuffer<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn ensure_staging_buffer(&mut self, handles: &GPUHandles) {
+        GpuBuffer::ensure_staging_buffer(self, handles);
+    }
+
+    fn as_storage_mut(&mut self) -> &mut dyn StorageBuffer {
+        self
+    }
+}
+
+pub struct KernelGraph<'a> {
+    handles: &'a GPUHandles,
+    buffers: Vec<Box<dyn Resident>>,
+    uniforms: Vec<Uniform>,
+    nodes: Vec<Node>,
+    outputs: Vec<usize>,
+}
+
+impl<'a> KernelGraph<'a> {
+    pub fn new(handles: &'a GPUHandles) -> Self {
+        Self {
+            handles,
+            buffers: Vec::new(),
+            uniforms: Vec::new(),
+            nodes: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Hands an existing buffer to the graph.
+    pub fn insert<T: Pod>(&mut self, buffer: GpuBuffer<T>) -> BufferId<T> {
+        self.buffers.push(Box::new(buffer));
+        BufferId {
+            index: self.buffers.len() - 1,
+            element: PhantomData,
+        }
+    }
+
+    /// Uploads `data` as a one dimensional buffer.
+    pub fn upload<T: Pod>(&mut self, data: Vec<T>) -> BufferId<T> {
+        let label: String = format!("graph buffer {}", self.buffers.len());
+        self.insert(GpuBuffer::new(self.handles, data, &label, false))
+    }
+
+    /// A zeroed buffer of `shape`, e.g. for intermediate results.
+    pub fn zeros<T: Pod>(&mut self, shape: &[usize]) -> BufferId<T> {
+        let label: String = format!("graph buffer {}", self.buffers.len());
+        self.insert(GpuBuffer::zeros(self.handles, shape, &label, false))
+    }
+
+    pub fn uniform(
+        &mut self,
+        argument_0: usize,
+        argument_1: usize,
+        argument_2: usize,
+        argument_3: usize,
+    ) -> UniformId {
+        let uniform: Uniform =
+            Uniform::new(self.handles, argument_0, argument_1, argument_2, argument_3);
+        self.uniforms.push(uniform);
+        UniformId(self.uniforms.len() - 1)
+    }
+
+    pub fn buffer<T: Pod>(&self, id: BufferId<T>) -> &GpuBuffer<T> {
+        self.buffers[id.index]
+            .as_any()
+            .downcast_ref()
+            .expect("BufferId of another graph")
+    }
+
+    /// Gives access to e.g. [`GpuBuffer::upload`] between runs.
+    pub fn buffer_mut<T: Pod>(&mut self, id: BufferId<T>) -> &mut GpuBuffer<T> {
+        self.buffers[id.index]
+            .as_any_mut()
+            .downcast_mut()
+            .expect("BufferId of another graph")
+    }
+
+    /// Starts a node running `entry_point` of `shader`. The node runs after all nodes
+    /// added before it.
+    pub fn node(
+        &mut self,
+        shader: &str,
+        entry_point: &str,
+    ) -> Result<NodeBuilder<'_, 'a>, KernelError> {
+        let kernel: Arc<Kernel> = self.handles.kernel(shader, entry_point)?;
+        Ok(NodeBuilder {
+            graph: self,
+            node: Node {
+                kernel,
+                slots: Vec::new(),
+                workgroups: [1, 1, 1],
+            },
+        })
+    }
+
+    /// Copies `id` back to its `cpu_data` at the end of every run.
+    pub fn read_back<T: Pod>(&mut self, id: BufferId<T>) {
+        if !self.outputs.contains(&id.index) {
+            self.outputs.push(id.index);
+        }
+    }
+
+    /// Records every node and the read-backs into one encoder, submits it and waits
+    /// for the read-backs.
+    pub fn run(&mut self) -> Result<(), KernelError> {
+        pollster::block_on(self.run_async())
+    }
+
+    /// Same as [`Self::run`] without blocking.
+    pub async fn run_async(&mut self) -> Result<(), KernelError> {
+        let handles: &GPUHandles = self.handles;
+        let mut encoder: CommandEncoder =
+            handles
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("kernel graph"),
+                });
+
+        for node in &self.nodes {
+            let buffers: Vec<&Buffer> = node
+                .slots
+                .iter()
+                .map(|slot| match *slot {
+                    Slot::Uniform(index) => &self.uniforms[index].storage_buffer,
+                    Slot::Read(index) | Slot::Write(index) => self.buffers[index].storage_buffer(),
+                })
+                .collect();
+            record_dispatch(
+                handles,
+                &mut encoder,
+                &node.kernel,
+                &buffers,
+                node.workgroups,
+            );
+        }
+
+        for &index in &self.outputs {
+            self.buffers[index].ensure_staging_buffer(handles);
+            self.buffers[index].enqueue_readback(&mut encoder);
+        }
+        handles.queue.submit(Some(encoder.finish()));
+
+        let outputs: &[usize] = &self.outputs;
+        let mut read: Vec<&mut dyn StorageBuffer> = self
+            .buffers
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| outputs.contains(index))
+            .map(|(_, buffer)| buffer.as_storage_mut())
+            .collect();
+        read_back(handles, &mut read).await
+    }
+}
+
+/// One dispatch being added to a [`KernelGraph`]. Arguments are given in binding
+/// order, like for [`KernelInvocation`](crate::KernelInvocation).
+pub struct NodeBuilder<'g, 'a> {
+    graph: &'g mut KernelGraph<'a>,
+    node: Node,
+}
+
+impl NodeBuilder<'_, '_> {
+    pub fn uniform(mut self, id: UniformId) -> Self {
+        self.node.slots.push(Slot::Uniform(id.0));
+        self
+    }
+
+    pub fn read<T: Pod>(mut self, id: BufferId<T>) -> Self {
+        self.node.slots.push(Slot::Read(id.index));
+        self
+    }
+
+    pub fn write<T: Pod>(mut self, id: BufferId<T>) -> Self {
+        self.node.slots.push(Slot::Write(id.index));
+        self
+    }
+
+    pub fn workgroups(mut self, x: u32, y: u32, z: u32) -> Self {
+        self.node.workgroups = [x, y, z];
+        self
+    }
+
+    /// Enough workgroups to cover `x` x `y` x `z` threads with the shader's
+    /// `@workgroup_size`.
+    pub fn threads(self, x: u32, y: u32, z: u32) -> Self {
+        let [size_x, size_y, size_z] = self.node.kernel.reflection.workgroup_size;
+        self.workgroups(
+            x.div_ceil(size_x.max(1)),
+            y.div_ceil(size_y.max(1)),
+            z.div_ceil(size_z.max(1)),
+        )
+    }
+
+    /// Checks the arguments against the shader, and the workgroup counts against the
+    /// device, and appends the node to the graph. A buffer may be read through several
+    /// arguments, but not bound twice if one of them writes it.
+    pub fn add(self) -> Result<(), KernelError> {
+        let graph: &mut KernelGraph = self.graph;
+        for (second, slot) in self.node.slots.iter().enumerate() {
+            let first: Option<usize> =
+                self.node.slots[..second]
+                    .iter()
+                    .position(|earlier| match (*earlier, *slot) {
+                        (Slot::Read(a), Slot::Write(b))
+                        | (Slot::Write(a), Slot::Read(b))
+                        | (Slot::Write(a), Slot::Write(b)) => a == b,
+                        _ => false,
+                    });
+            if let Some(first) = first {
+                return Err(KernelError::AliasedWrite { first, second });
+            }
+        }
+
+        let arguments: Vec<ArgumentInfo> = self
+            .node
+            .slots
+            .iter()
+            .map(|slot| match *slot {
+                Slot::Uniform(index) => ArgumentInfo {
+                    kind: BindingKind::Uniform,
+                    element_size: None,
+                    size: graph.uniforms[index].storage_buffer.size(),
+                },
+                Slot::Read(index) | Slot::Write(index) => {
+                    let buffer: &dyn Resident = graph.buffers[index].as_ref();
+                    ArgumentInfo {
+                        kind: BindingKind::Storage {
+                            read_only: matches!(slot, Slot::Read(_)),
+                        },
+                        element_size: Some(buffer.element_size() as u64),
+                        size: buffer.storage_buffer().size(),
+                    }
+                }
+            })
+            .collect();
+        self.node.kernel.reflection.check_arguments(&arguments)?;
+        check_workgroups(self.node.workgroups, &graph.handles.device.limits())?;
+
+        graph.nodes.push(self.node);
+        Ok(())
+    }
+}
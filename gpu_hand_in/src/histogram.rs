@@ -0,0 +1,40 @@
+//! Counting `u32` values into bins with [`shaders::HISTOGRAM`].
+//!
+//! ```no_run
+//! # use gpu_hand_in::*;
+//! # fn demo(handles: &GPUHandles) -> Result<(), KernelError> {
+//! let values: GpuBuffer<u32> = GpuBuffer::new(handles, vec![0, 3, 3, 9], "values", false);
+//! let bins: GpuBuffer<u32> = histogram(handles, &values, 4)?;
+//! assert_eq!(bins.cpu_data, vec![1, 0, 0, 3]);
+//! # Ok(())
+//! # }
+//! ```
+use crate::buffers::{GpuBuffer, Uniform};
+use crate::context::GPUHandles;
+use crate::error::KernelError;
+use crate::invocation::KernelInvocation;
+use crate::shaders;
+
+/// Counts `values` into `bin_count` bins, values past the last bin in the last bin.
+///
+/// The kernel clamps to `bin_count - 1`, so zero bins is an error instead of a
+/// dispatch.
+pub fn histogram(
+    handles: &GPUHandles,
+    values: &GpuBuffer<u32>,
+    bin_count: usize,
+) -> Result<GpuBuffer<u32>, KernelError> {
+    if bin_count == 0 {
+        return Err(KernelError::NoBins);
+    }
+
+    let uniform: Uniform = Uniform::new(handles, values.len(), bin_count, 0, 0);
+    let mut bins: GpuBuffer<u32> = GpuBuffer::zeros(handles, &[bin_count], "bins", true);
+    KernelInvocation::new(handles, shaders::HISTOGRAM, "histogram")?
+        .uniform(&uniform)
+        .read(values)
+        .read_write(&mut bins)
+        .threads(values.len() as u32, 1, 1)
+        .dispatch()?;
+    Ok(bins)
+}
@@ -0,0 +1,202 @@
+//! Dispatching a kernel with any ordered list of buffers and uniforms.
+//!
+//! ```no_run
+//! # use gpu_hand_in::*;
+//! # fn demo(handles: &GPUHandles, uniform: &Uniform, a: &GPUVector, b: &GPUVector, out: &mut GPUVector) -> Result<(), KernelError> {
+//! KernelInvocation::new(handles, shaders::VECTOR_ADD, "vector_add")?
+//!     .uniform(uniform)
+//!     .read(a)
+//!     .read(b)
+//!     .read_write(out)
+//!     .threads(100, 1, 1)
+//!     .dispatch()?;
+//! # Ok(())
+//! # }
+//! ```
+use std::sync::Arc;
+
+use bytemuck::Pod;
+use wgpu::{Buffer, CommandEncoder};
+
+use crate::buffers::{read_back, GpuBuffer, StorageBuffer, Uniform};
+use crate::context::GPUHandles;
+use crate::error::KernelError;
+use crate::future::Submission;
+use crate::kernels::{record_dispatch, Kernel};
+use crate::reflection::{check_workgroups, ArgumentInfo, BindingKind};
+
+/// One bound resource, in the order of the kernel's binding numbers.
+pub enum Argument<'a> {
+    Uniform(&'a Uniform),
+    Read(&'a dyn StorageBuffer),
+    /// Read back into `cpu_data` after the dispatch if the buffer has a staging buffer.
+    ReadWrite(&'a mut dyn StorageBuffer),
+}
+
+impl Argument<'_> {
+    pub fn kind(&self) -> BindingKind {
+        match self {
+            Argument::Uniform(_) => BindingKind::Uniform,
+            Argument::Read(_) => BindingKind::Storage { read_only: true },
+            Argument::ReadWrite(_) => BindingKind::Storage { read_only: false },
+        }
+    }
+
+    fn buffer(&self) -> &Buffer {
+        match self {
+            Argument::Uniform(uniform) => &uniform.storage_buffer,
+            Argument::Read(buffer) => buffer.storage_buffer(),
+            Argument::ReadWrite(buffer) => buffer.storage_buffer(),
+        }
+    }
+
+    fn info(&self) -> ArgumentInfo {
+        let element_size: Option<u64> = match self {
+            Argument::Uniform(_) => None,
+            Argument::Read(buffer) => Some(buffer.element_size() as u64),
+            Argument::ReadWrite(buffer) => Some(buffer.element_size() as u64),
+        };
+        ArgumentInfo {
+            kind: self.kind(),
+            element_size,
+            size: self.buffer().size(),
+        }
+    }
+}
+
+/// Builds up one dispatch of a kernel.
+pub struct KernelInvocation<'a> {
+    handles: &'a GPUHandles,
+    kernel: Arc<Kernel>,
+    arguments: Vec<Argument<'a>>,
+    workgroups: [u32; 3],
+}
+
+impl<'a> KernelInvocation<'a> {
+    /// Compiles `entry_point` of `shader`, or takes it from [`GPUHandles::kernels`].
+    pub fn new(
+        handles: &'a GPUHandles,
+        shader: &str,
+        entry_point: &str,
+    ) -> Result<Self, KernelError> {
+        Ok(Self {
+            handles,
+            kernel: handles.kernel(shader, entry_point)?,
+            arguments: Vec::new(),
+            workgroups: [1, 1, 1],
+        })
+    }
+
+    pub fn kernel(&self) -> &Kernel {
+        &self.kernel
+    }
+
+    pub fn argument(mut self, argument: Argument<'a>) -> Self {
+        self.arguments.push(argument);
+        self
+    }
+
+    pub fn uniform(self, uniform: &'a Uniform) -> Self {
+        self.argument(Argument::Uniform(uniform))
+    }
+
+    pub fn read<T: Pod>(self, buffer: &'a GpuBuffer<T>) -> Self {
+        self.argument(Argument::Read(buffer))
+    }
+
+    pub fn read_write<T: Pod>(self, buffer: &'a mut GpuBuffer<T>) -> Self {
+        self.argument(Argument::ReadWrite(buffer))
+    }
+
+    /// Number of workgroups to launch in each dimension.
+    pub fn workgroups(mut self, x: u32, y: u32, z: u32) -> Self {
+        self.workgroups = [x, y, z];
+        self
+    }
+
+    /// Enough workgroups to cover `x` x `y` x `z` threads with the shader's
+    /// `@workgroup_size`.
+    pub fn threads(self, x: u32, y: u32, z: u32) -> Self {
+        let [size_x, size_y, size_z] = self.kernel.reflection.workgroup_size;
+        self.workgroups(
+            x.div_ceil(size_x.max(1)),
+            y.div_ceil(size_y.max(1)),
+            z.div_ceil(size_z.max(1)),
+        )
+    }
+
+    /// Checks the arguments against the bindings reflected from the shader, and the
+    /// workgroup counts against the device.
+    pub fn validate(&self) -> Result<(), KernelError> {
+        let arguments: Vec<ArgumentInfo> = self.arguments.iter().map(Argument::info).collect();
+        self.kernel.reflection.check_arguments(&arguments)?;
+        check_workgroups(self.workgroups, &self.handles.device.limits())
+    }
+
+    /// Validates, runs the kernel and waits for the read-write buffers with a staging
+    /// buffer to be read back.
+    pub fn dispatch(self) -> Result<(), KernelError> {
+        pollster::block_on(self.dispatch_async())
+    }
+
+    /// Same as [`Self::dispatch`] without blocking.
+    pub async fn dispatch_async(mut self) -> Result<(), KernelError> {
+        let handles: &GPUHandles = self.handles;
+        let mut encoder: CommandEncoder = self.encode()?;
+
+        let mut outputs: Vec<&mut dyn StorageBuffer> = self
+            .arguments
+            .drain(..)
+            .filter_map(|argument| match argument {
+                Argument::ReadWrite(buffer) if buffer.staging_buffer().is_some() => Some(buffer),
+                _ => None,
+            })
+            .collect();
+
+        // Add the commands to the encoder copying the outputs back to CPU
+        for output in outputs.iter_mut() {
+            output.enqueue_readback(&mut encoder);
+        }
+
+        // Finish our encoder and submit it to the queue.
+        handles.queue.submit(Some(encoder.finish()));
+
+        read_back(handles, &mut outputs).await
+    }
+
+    /// Validates and queues the kernel without reading anything back, so the next
+    /// kernel can consume its outputs on the GPU. Read results later with
+    /// [`GpuBuffer::download_async`].
+    pub fn submit(self) -> Result<Submission, KernelError> {
+        let encoder: CommandEncoder = self.encode()?;
+        let index = self.handles.queue.submit(Some(encoder.finish()));
+        Ok(Submission::new(index))
+    }
+
+    fn encode(&self) -> Result<CommandEncoder, KernelError> {
+        self.validate()?;
+
+        // The command encode is essentially just a list of commands
+        // we can accumulate and then send together to the GPU.
+        // The command list emitted by the command encoder
+        // will be added to the queue, once it has been finished.
+        let mut encoder: CommandEncoder = self
+            .handles
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.record(&mut encoder);
+        Ok(encoder)
+    }
+
+    /// Records the dispatch into `encoder` without validating.
+    pub(crate) fn record(&self, encoder: &mut CommandEncoder) {
+        let buffers: Vec<&Buffer> = self.arguments.iter().map(Argument::buffer).collect();
+        record_dispatch(
+            self.handles,
+            encoder,
+            &self.kernel,
+            &buffers,
+            self.workgroups,
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use wgpu::{
+    BindGroup, BindGroupEntry, BindGroupLayout, BindingResource, Buffer, CommandEncoder,
+    ComputePass, ComputePipeline, Device, ShaderModule,
+};
+
+use crate::buffers::{GPUVector, Uniform};
+use crate::context::GPUHandles;
+use crate::error::KernelError;
+use crate::invocation::KernelInvocation;
+use crate::reflection::{reflect, Reflection};
+use crate::shaders::specialize;
+
+// Compile our shader code.
+pub fn create_shader_module(gpu_handles: &GPUHandles, shader: &str) -> ShaderModule {
+    compile_shader_module(&gpu_handles.device, shader)
+}
+
+fn compile_shader_module(device: &Device, shader: &str) -> ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(shader)),
+    })
+}
+
+// Create a compute pipeline.
+pub fn create_compute_pipeline(
+    gpu_handles: &GPUHandles,
+    module: &ShaderModule,
+    entry_point: &str,
+) -> ComputePipeline {
+    compile_compute_pipeline(&gpu_handles.device, module, entry_point)
+}
+
+fn compile_compute_pipeline(
+    device: &Device,
+    module: &ShaderModule,
+    entry_point: &str,
+) -> ComputePipeline {
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some(entry_point),
+        layout: None,
+        module,
+        entry_point,
+    })
+}
+
+/// A compiled entry point together with the bind group layout wgpu derived for it.
+pub struct Kernel {
+    pub pipeline: ComputePipeline,
+    pub bind_group_layout: BindGroupLayout,
+    pub reflection: Reflection,
+}
+
+impl Kernel {
+    /// Reflects the shader before handing it to wgpu, so invalid WGSL, or a workgroup
+    /// larger than the device allows, is an error instead of a panic inside the device.
+    pub fn new(device: &Device, shader: &str, entry_point: &str) -> Result<Self, KernelError> {
+        // Inlines constants used in `@workgroup_size`.
+        let shader: String = specialize(shader, &[])?;
+        let reflection: Reflection = reflect(&shader, entry_point)?;
+        reflection.check_limits(&device.limits())?;
+        let module: ShaderModule = compile_shader_module(device, &shader);
+        let pipeline: ComputePipeline = compile_compute_pipeline(device, &module, entry_point);
+        let bind_group_layout: BindGroupLayout = pipeline.get_bind_group_layout(0);
+        Ok(Self {
+            pipeline,
+            bind_group_layout,
+            reflection,
+        })
+    }
+}
+
+/// Compiled kernels by shader source and entry point, so dispatching the same kernel
+/// again skips WGSL compilation and pipeline creation.
+#[derive(Default)]
+pub struct KernelCache {
+    kernels: Mutex<HashMap<String, HashMap<String, Arc<Kernel>>>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl KernelCache {
+    /// The cached kernel, compiling it on first use. Failed compilations are not cached.
+    pub fn get_or_compile(
+        &self,
+        device: &Device,
+        shader: &str,
+        entry_point: &str,
+    ) -> Result<Arc<Kernel>, KernelError> {
+        let mut kernels = self
+            .kernels
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        // Look up by &str first so a hit does not copy the source.
+        if let Some(kernel) = kernels
+            .get(shader)
+            .and_then(|entry_points| entry_points.get(entry_point))
+        {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(kernel.clone());
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let kernel: Arc<Kernel> = Arc::new(Kernel::new(device, shader, entry_point)?);
+        kernels
+            .entry(shader.to_string())
+            .or_default()
+            .insert(entry_point.to_string(), kernel.clone());
+        Ok(kernel)
+    }
+
+    /// Number of compiled kernels.
+    pub fn len(&self) -> usize {
+        let kernels = self
+            .kernels
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        kernels.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all compiled kernels. The hit and miss counts are kept.
+    pub fn clear(&self) {
+        self.kernels
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .clear();
+    }
+
+    /// Lookups answered from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Lookups that had to compile.
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+// Create a bind group from a vector
+// of bindings.
+pub fn create_bind_group(
+    gpu_handles: &GPUHandles,
+    bind_group_layout: &BindGroupLayout,
+    to_be_bound: Vec<(u32, BindingResource)>,
+) -> BindGroup {
+    let entries: Vec<BindGroupEntry> = to_be_bound
+        .into_iter()
+        .map(|(binding, resource)| BindGroupEntry { binding, resource })
+        .collect();
+
+    gpu_handles
+        .device
+        .create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: bind_group_layout,
+            entries: entries.as_slice(),
+        })
+}
+
+/// Records one dispatch of `kernel` into `encoder`, binding `buffers` to the kernel's
+/// bindings in order. The buffers must have been checked with
+/// [`Reflection::check_arguments`].
+pub(crate) fn record_dispatch(
+    handles: &GPUHandles,
+    encoder: &mut CommandEncoder,
+    kernel: &Kernel,
+    buffers: &[&Buffer],
+    workgroups: [u32; 3],
+) {
+    // Instantiates the bind group, specifying the binding of buffers.
+    // The bind group itself is cheap and refers to this call's buffers, so it
+    // is created every time.
+    let to_be_bound: Vec<(u32, BindingResource)> = kernel
+        .reflection
+        .bindings
+        .iter()
+        .zip(buffers)
+        .map(|(binding, buffer)| (binding.binding, buffer.as_entire_binding()))
+        .collect();
+    let bind_group: BindGroup = create_bind_group(handles, &kernel.bind_group_layout, to_be_bound);
+
+    // This enclosing scope makes sure the ComputePass is dropped.
+    {
+        let entry_point: &str = &kernel.reflection.entry_point;
+        let mut cpass: ComputePass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some(entry_point),
+        });
+        cpass.set_pipeline(&kernel.pipeline);
+        cpass.set_bind_group(0, &bind_group, &[]);
+        cpass.insert_debug_marker(entry_point);
+        let [x, y, z] = workgroups;
+        cpass.dispatch_workgroups(x, y, z);
+    }
+}
+
+/// Dispatches `shader_function` of `shader_file` over
+/// `launch_blocks_x` x `launch_blocks_y` workgroups and reads `output` back into its
+/// `cpu_data`.
+///
+/// The kernel binds `uniform` at 0, `input_a` at 1, `input_b` at 2 and `output` at 3.
+/// The block sizes are only reported, the shader's `@workgroup_size` decides them.
+/// Kernels with other bindings go through [`KernelInvocation`] instead.
+///
+/// Fails like [`KernelInvocation::dispatch`] if the kernel does not match these
+/// bindings or the device.
+#[allow(clippy::too_many_arguments)]
+pub fn run_compute_shader(
+    handles: &GPUHandles,
+    block_size_x: usize,
+    launch_blocks_x: u32,
+    block_size_y: usize,
+    launch_blocks_y: u32,
+    shader_file: &str,
+    shader_function: &str,
+    uniform: &Uniform,
+    input_a: &GPUVector,
+    input_b: &GPUVector,
+    output: &mut GPUVector,
+) -> Result<(), KernelError> {
+    log::debug!(
+        "Dispatching {} x blocks of {} threads and {} y blocks of {} threads each for a total of {} threads!",
+        launch_blocks_x,
+        block_size_x,
+        launch_blocks_y,
+        block_size_y,
+        launch_blocks_x as usize * launch_blocks_y as usize * block_size_x * block_size_y
+    );
+
+    KernelInvocation::new(handles, shader_file, shader_function)?
+        .uniform(uniform)
+        .read(input_a)
+        .read(input_b)
+        .read_write(output)
+        .workgroups(launch_blocks_x, launch_blocks_y, 1)
+        .dispatch()
+}
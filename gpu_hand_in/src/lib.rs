@@ -0,0 +1,58 @@
+//! A small compute layer on top of wgpu.
+//!
+//! * [`context`] finds an adapter and opens the device and queue ([`GPUHandles`]).
+//! * [`buffers`] holds data on the GPU: [`GpuBuffer`] for typed storage arrays
+//!   ([`GPUVector`] for `f32`) and [`Uniform`] for the four dimensions most kernels take.
+//! * [`kernels`] compiles WGSL once per device ([`KernelCache`]), [`reflection`] reads
+//!   the bindings it expects.
+//! * [`invocation`] binds any list of buffers and dispatches a kernel
+//!   ([`KernelInvocation`]). [`run_compute_shader`] is the shortcut for the common
+//!   uniform, two inputs and one output.
+//! * [`graph`] records several kernels into one submission, keeping intermediate
+//!   buffers on the GPU ([`KernelGraph`]).
+//! * [`histogram`] counts `u32` values into bins ([`histogram()`]).
+//! * [`convolution`] convolves a signal with a filter ([`Convolution`]), sizing the
+//!   workgroup memory with [`ConvolutionConfig`].
+//! * [`gemm`] multiplies matrices of any shape with a family of kernels ([`Gemm`]).
+//! * [`autotune`] times kernel configurations, e.g. [`GemmConfig::candidates`], and
+//!   caches the fastest per adapter on disk ([`Autotuner`]).
+//! * [`future`] lets kernels be submitted back to back and read back without
+//!   blocking ([`Submission`], [`GpuBuffer::download_async`]).
+//! * [`profiling`] times uploads, kernels and downloads with GPU timestamps or the
+//!   wall clock ([`Profiler`]).
+//! * [`shaders`] bundles the WGSL kernels of this crate.
+//!
+//! The vector addition, convolution and matrix multiplication demos live in `examples/`:
+//!
+//! ```text
+//! cargo run --release --example vector_add
+//! ```
+pub mod autotune;
+pub mod buffers;
+pub mod context;
+pub mod convolution;
+pub mod error;
+pub mod future;
+pub mod gemm;
+pub mod graph;
+pub mod histogram;
+pub mod invocation;
+pub mod kernels;
+pub mod profiling;
+pub mod reflection;
+pub mod shaders;
+pub mod utility;
+
+pub use autotune::{benchmark, Autotuner};
+pub use buffers::{GPUVector, GpuBuffer, StorageBuffer, Uniform, UniformElements, F16};
+pub use context::{initialize_fallback_gpu, initialize_gpu, self_test, GPUHandles};
+pub use convolution::{Convolution, ConvolutionConfig, ConvolutionKernel};
+pub use error::KernelError;
+pub use future::{GpuFuture, Submission};
+pub use gemm::{Gemm, GemmConfig, GemmKernel};
+pub use graph::{BufferId, KernelGraph, NodeBuilder, UniformId};
+pub use histogram::histogram;
+pub use invocation::{Argument, KernelInvocation};
+pub use kernels::{run_compute_shader, Kernel, KernelCache};
+pub use profiling::{Profile, Profiler, TimingSource};
+pub use reflection::{BindingInfo, BindingKind, Reflection};
@@ -0,0 +1,315 @@
+//! Times uploads, kernels and downloads separately and turns the times into GFLOP/s and
+//! GB/s.
+//!
+//! Where the device has [`wgpu::Features::TIMESTAMP_QUERY`] every phase is bracketed by
+//! timestamps written on the GPU, so only the copies and kernels themselves are timed.
+//! Otherwise the wall clock runs from before the submission until polling reports it
+//! done, which also counts staging the upload and mapping the download.
+//!
+//! ```no_run
+//! # use gpu_hand_in::*;
+//! # fn demo(handles: &GPUHandles, gemm: Gemm) -> Result<(), KernelError> {
+//! let uniform: Uniform = gemm.uniform(handles);
+//! let mut left: GPUVector = GPUVector::zeros(handles, &[gemm.rows, gemm.inner], "left", false);
+//! let right: GPUVector = GPUVector::zeros(handles, &[gemm.inner, gemm.columns], "right", false);
+//! let mut output: GPUVector = GPUVector::zeros(handles, &[gemm.rows, gemm.columns], "output", true);
+//!
+//! let mut profiler: Profiler = Profiler::new(handles);
+//! profiler.upload(&mut left, vec![1.0; gemm.rows * gemm.inner])?;
+//! let invocation = gemm.invocation(handles, GemmKernel::Tiled, &uniform, &left, &right, &mut output)?;
+//! profiler.compute(invocation, gemm.flops(), gemm.accessed_bytes())?;
+//! profiler.download(&mut output)?;
+//! println!("{}", profiler.profile());
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use bytemuck::Pod;
+use wgpu::util::DeviceExt;
+use wgpu::{Buffer, CommandEncoder, QuerySet};
+
+use crate::buffers::{read_back, GpuBuffer};
+use crate::context::GPUHandles;
+use crate::error::KernelError;
+use crate::future::gpu_channel;
+use crate::invocation::KernelInvocation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingSource {
+    /// Timestamp queries on the GPU.
+    Timestamps,
+    /// Wall-clock time around submitting and polling.
+    WallClock,
+}
+
+impl fmt::Display for TimingSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimingSource::Timestamps => f.write_str("GPU timestamps"),
+            TimingSource::WallClock => f.write_str("wall clock"),
+        }
+    }
+}
+
+/// What a [`Profiler`] measured, summed over all calls of each phase.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub source: TimingSource,
+    pub upload: Duration,
+    pub compute: Duration,
+    pub download: Duration,
+    pub uploaded_bytes: u64,
+    pub downloaded_bytes: u64,
+    /// Floating point operations of the kernels, as given to [`Profiler::compute`].
+    pub flops: f64,
+    /// Bytes the kernels read and wrote, as given to [`Profiler::compute`].
+    pub accessed_bytes: u64,
+}
+
+/// `amount` per second in billions, 0 if no time was measured.
+fn giga_per_second(amount: f64, time: Duration) -> f64 {
+    if time.is_zero() {
+        return 0.0;
+    }
+    amount / time.as_secs_f64() / 1e9
+}
+
+impl Profile {
+    pub fn gflops(&self) -> f64 {
+        giga_per_second(self.flops, self.compute)
+    }
+
+    /// Throughput of the kernels' memory accesses in GB/s.
+    pub fn compute_bandwidth(&self) -> f64 {
+        giga_per_second(self.accessed_bytes as f64, self.compute)
+    }
+
+    pub fn upload_bandwidth(&self) -> f64 {
+        giga_per_second(self.uploaded_bytes as f64, self.upload)
+    }
+
+    pub fn download_bandwidth(&self) -> f64 {
+        giga_per_second(self.downloaded_bytes as f64, self.download)
+    }
+
+    pub fn total(&self) -> Duration {
+        self.upload + self.compute + self.download
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let milliseconds = |time: Duration| time.as_secs_f64() * 1000.0;
+        writeln!(f, "timed with {}", self.source)?;
+        writeln!(
+            f,
+            "upload   {:>10.3} ms {:>8.2} GB/s",
+            milliseconds(self.upload),
+            self.upload_bandwidth()
+        )?;
+        writeln!(
+            f,
+            "compute  {:>10.3} ms {:>8.2} GB/s {:>8.2} GFLOP/s",
+            milliseconds(self.compute),
+            self.compute_bandwidth(),
+            self.gflops()
+        )?;
+        writeln!(
+            f,
+            "download {:>10.3} ms {:>8.2} GB/s",
+            milliseconds(self.download),
+            self.download_bandwidth()
+        )?;
+        write!(f, "total    {:>10.3} ms", milliseconds(self.total()))
+    }
+}
+
+/// Runs uploads, kernels and downloads one submission at a time and times each.
+pub struct Profiler<'a> {
+    handles: &'a GPUHandles,
+    profile: Profile,
+}
+
+impl<'a> Profiler<'a> {
+    /// Uses timestamp queries if the device supports them.
+    pub fn new(handles: &'a GPUHandles) -> Self {
+        let source: TimingSource = if handles.supports_timestamps() {
+            TimingSource::Timestamps
+        } else {
+            TimingSource::WallClock
+        };
+        Self::with_source(handles, source)
+    }
+
+    /// Uses the wall clock even if timestamps are available, e.g. to compare the two.
+    pub fn wall_clock(handles: &'a GPUHandles) -> Self {
+        Self::with_source(handles, TimingSource::WallClock)
+    }
+
+    fn with_source(handles: &'a GPUHandles, source: TimingSource) -> Self {
+        Self {
+            handles,
+            profile: Profile {
+                source,
+                upload: Duration::ZERO,
+                compute: Duration::ZERO,
+                download: Duration::ZERO,
+                uploaded_bytes: 0,
+                downloaded_bytes: 0,
+                flops: 0.0,
+                accessed_bytes: 0,
+            },
+        }
+    }
+
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Like [`GpuBuffer::upload`], copying through a temporary buffer so the GPU side
+    /// of the copy can be timed.
+    ///
+    /// # Panics
+    ///
+    /// If `data` has a different length than `buffer`.
+    pub fn upload<T: Pod>(
+        &mut self,
+        buffer: &mut GpuBuffer<T>,
+        data: Vec<T>,
+    ) -> Result<(), KernelError> {
+        assert_eq!(
+            data.len(),
+            buffer.len(),
+            "Uploads must keep the buffer length"
+        );
+        let bytes: u64 = std::mem::size_of_val(data.as_slice()) as u64;
+        let start: Instant = Instant::now();
+        let source: Buffer =
+            self.handles
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("upload"),
+                    contents: bytemuck::cast_slice(&data),
+                    usage: wgpu::BufferUsages::COPY_SRC,
+                });
+        let time: Duration = self.measure(start, |encoder| {
+            encoder.copy_buffer_to_buffer(&source, 0, &buffer.storage_buffer, 0, bytes);
+        })?;
+        buffer.cpu_data = data;
+
+        self.profile.upload += time;
+        self.profile.uploaded_bytes += bytes;
+        Ok(())
+    }
+
+    /// Validates and runs `invocation`. `flops` and `accessed_bytes` describe the work,
+    /// e.g. [`Gemm::flops`](crate::Gemm::flops), for the GFLOP/s and GB/s.
+    pub fn compute(
+        &mut self,
+        invocation: KernelInvocation,
+        flops: f64,
+        accessed_bytes: u64,
+    ) -> Result<(), KernelError> {
+        invocation.validate()?;
+        let time: Duration = self.measure(Instant::now(), |encoder| invocation.record(encoder))?;
+
+        self.profile.compute += time;
+        self.profile.flops += flops;
+        self.profile.accessed_bytes += accessed_bytes;
+        Ok(())
+    }
+
+    /// Like [`GpuBuffer::download`].
+    pub fn download<'b, T: Pod>(
+        &mut self,
+        buffer: &'b mut GpuBuffer<T>,
+    ) -> Result<&'b [T], KernelError> {
+        let temporary: bool = buffer.ensure_staging_buffer(self.handles);
+        let start: Instant = Instant::now();
+        let source: TimingSource = self.profile.source;
+        let result: Result<Duration, KernelError> = self
+            .measure(start, |encoder| {
+                buffer.transfer_from_gpu_to_cpu_mut(encoder)
+            })
+            .and_then(|copied| {
+                pollster::block_on(read_back(self.handles, &mut [&mut *buffer]))?;
+                // The wall clock includes mapping the staging buffer.
+                Ok(match source {
+                    TimingSource::Timestamps => copied,
+                    TimingSource::WallClock => start.elapsed(),
+                })
+            });
+        if temporary {
+            buffer.staging_buffer = None;
+        }
+        let time: Duration = result?;
+
+        self.profile.download += time;
+        self.profile.downloaded_bytes += std::mem::size_of_val(buffer.cpu_data.as_slice()) as u64;
+        Ok(&buffer.cpu_data)
+    }
+
+    /// Submits what `record` records on its own and times it. The wall clock counts
+    /// from `start`.
+    fn measure(
+        &self,
+        start: Instant,
+        record: impl FnOnce(&mut CommandEncoder),
+    ) -> Result<Duration, KernelError> {
+        let handles: &GPUHandles = self.handles;
+        let mut encoder: CommandEncoder =
+            handles
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("profiler"),
+                });
+
+        if self.profile.source == TimingSource::WallClock {
+            record(&mut encoder);
+            let index = handles.queue.submit(Some(encoder.finish()));
+            handles
+                .device
+                .poll(wgpu::Maintain::WaitForSubmissionIndex(index));
+            return Ok(start.elapsed());
+        }
+
+        let query_set: QuerySet = handles.device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("profiler"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+        let size: u64 = 2 * std::mem::size_of::<u64>() as u64;
+        let resolved: Buffer = handles.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("profiler timestamps"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readable: Buffer = handles.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("profiler timestamps"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        encoder.write_timestamp(&query_set, 0);
+        record(&mut encoder);
+        encoder.write_timestamp(&query_set, 1);
+        encoder.resolve_query_set(&query_set, 0..2, &resolved, 0);
+        encoder.copy_buffer_to_buffer(&resolved, 0, &readable, 0, size);
+        handles.queue.submit(Some(encoder.finish()));
+
+        let (sender, future) = gpu_channel(handles);
+        readable.slice(..).map_async(wgpu::MapMode::Read, sender);
+        pollster::block_on(future)?;
+        let ticks: Vec<u64> = bytemuck::cast_slice(&readable.slice(..).get_mapped_range()).to_vec();
+        readable.unmap();
+
+        // Ticks are converted to nanoseconds by the queue's period.
+        let nanoseconds: f64 =
+            ticks[1].saturating_sub(ticks[0]) as f64 * handles.queue.get_timestamp_period() as f64;
+        Ok(Duration::from_nanos(nanoseconds as u64))
+    }
+}
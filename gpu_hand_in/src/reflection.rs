@@ -0,0 +1,228 @@
+//! What a kernel expects to be bound, read from its WGSL with naga.
+use std::fmt;
+
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use naga::{AddressSpace, ArraySize, Handle, ShaderStage, StorageAccess, Type, TypeInner};
+
+use crate::error::KernelError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+    Uniform,
+    Storage { read_only: bool },
+}
+
+impl fmt::Display for BindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BindingKind::Uniform => write!(f, "a uniform"),
+            BindingKind::Storage { read_only: true } => write!(f, "a read-only storage buffer"),
+            BindingKind::Storage { read_only: false } => {
+                write!(f, "a read-write storage buffer")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BindingInfo {
+    pub binding: u32,
+    pub kind: BindingKind,
+    /// Size of the bound type, or of one element for runtime-sized arrays.
+    pub min_size: u64,
+    /// Stride of the runtime-sized array, if the binding is or ends in one.
+    pub element_size: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reflection {
+    pub entry_point: String,
+    pub workgroup_size: [u32; 3],
+    /// Bytes of `var<workgroup>` memory the entry point uses, each variable rounded up
+    /// to 16 bytes like WebGPU counts them.
+    pub workgroup_storage: u64,
+    /// Bindings of group 0 the entry point uses, by binding number. Unused
+    /// bindings are left out, the same way wgpu derives the pipeline layout.
+    pub bindings: Vec<BindingInfo>,
+}
+
+/// What is bound to a binding, as far as checking it against the shader goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArgumentInfo {
+    pub kind: BindingKind,
+    /// Size of one element, `None` for uniforms.
+    pub element_size: Option<u64>,
+    /// Size of the whole buffer.
+    pub size: u64,
+}
+
+impl Reflection {
+    /// Checks the workgroup size and workgroup memory against the device's `limits`,
+    /// which wgpu would otherwise panic on when creating the pipeline.
+    pub fn check_limits(&self, limits: &wgpu::Limits) -> Result<(), KernelError> {
+        let [x, y, z] = self.workgroup_size.map(u64::from);
+        check_limit(
+            "max_compute_workgroup_size_x",
+            x,
+            limits.max_compute_workgroup_size_x,
+        )?;
+        check_limit(
+            "max_compute_workgroup_size_y",
+            y,
+            limits.max_compute_workgroup_size_y,
+        )?;
+        check_limit(
+            "max_compute_workgroup_size_z",
+            z,
+            limits.max_compute_workgroup_size_z,
+        )?;
+        check_limit(
+            "max_compute_invocations_per_workgroup",
+            x * y * z,
+            limits.max_compute_invocations_per_workgroup,
+        )?;
+        check_limit(
+            "max_compute_workgroup_storage_size",
+            self.workgroup_storage,
+            limits.max_compute_workgroup_storage_size,
+        )
+    }
+
+    /// Checks `arguments`, in binding order, against the bindings of the kernel.
+    pub fn check_arguments(&self, arguments: &[ArgumentInfo]) -> Result<(), KernelError> {
+        if self.bindings.len() != arguments.len() {
+            return Err(KernelError::ArgumentCount {
+                expected: self.bindings.len(),
+                found: arguments.len(),
+            });
+        }
+
+        for (binding, argument) in self.bindings.iter().zip(arguments) {
+            if binding.kind != argument.kind {
+                return Err(KernelError::BindingMismatch {
+                    binding: binding.binding,
+                    expected: binding.kind,
+                    found: argument.kind,
+                });
+            }
+            if let (Some(expected), Some(found)) = (binding.element_size, argument.element_size) {
+                if expected != found {
+                    return Err(KernelError::ElementSize {
+                        binding: binding.binding,
+                        expected,
+                        found,
+                    });
+                }
+            }
+            if argument.size < binding.min_size {
+                return Err(KernelError::BufferTooSmall {
+                    binding: binding.binding,
+                    required: binding.min_size,
+                    found: argument.size,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses and validates `shader` and lists the bindings of `entry_point`.
+pub fn reflect(shader: &str, entry_point: &str) -> Result<Reflection, KernelError> {
+    let invalid = |message: String| KernelError::Shader {
+        entry_point: entry_point.to_string(),
+        message,
+    };
+    let module: naga::Module = naga::front::wgsl::parse_str(shader)
+        .map_err(|error| invalid(error.emit_to_string(shader)))?;
+    let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|error| invalid(error.emit_to_string(shader)))?;
+
+    let (index, entry) = module
+        .entry_points
+        .iter()
+        .enumerate()
+        .find(|(_, entry)| entry.stage == ShaderStage::Compute && entry.name == entry_point)
+        .ok_or_else(|| KernelError::MissingEntryPoint(entry_point.to_string()))?;
+    let usage = info.get_entry_point(index);
+
+    let mut bindings: Vec<BindingInfo> = Vec::new();
+    let mut workgroup_storage: u64 = 0;
+    for (handle, variable) in module.global_variables.iter() {
+        if usage[handle].is_empty() {
+            continue;
+        }
+        if variable.space == AddressSpace::WorkGroup {
+            let size: u64 = module.types[variable.ty].inner.size(&module.constants) as u64;
+            workgroup_storage += size.div_ceil(16) * 16;
+            continue;
+        }
+        let Some(resource) = &variable.binding else {
+            continue;
+        };
+        if resource.group != 0 {
+            return Err(KernelError::UnsupportedGroup {
+                binding: resource.binding,
+                group: resource.group,
+            });
+        }
+        let kind = match variable.space {
+            AddressSpace::Uniform => BindingKind::Uniform,
+            AddressSpace::Storage { access } => BindingKind::Storage {
+                read_only: !access.contains(StorageAccess::STORE),
+            },
+            _ => return Err(KernelError::UnsupportedBinding(resource.binding)),
+        };
+        bindings.push(BindingInfo {
+            binding: resource.binding,
+            kind,
+            min_size: module.types[variable.ty].inner.size(&module.constants) as u64,
+            element_size: runtime_array_stride(&module, variable.ty),
+        });
+    }
+    bindings.sort_by_key(|binding| binding.binding);
+
+    Ok(Reflection {
+        entry_point: entry_point.to_string(),
+        workgroup_size: entry.workgroup_size,
+        workgroup_storage,
+        bindings,
+    })
+}
+
+/// Checks the workgroup counts of a dispatch against the device's `limits`.
+pub fn check_workgroups(workgroups: [u32; 3], limits: &wgpu::Limits) -> Result<(), KernelError> {
+    for count in workgroups {
+        check_limit(
+            "max_compute_workgroups_per_dimension",
+            count as u64,
+            limits.max_compute_workgroups_per_dimension,
+        )?;
+    }
+    Ok(())
+}
+
+fn check_limit(limit: &'static str, required: u64, allowed: u32) -> Result<(), KernelError> {
+    if required > allowed as u64 {
+        return Err(KernelError::DeviceLimit {
+            limit,
+            required,
+            allowed: allowed as u64,
+        });
+    }
+    Ok(())
+}
+
+fn runtime_array_stride(module: &naga::Module, ty: Handle<Type>) -> Option<u64> {
+    match &module.types[ty].inner {
+        TypeInner::Array {
+            size: ArraySize::Dynamic,
+            stride,
+            ..
+        } => Some(*stride as u64),
+        TypeInner::Struct { members, .. } => members
+            .last()
+            .and_then(|member| runtime_array_stride(module, member.ty)),
+        _ => None,
+    }
+}
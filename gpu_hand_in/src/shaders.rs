@@ -0,0 +1,110 @@
+//! WGSL sources of the kernels in `src/shaders/`, with the entry points they define.
+//!
+//! All of them bind a [`Uniform`](crate::Uniform) at 0. Unless noted otherwise they
+//! bind two read-only `f32` arrays at 1 and 2 and the output at 3.
+
+use std::collections::HashMap;
+
+use crate::error::KernelError;
+
+/// `vector_add`: `output = input_a + input_b`.
+pub const VECTOR_ADD: &str = include_str!("shaders/vector_add.wgsl");
+
+/// `conv_naive`: 1D convolution of a signal with an odd-length filter. Specialize
+/// `BLOCK_SIZE` to change the workgroup size, or let
+/// [`ConvolutionConfig`](crate::ConvolutionConfig) do it.
+pub const CONVOLUTION_NAIVE: &str = include_str!("shaders/convolution_naive.wgsl");
+/// `conv_shared`: as `conv_naive`, staging signal and filter in workgroup memory.
+/// Also specialize `MAX_FILTER_SIZE` and `SHARED_SIZE = BLOCK_SIZE + MAX_FILTER_SIZE - 1`.
+pub const CONVOLUTION_SHARED: &str = include_str!("shaders/convolution_shared.wgsl");
+/// `conv_padded`: as `conv_shared` without bounds checks. The signal is zero padded by
+/// half the filter in front and to `workgroups * BLOCK_SIZE + filter_size - 1`
+/// elements, the output to `workgroups * BLOCK_SIZE`.
+pub const CONVOLUTION_PADDED: &str = include_str!("shaders/convolution_padded.wgsl");
+
+/// `matmul_naive`: one thread per output element. The uniform holds
+/// `(outer_right, outer_left, inner, transpose)`, see [`Gemm`](crate::Gemm).
+pub const MATRIX_MULTIPLICATION_NAIVE: &str =
+    include_str!("shaders/matrix_multiplication_naive.wgsl");
+/// `matmul_tiled`: as `matmul_naive` with `TILE_SIZE` x `TILE_SIZE` tiles in workgroup
+/// memory. Specialize `TILE_SIZE` and `SHARED_SIZE = TILE_SIZE * TILE_SIZE`.
+pub const MATRIX_MULTIPLICATION_TILED: &str =
+    include_str!("shaders/matrix_multiplication_tiled.wgsl");
+/// `matmul_register`: as `matmul_tiled` with every thread computing `WORK` x `WORK`
+/// outputs in registers, so a workgroup covers `BLOCK_SIZE = TILE_SIZE * WORK` squared.
+pub const MATRIX_MULTIPLICATION_REGISTER: &str =
+    include_str!("shaders/matrix_multiplication_register.wgsl");
+/// `matmul_padded`: the tiled kernel for matrices whose dimensions are all zero padded
+/// to a multiple of `TILE_SIZE`. Does not transpose.
+pub const MATRIX_MULTIPLICATION_PADDED: &str =
+    include_str!("shaders/matrix_multiplication_padded.wgsl");
+
+/// `histogram`: counts `u32` values into `bin_count` bins with atomics. Binds the
+/// uniform, the values and the bins. `bin_count` must not be zero, see
+/// [`histogram`](crate::histogram()).
+pub const HISTOGRAM: &str = include_str!("shaders/histogram.wgsl");
+/// `prefix_sum`: inclusive scan of `i32` values within blocks of 256. Binds the uniform,
+/// the input and the output.
+pub const PREFIX_SUM: &str = include_str!("shaders/prefix_sum.wgsl");
+
+/// Overrides module-scope `const NAME: u32 = ...;` declarations of `shader`, e.g. to
+/// pick the block size of a kernel.
+///
+/// naga only accepts literals in `@workgroup_size`, so constants named there are
+/// replaced by their values, which also lets unspecialized sources compile.
+///
+/// An unknown constant is an error, [`KernelError::UnknownConstant`], so a typo does
+/// not silently leave the default in place.
+pub fn specialize(shader: &str, constants: &[(&str, u32)]) -> Result<String, KernelError> {
+    let mut values: HashMap<&str, u32> = HashMap::new();
+    let mut lines: Vec<String> = Vec::new();
+    for line in shader.lines() {
+        match parse_constant(line) {
+            Some((name, value)) => {
+                let value: u32 = constants
+                    .iter()
+                    .find(|(constant, _)| *constant == name)
+                    .map_or(value, |&(_, value)| value);
+                values.insert(name, value);
+                lines.push(format!("const {name}: u32 = {value}u;"));
+            }
+            None => lines.push(line.to_string()),
+        }
+    }
+
+    if let Some((name, _)) = constants
+        .iter()
+        .find(|(name, _)| !values.contains_key(name))
+    {
+        return Err(KernelError::UnknownConstant(name.to_string()));
+    }
+
+    for line in lines.iter_mut() {
+        if let Some(start) = line.find("@workgroup_size(") {
+            let start: usize = start + "@workgroup_size(".len();
+            let end: usize = start + line[start..].find(')').unwrap_or(line.len() - start);
+            let arguments: Vec<String> = line[start..end]
+                .split(',')
+                .map(|argument| match values.get(argument.trim()) {
+                    Some(value) => value.to_string(),
+                    None => argument.trim().to_string(),
+                })
+                .collect();
+            line.replace_range(start..end, &arguments.join(", "));
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
+
+/// `const NAME: u32 = 32u;` as `("NAME", 32)`.
+fn parse_constant(line: &str) -> Option<(&str, u32)> {
+    let declaration: &str = line.trim().strip_prefix("const ")?.strip_suffix(';')?;
+    let (name, rest) = declaration.split_once(':')?;
+    let (kind, value) = rest.split_once('=')?;
+    if kind.trim() != "u32" {
+        return None;
+    }
+    let value: u32 = value.trim().trim_end_matches('u').parse().ok()?;
+    Some((name.trim(), value))
+}
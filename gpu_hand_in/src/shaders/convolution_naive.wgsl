@@ -19,21 +19,28 @@ var<storage, read> filter_signal : array<f32>;
 @group(0) @binding(3)
 var<storage, read_write> output : array<f32>;
 
-@compute @workgroup_size(32, 1, 1)
+// Override with shaders::specialize.
+const BLOCK_SIZE: u32 = 32u;
+
+@compute @workgroup_size(BLOCK_SIZE, 1, 1)
 
 fn conv_naive(
 @builtin(global_invocation_id) global_id : vec3 <u32>
 )
 {
     let thread_id: u32 = global_id.x;
+    if (dimensions.element_count <= thread_id) {
+        return;
+    }
     let filter_offset: i32 = dimensions.filter_size / 2;
-    
+
     // for loop to iterate over the filter
+    var sum: f32 = 0.0;
     for (var i: i32 = 0; i < dimensions.filter_size; i = i + 1) {
-        let offset: i32 = i32(thread_id ) + i - filter_offset;
+        let offset: i32 = i32(thread_id) + i - filter_offset;
         if (-1 < offset && offset < i32(dimensions.element_count)) {
-            output[thread_id] = output[thread_id] + input_signal[offset] * filter_signal[i];
+            sum = sum + input_signal[offset] * filter_signal[i];
         }
     }
-
-}
\ No newline at end of file
+    output[thread_id] = sum;
+}
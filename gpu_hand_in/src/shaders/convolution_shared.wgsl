@@ -0,0 +1,67 @@
+struct Uniform {
+    element_count : u32,
+    filter_size : i32,
+    not_used : u32,
+    not_used : u32,
+};
+
+@group(0) @binding(0)
+var<uniform> dimensions : Uniform;
+
+//Bind a read only array of 32-bit floats
+@group(0) @binding(1)
+var<storage, read> input_signal : array<f32>;
+
+@group(0) @binding(2)
+var<storage, read> filter_signal : array<f32>;
+
+//Bind a read/write array
+@group(0) @binding(3)
+var<storage, read_write> output : array<f32>;
+
+// Override with shaders::specialize. SHARED_SIZE has to be
+// BLOCK_SIZE + MAX_FILTER_SIZE - 1, naga can't compute it here.
+const BLOCK_SIZE: u32 = 32u;
+const MAX_FILTER_SIZE: u32 = 33u;
+const SHARED_SIZE: u32 = 64u;
+
+// The signal of the whole block plus half a filter on either side.
+var<workgroup> shared_signal : array<f32, SHARED_SIZE>;
+var<workgroup> shared_filter : array<f32, MAX_FILTER_SIZE>;
+
+@compute @workgroup_size(BLOCK_SIZE, 1, 1)
+
+fn conv_shared(
+@builtin(global_invocation_id) global_id : vec3 <u32>,
+@builtin(local_invocation_id) local_id : vec3 <u32>,
+@builtin(workgroup_id) group_id : vec3 <u32>,
+)
+{
+    let filter_size: u32 = u32(dimensions.filter_size);
+    let filter_offset: i32 = dimensions.filter_size / 2;
+    let first: i32 = i32(group_id.x * BLOCK_SIZE) - filter_offset;
+    let loaded: u32 = BLOCK_SIZE + filter_size - 1u;
+
+    // Every thread loads every BLOCK_SIZE'th element, zeros outside the signal.
+    for (var i: u32 = local_id.x; i < loaded; i = i + BLOCK_SIZE) {
+        let index: i32 = first + i32(i);
+        if (-1 < index && index < i32(dimensions.element_count)) {
+            shared_signal[i] = input_signal[index];
+        } else {
+            shared_signal[i] = 0.0;
+        }
+    }
+    for (var i: u32 = local_id.x; i < filter_size; i = i + BLOCK_SIZE) {
+        shared_filter[i] = filter_signal[i];
+    }
+
+    workgroupBarrier();
+
+    if (global_id.x < dimensions.element_count) {
+        var sum: f32 = 0.0;
+        for (var i: u32 = 0u; i < filter_size; i = i + 1u) {
+            sum = sum + shared_signal[local_id.x + i] * shared_filter[i];
+        }
+        output[global_id.x] = sum;
+    }
+}
@@ -0,0 +1,27 @@
+struct Uniform {
+    element_count: u32,
+    bin_count: u32,
+    not_used_0: u32,
+    not_used_1: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> dimensions: Uniform;
+
+@group(0) @binding(1)
+var<storage, read> values: array<u32>;
+
+// Many threads increment the same bin, so the bins have to be atomic.
+// An atomic<u32> has the same layout as a u32, so the CPU side is a plain u32 buffer.
+@group(0) @binding(2)
+var<storage, read_write> bins: array<atomic<u32>>;
+
+@compute @workgroup_size(64, 1, 1)
+fn histogram(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let thread_id: u32 = global_id.x;
+    if (thread_id < dimensions.element_count) {
+        // Values past the last bin are counted in the last bin.
+        let bin: u32 = min(values[thread_id], dimensions.bin_count - 1u);
+        atomicAdd(&bins[bin], 1u);
+    }
+}
@@ -0,0 +1,58 @@
+struct Uniform {
+    outer_right : i32,
+    outer_left : i32,
+    inner : i32,
+    // Bit 0: the left matrix is stored transposed, bit 1: the right one.
+    transpose : u32,
+};
+
+@group(0) @binding(0)
+var<uniform> dimensions : Uniform;
+
+//Bind a read only array of 32-bit floats
+@group(0) @binding(1)
+var<storage, read> left_matrix : array<f32>;
+
+@group(0) @binding(2)
+var<storage, read> right_matrix : array<f32>;
+
+//Bind a read/write array
+@group(0) @binding(3)
+var<storage, read_write> output : array<f32>;
+
+// Element (row, i) of the outer_left x inner left operand.
+fn left_index(row: i32, i: i32) -> i32 {
+    if ((dimensions.transpose & 1u) == 0u) {
+        return row * dimensions.inner + i;
+    }
+    return i * dimensions.outer_left + row;
+}
+
+// Element (i, column) of the inner x outer_right right operand.
+fn right_index(i: i32, column: i32) -> i32 {
+    if ((dimensions.transpose & 2u) == 0u) {
+        return i * dimensions.outer_right + column;
+    }
+    return column * dimensions.inner + i;
+}
+
+// Override with shaders::specialize.
+const TILE_SIZE: u32 = 16u;
+
+@compute @workgroup_size(TILE_SIZE, TILE_SIZE, 1)
+
+fn matmul_naive(
+@builtin(global_invocation_id) global_id : vec3 <u32>,
+)
+{
+    let row = i32(global_id.y);
+    let col = i32(global_id.x);
+
+    var sum : f32 = 0.0;
+    if (row < dimensions.outer_left && col < dimensions.outer_right) {
+        for (var i = 0; i < dimensions.inner; i = i + 1) {
+            sum += left_matrix[left_index(row, i)] * right_matrix[right_index(i, col)];
+        }
+        output[row * dimensions.outer_right + col] = sum;
+    }
+}
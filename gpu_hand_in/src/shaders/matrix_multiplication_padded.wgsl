@@ -0,0 +1,64 @@
+struct Uniform {
+    outer_right : i32,
+    outer_left : i32,
+    inner : i32,
+    not_used : u32,
+};
+
+@group(0) @binding(0)
+var<uniform> dimensions : Uniform;
+
+//Bind a read only array of 32-bit floats
+@group(0) @binding(1)
+var<storage, read> left_matrix : array<f32>;
+
+@group(0) @binding(2)
+var<storage, read> right_matrix : array<f32>;
+
+//Bind a read/write array
+@group(0) @binding(3)
+var<storage, read_write> output : array<f32>;
+
+// Override with shaders::specialize. SHARED_SIZE has to be TILE_SIZE * TILE_SIZE,
+// naga can't compute it here.
+const TILE_SIZE: u32 = 16u;
+const SHARED_SIZE: u32 = 256u;
+
+var<workgroup> A : array<f32, SHARED_SIZE>;
+var<workgroup> B : array<f32, SHARED_SIZE>;
+
+// Every dimension is zero padded to a multiple of TILE_SIZE, so there are no
+// if-guards.
+@compute @workgroup_size(TILE_SIZE, TILE_SIZE, 1)
+
+fn matmul_padded(
+@builtin(local_invocation_id) local_id : vec3 <u32>,
+@builtin(workgroup_id) group_id : vec3 <u32>,
+)
+{
+    let tile: i32 = i32(TILE_SIZE);
+    let tx: i32 = i32(local_id.x);
+    let ty: i32 = i32(local_id.y);
+
+    let row = i32(group_id.y) * tile + ty;
+    let col = i32(group_id.x) * tile + tx;
+
+    let num_tiles = dimensions.inner / tile;
+
+    var sum : f32 = 0.0;
+
+    for (var i = 0; i < num_tiles; i = i + 1) {
+        A[ty * tile + tx] = left_matrix[row * dimensions.inner + i * tile + tx];
+        B[ty * tile + tx] = right_matrix[(i * tile + ty) * dimensions.outer_right + col];
+
+        workgroupBarrier();
+
+        for (var k = 0; k < tile; k = k + 1) {
+            sum += A[ty * tile + k] * B[k * tile + tx];
+        }
+
+        workgroupBarrier();
+    }
+
+    output[row * dimensions.outer_right + col] = sum;
+}
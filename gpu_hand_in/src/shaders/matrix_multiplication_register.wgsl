@@ -0,0 +1,122 @@
+struct Uniform {
+    outer_right : i32,
+    outer_left : i32,
+    inner : i32,
+    // Bit 0: the left matrix is stored transposed, bit 1: the right one.
+    transpose : u32,
+};
+
+@group(0) @binding(0)
+var<uniform> dimensions : Uniform;
+
+//Bind a read only array of 32-bit floats
+@group(0) @binding(1)
+var<storage, read> left_matrix : array<f32>;
+
+@group(0) @binding(2)
+var<storage, read> right_matrix : array<f32>;
+
+//Bind a read/write array
+@group(0) @binding(3)
+var<storage, read_write> output : array<f32>;
+
+// Element (row, i) of the outer_left x inner left operand.
+fn left_index(row: i32, i: i32) -> i32 {
+    if ((dimensions.transpose & 1u) == 0u) {
+        return row * dimensions.inner + i;
+    }
+    return i * dimensions.outer_left + row;
+}
+
+// Element (i, column) of the inner x outer_right right operand.
+fn right_index(i: i32, column: i32) -> i32 {
+    if ((dimensions.transpose & 2u) == 0u) {
+        return i * dimensions.outer_right + column;
+    }
+    return column * dimensions.inner + i;
+}
+
+// Override with shaders::specialize. naga can't compute constants from others, so
+// BLOCK_SIZE has to be TILE_SIZE * WORK, SHARED_SIZE BLOCK_SIZE * TILE_SIZE and
+// ACCUMULATORS WORK * WORK.
+const TILE_SIZE: u32 = 16u;
+const WORK: u32 = 4u;
+const BLOCK_SIZE: u32 = 64u;
+const SHARED_SIZE: u32 = 1024u;
+const ACCUMULATORS: u32 = 16u;
+
+// BLOCK_SIZE rows x TILE_SIZE columns of the left operand and TILE_SIZE rows x
+// BLOCK_SIZE columns of the right one.
+var<workgroup> A : array<f32, SHARED_SIZE>;
+var<workgroup> B : array<f32, SHARED_SIZE>;
+
+// Every thread computes WORK x WORK outputs, TILE_SIZE apart so neighbouring threads
+// read neighbouring elements. The partial sums stay in registers.
+@compute @workgroup_size(TILE_SIZE, TILE_SIZE, 1)
+
+fn matmul_register(
+@builtin(local_invocation_id) local_id : vec3 <u32>,
+@builtin(workgroup_id) group_id : vec3 <u32>,
+)
+{
+    let tile: i32 = i32(TILE_SIZE);
+    let work: i32 = i32(WORK);
+    let block: i32 = i32(BLOCK_SIZE);
+    let tx: i32 = i32(local_id.x);
+    let ty: i32 = i32(local_id.y);
+    let flat_id: i32 = ty * tile + tx;
+    let first_row: i32 = i32(group_id.y) * block;
+    let first_col: i32 = i32(group_id.x) * block;
+
+    var sums : array<f32, ACCUMULATORS>;
+    var left_values : array<f32, WORK>;
+    var right_values : array<f32, WORK>;
+
+    let num_tiles = (dimensions.inner + tile - 1) / tile;
+    for (var i = 0; i < num_tiles; i = i + 1) {
+        for (var element = flat_id; element < block * tile; element = element + tile * tile) {
+            let row = first_row + element / tile;
+            let column = i * tile + element % tile;
+            if (row < dimensions.outer_left && column < dimensions.inner) {
+                A[element] = left_matrix[left_index(row, column)];
+            } else {
+                A[element] = 0.0;
+            }
+        }
+        for (var element = flat_id; element < block * tile; element = element + tile * tile) {
+            let row = i * tile + element / block;
+            let column = first_col + element % block;
+            if (row < dimensions.inner && column < dimensions.outer_right) {
+                B[element] = right_matrix[right_index(row, column)];
+            } else {
+                B[element] = 0.0;
+            }
+        }
+
+        workgroupBarrier();
+
+        for (var k = 0; k < tile; k = k + 1) {
+            for (var w = 0; w < work; w = w + 1) {
+                left_values[w] = A[(ty + w * tile) * tile + k];
+                right_values[w] = B[k * block + tx + w * tile];
+            }
+            for (var y = 0; y < work; y = y + 1) {
+                for (var x = 0; x < work; x = x + 1) {
+                    sums[y * work + x] += left_values[y] * right_values[x];
+                }
+            }
+        }
+
+        workgroupBarrier();
+    }
+
+    for (var y = 0; y < work; y = y + 1) {
+        for (var x = 0; x < work; x = x + 1) {
+            let row = first_row + ty + y * tile;
+            let col = first_col + tx + x * tile;
+            if (row < dimensions.outer_left && col < dimensions.outer_right) {
+                output[row * dimensions.outer_right + col] = sums[y * work + x];
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+struct Uniform {
+    outer_right : i32,
+    outer_left : i32,
+    inner : i32,
+    // Bit 0: the left matrix is stored transposed, bit 1: the right one.
+    transpose : u32,
+};
+
+@group(0) @binding(0)
+var<uniform> dimensions : Uniform;
+
+//Bind a read only array of 32-bit floats
+@group(0) @binding(1)
+var<storage, read> left_matrix : array<f32>;
+
+@group(0) @binding(2)
+var<storage, read> right_matrix : array<f32>;
+
+//Bind a read/write array
+@group(0) @binding(3)
+var<storage, read_write> output : array<f32>;
+
+// Element (row, i) of the outer_left x inner left operand.
+fn left_index(row: i32, i: i32) -> i32 {
+    if ((dimensions.transpose & 1u) == 0u) {
+        return row * dimensions.inner + i;
+    }
+    return i * dimensions.outer_left + row;
+}
+
+// Element (i, column) of the inner x outer_right right operand.
+fn right_index(i: i32, column: i32) -> i32 {
+    if ((dimensions.transpose & 2u) == 0u) {
+        return i * dimensions.outer_right + column;
+    }
+    return column * dimensions.inner + i;
+}
+
+// Override with shaders::specialize. SHARED_SIZE has to be TILE_SIZE * TILE_SIZE,
+// naga can't compute it here.
+const TILE_SIZE: u32 = 16u;
+const SHARED_SIZE: u32 = 256u;
+
+var<workgroup> A : array<f32, SHARED_SIZE>;
+var<workgroup> B : array<f32, SHARED_SIZE>;
+
+@compute @workgroup_size(TILE_SIZE, TILE_SIZE, 1)
+
+fn matmul_tiled(
+@builtin(local_invocation_id) local_id : vec3 <u32>,
+@builtin(workgroup_id) group_id : vec3 <u32>,
+)
+{
+    let tile: i32 = i32(TILE_SIZE);
+    let tx: i32 = i32(local_id.x);
+    let ty: i32 = i32(local_id.y);
+
+    let row = i32(group_id.y) * tile + ty;
+    let col = i32(group_id.x) * tile + tx;
+
+    // Tiles along the inner dimension, the last one may be partial.
+    let num_tiles = (dimensions.inner + tile - 1) / tile;
+
+    var sum : f32 = 0.0;
+
+    for (var i = 0; i < num_tiles; i = i + 1) {
+        // Zeros outside the matrices, they don't change the sum.
+        let left_column = i * tile + tx;
+        if (row < dimensions.outer_left && left_column < dimensions.inner) {
+            A[ty * tile + tx] = left_matrix[left_index(row, left_column)];
+        } else {
+            A[ty * tile + tx] = 0.0;
+        }
+        let right_row = i * tile + ty;
+        if (right_row < dimensions.inner && col < dimensions.outer_right) {
+            B[ty * tile + tx] = right_matrix[right_index(right_row, col)];
+        } else {
+            B[ty * tile + tx] = 0.0;
+        }
+
+        workgroupBarrier();
+
+        for (var k = 0; k < tile; k = k + 1) {
+            sum += A[ty * tile + k] * B[k * tile + tx];
+        }
+
+        workgroupBarrier();
+    }
+
+    if (row < dimensions.outer_left && col < dimensions.outer_right) {
+        output[row * dimensions.outer_right + col] = sum;
+    }
+}
@@ -0,0 +1,51 @@
+struct Uniform {
+    element_count: u32,
+    not_used_0: u32,
+    not_used_1: u32,
+    not_used_2: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> dimensions: Uniform;
+
+@group(0) @binding(1)
+var<storage, read> input: array<i32>;
+
+@group(0) @binding(2)
+var<storage, read_write> output: array<i32>;
+
+// One element per thread, one block of 256 elements per workgroup.
+var<workgroup> block: array<i32, 256>;
+
+// Inclusive scan within each block of 256 elements (Hillis-Steele). Adding the
+// total of all earlier blocks is left to the caller.
+@compute @workgroup_size(256, 1, 1)
+fn prefix_sum(
+    @builtin(global_invocation_id) global_id: vec3<u32>,
+    @builtin(local_invocation_id) local_id: vec3<u32>,
+) {
+    let thread_id: u32 = global_id.x;
+    let local: u32 = local_id.x;
+
+    if (thread_id < dimensions.element_count) {
+        block[local] = input[thread_id];
+    } else {
+        block[local] = 0;
+    }
+    workgroupBarrier();
+
+    for (var offset: u32 = 1u; offset < 256u; offset = offset * 2u) {
+        var sum: i32 = block[local];
+        if (local >= offset) {
+            sum = sum + block[local - offset];
+        }
+        // Everyone has to read before anyone overwrites.
+        workgroupBarrier();
+        block[local] = sum;
+        workgroupBarrier();
+    }
+
+    if (thread_id < dimensions.element_count) {
+        output[thread_id] = block[local];
+    }
+}
@@ -0,0 +1,104 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Wake, Waker};
+use std::time::Duration;
+
+use gpu_hand_in::{
+    initialize_fallback_gpu, shaders, GPUHandles, GPUVector, KernelInvocation, Uniform,
+};
+
+fn handles() -> GPUHandles {
+    pollster::block_on(initialize_fallback_gpu()).expect("no GPU adapter available")
+}
+
+/// Counts how often the future asked to be polled again.
+#[derive(Default)]
+struct CountingWaker {
+    wakes: Mutex<usize>,
+    woken: Condvar,
+}
+
+impl Wake for CountingWaker {
+    fn wake(self: Arc<Self>) {
+        *self.wakes.lock().unwrap() += 1;
+        self.woken.notify_all();
+    }
+}
+
+#[test]
+fn submitted_kernels_feed_each_other_before_any_readback() {
+    let handles: GPUHandles = handles();
+    let uniform = Uniform::new(&handles, 64, 0, 0, 0);
+    let ones = GPUVector::new(&handles, vec![1.0; 64], "ones", false);
+    let mut twos = GPUVector::zeros(&handles, &[64], "twos", false);
+    let mut fours = GPUVector::zeros(&handles, &[64], "fours", false);
+
+    KernelInvocation::new(&handles, shaders::VECTOR_ADD, "vector_add")
+        .unwrap()
+        .uniform(&uniform)
+        .read(&ones)
+        .read(&ones)
+        .read_write(&mut twos)
+        .threads(64, 1, 1)
+        .submit()
+        .unwrap();
+    let second = KernelInvocation::new(&handles, shaders::VECTOR_ADD, "vector_add")
+        .unwrap()
+        .uniform(&uniform)
+        .read(&twos)
+        .read(&twos)
+        .read_write(&mut fours)
+        .threads(64, 1, 1)
+        .submit()
+        .unwrap();
+
+    // Nothing was read back yet.
+    assert_eq!(twos.cpu_data, vec![0.0; 64]);
+
+    pollster::block_on(async {
+        second.done(&handles).await;
+        assert_eq!(fours.download_async(&handles).await.unwrap(), &[4.0; 64]);
+        assert_eq!(twos.download_async(&handles).await.unwrap(), &[2.0; 64]);
+    });
+}
+
+#[test]
+fn pending_futures_are_woken_by_the_gpu_not_by_polling() {
+    let handles: GPUHandles = handles();
+    let uniform = Uniform::new(&handles, 1 << 16, 0, 0, 0);
+    let ones = GPUVector::new(&handles, vec![1.0; 1 << 16], "ones", false);
+    let mut twos = GPUVector::zeros(&handles, &[1 << 16], "twos", false);
+
+    let submission = KernelInvocation::new(&handles, shaders::VECTOR_ADD, "vector_add")
+        .unwrap()
+        .uniform(&uniform)
+        .read(&ones)
+        .read(&ones)
+        .read_write(&mut twos)
+        .threads(1 << 16, 1, 1)
+        .submit()
+        .unwrap();
+
+    let counter: Arc<CountingWaker> = Arc::default();
+    let waker: Waker = counter.clone().into();
+    let mut context = Context::from_waker(&waker);
+    let mut done = pin!(submission.done(&handles));
+
+    if done.as_mut().poll(&mut context).is_pending() {
+        // Only the callback wakes the task, exactly once.
+        let wakes = counter
+            .woken
+            .wait_timeout_while(
+                counter.wakes.lock().unwrap(),
+                Duration::from_secs(10),
+                |wakes| *wakes == 0,
+            )
+            .unwrap()
+            .0;
+        assert_eq!(*wakes, 1);
+        drop(wakes);
+        assert!(done.as_mut().poll(&mut context).is_ready());
+    }
+    assert!(*counter.wakes.lock().unwrap() <= 1);
+}
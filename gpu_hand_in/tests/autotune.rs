@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use gpu_hand_in::{
+    initialize_fallback_gpu, Autotuner, GPUHandles, GPUVector, Gemm, GemmConfig, GemmKernel,
+    KernelError, Uniform,
+};
+
+fn handles() -> GPUHandles {
+    pollster::block_on(initialize_fallback_gpu()).expect("no GPU adapter available")
+}
+
+#[test]
+fn the_fastest_candidate_is_cached_on_disk() {
+    let handles: GPUHandles = handles();
+    let path: PathBuf = std::env::temp_dir().join(format!("autotune-{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let candidates: [GemmConfig; 3] = GemmKernel::ALL.map(GemmConfig::from);
+
+    let mut autotuner: Autotuner = Autotuner::open(&path).unwrap();
+    let winner: GemmConfig = autotuner
+        .tune(&handles, "problem", &candidates, |config| {
+            match config.kernel {
+                GemmKernel::Naive => Ok(Duration::from_millis(3)),
+                GemmKernel::Tiled => Ok(Duration::from_millis(1)),
+                // Failing candidates are skipped.
+                GemmKernel::RegisterBlocked => Err(KernelError::MissingEntryPoint("x".into())),
+            }
+        })
+        .unwrap();
+    assert_eq!(winner.kernel, GemmKernel::Tiled);
+
+    let mut reopened: Autotuner = Autotuner::open(&path).unwrap();
+    let cached: GemmConfig = reopened
+        .tune(&handles, "problem", &candidates, |_| {
+            panic!("the cached winner should be used")
+        })
+        .unwrap();
+    assert_eq!(cached, winner);
+    // Another problem is tuned on its own.
+    assert_eq!(
+        reopened.cached(&handles, "other problem", &candidates),
+        None
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn configurations_round_trip_through_text() {
+    let config: GemmConfig = GemmConfig::new(GemmKernel::RegisterBlocked)
+        .with_tile_size(8)
+        .with_work(2);
+    assert_eq!(config.to_string(), "register/8/2");
+    assert_eq!("register/8/2".parse::<GemmConfig>().unwrap(), config);
+    assert!("register/8".parse::<GemmConfig>().is_err());
+    assert!("strassen/8/1".parse::<GemmConfig>().is_err());
+}
+
+#[test]
+fn every_candidate_multiplies_correctly() {
+    let handles: GPUHandles = handles();
+    let gemm: Gemm = Gemm::new(37, 29, 45).transpose_right(true);
+    // op(right) is the identity padded with zeros, so the product is the left matrix
+    // cut off after 29 columns.
+    let left: Vec<f32> = (0..gemm.rows * gemm.inner)
+        .map(|x| (x % 11) as f32)
+        .collect();
+    let mut right: Vec<f32> = vec![0.0; gemm.inner * gemm.columns];
+    for index in 0..gemm.inner {
+        right[index * gemm.inner + index] = 1.0;
+    }
+    let mut expected: Vec<f32> = vec![0.0; gemm.rows * gemm.columns];
+    for row in 0..gemm.rows {
+        expected[row * gemm.columns..row * gemm.columns + gemm.inner]
+            .copy_from_slice(&left[row * gemm.inner..(row + 1) * gemm.inner]);
+    }
+
+    let uniform: Uniform = gemm.uniform(&handles);
+    let left: GPUVector = GPUVector::new(&handles, left, "left", false);
+    let right: GPUVector = GPUVector::new(&handles, right, "right", false);
+    let candidates: Vec<GemmConfig> = GemmConfig::candidates(&handles.device.limits());
+    assert!(candidates.len() > 3);
+    for config in candidates {
+        let mut output: GPUVector =
+            GPUVector::zeros(&handles, &[gemm.rows, gemm.columns], "output", true);
+        gemm.invocation(&handles, config, &uniform, &left, &right, &mut output)
+            .unwrap()
+            .dispatch()
+            .unwrap();
+        assert_eq!(output.cpu_data, expected, "{config}");
+    }
+}
@@ -0,0 +1,75 @@
+use gpu_hand_in::{initialize_fallback_gpu, GPUHandles, GPUVector, Gemm, GemmKernel, Uniform};
+
+fn matrix_multiplication_cpu(gemm: &Gemm, left: &[f32], right: &[f32]) -> Vec<f32> {
+    let mut output: Vec<f32> = vec![0.0; gemm.rows * gemm.columns];
+    for row in 0..gemm.rows {
+        for column in 0..gemm.columns {
+            for index in 0..gemm.inner {
+                let left_value: f32 = if gemm.transpose_left {
+                    left[index * gemm.rows + row]
+                } else {
+                    left[row * gemm.inner + index]
+                };
+                let right_value: f32 = if gemm.transpose_right {
+                    right[column * gemm.inner + index]
+                } else {
+                    right[index * gemm.columns + column]
+                };
+                output[row * gemm.columns + column] += left_value * right_value;
+            }
+        }
+    }
+    output
+}
+
+/// xorshift, so the sweep is the same on every run.
+fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+#[test]
+fn every_kernel_matches_the_cpu_on_random_shapes() {
+    let handles: GPUHandles =
+        pollster::block_on(initialize_fallback_gpu()).expect("no GPU adapter available");
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+
+    // Include the degenerate and the exact multiple of a block.
+    let mut shapes: Vec<Gemm> = vec![Gemm::new(1, 1, 1), Gemm::new(64, 16, 128)];
+    for _ in 0..8 {
+        let rows: usize = 1 + (next(&mut state) % 90) as usize;
+        let inner: usize = 1 + (next(&mut state) % 90) as usize;
+        let columns: usize = 1 + (next(&mut state) % 90) as usize;
+        shapes.push(
+            Gemm::new(rows, inner, columns)
+                .transpose_left((next(&mut state) >> 32).is_multiple_of(2))
+                .transpose_right((next(&mut state) >> 32).is_multiple_of(2)),
+        );
+    }
+
+    for gemm in shapes {
+        // Small integers, so every sum is exact.
+        let left: Vec<f32> = (0..gemm.rows * gemm.inner)
+            .map(|_| (next(&mut state) % 7) as f32 - 3.0)
+            .collect();
+        let right: Vec<f32> = (0..gemm.inner * gemm.columns)
+            .map(|_| (next(&mut state) % 7) as f32 - 3.0)
+            .collect();
+        let expected: Vec<f32> = matrix_multiplication_cpu(&gemm, &left, &right);
+
+        let uniform: Uniform = gemm.uniform(&handles);
+        let left: GPUVector = GPUVector::new(&handles, left, "left", false);
+        let right: GPUVector = GPUVector::new(&handles, right, "right", false);
+        for kernel in GemmKernel::ALL {
+            let mut output: GPUVector =
+                GPUVector::zeros(&handles, &[gemm.rows, gemm.columns], "output", true);
+            gemm.invocation(&handles, kernel, &uniform, &left, &right, &mut output)
+                .unwrap()
+                .dispatch()
+                .unwrap();
+            assert_eq!(output.cpu_data, expected, "{kernel:?} on {gemm:?}");
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use gpu_hand_in::{
+    initialize_fallback_gpu, shaders, BufferId, GPUHandles, KernelError, KernelGraph, UniformId,
+};
+
+fn handles() -> GPUHandles {
+    pollster::block_on(initialize_fallback_gpu()).expect("no GPU adapter available")
+}
+
+#[test]
+fn intermediates_stay_on_the_gpu() {
+    let handles: GPUHandles = handles();
+    let mut graph: KernelGraph = KernelGraph::new(&handles);
+    let sizes: UniformId = graph.uniform(8, 0, 0, 0);
+    let a: BufferId<f32> = graph.upload((0..8).map(|x| x as f32).collect());
+    let sum: BufferId<f32> = graph.zeros(&[8]);
+    let doubled: BufferId<f32> = graph.zeros(&[8]);
+
+    for (left, output) in [(a, sum), (sum, doubled)] {
+        graph
+            .node(shaders::VECTOR_ADD, "vector_add")
+            .unwrap()
+            .uniform(sizes)
+            .read(left)
+            .read(left)
+            .write(output)
+            .threads(8, 1, 1)
+            .add()
+            .unwrap();
+    }
+    graph.read_back(doubled);
+    graph.run().unwrap();
+
+    let expected: Vec<f32> = (0..8).map(|x| x as f32 * 4.0).collect();
+    assert_eq!(graph.buffer(doubled).cpu_data, expected);
+    // Not marked for read back, so the CPU copy is still the zeros it started as.
+    assert_eq!(graph.buffer(sum).cpu_data, vec![0.0; 8]);
+
+    // Inputs can be replaced between runs.
+    graph.buffer_mut(a).upload(&handles, vec![1.0; 8]);
+    graph.run().unwrap();
+    assert_eq!(graph.buffer(doubled).cpu_data, vec![4.0; 8]);
+}
+
+#[test]
+fn nodes_are_validated_when_added() {
+    let handles: GPUHandles = handles();
+    let mut graph: KernelGraph = KernelGraph::new(&handles);
+    let sizes: UniformId = graph.uniform(8, 0, 0, 0);
+    let a: BufferId<f32> = graph.upload(vec![0.0; 8]);
+    let output: BufferId<f32> = graph.zeros(&[8]);
+
+    let error: KernelError = graph
+        .node(shaders::VECTOR_ADD, "vector_add")
+        .unwrap()
+        .uniform(sizes)
+        .read(a)
+        .write(output)
+        .add()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        KernelError::ArgumentCount {
+            expected: 4,
+            found: 3
+        }
+    ));
+}
+
+#[test]
+fn a_written_buffer_cannot_be_bound_twice() {
+    let handles: GPUHandles = handles();
+    let mut graph: KernelGraph = KernelGraph::new(&handles);
+    let sizes: UniformId = graph.uniform(8, 0, 0, 0);
+    let a: BufferId<f32> = graph.upload(vec![1.0; 8]);
+
+    let error: KernelError = graph
+        .node(shaders::VECTOR_ADD, "vector_add")
+        .unwrap()
+        .uniform(sizes)
+        .read(a)
+        .read(a)
+        .write(a)
+        .add()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        KernelError::AliasedWrite {
+            first: 1,
+            second: 3
+        }
+    ));
+
+    // Reading one buffer twice is fine.
+    let output: BufferId<f32> = graph.zeros(&[8]);
+    graph
+        .node(shaders::VECTOR_ADD, "vector_add")
+        .unwrap()
+        .uniform(sizes)
+        .read(a)
+        .read(a)
+        .write(output)
+        .add()
+        .unwrap();
+    graph.read_back(output);
+    graph.run().unwrap();
+    assert_eq!(graph.buffer(output).cpu_data, vec![2.0; 8]);
+}
@@ -0,0 +1,50 @@
+use gpu_hand_in::{initialize_fallback_gpu, shaders, GPUHandles, KernelInvocation};
+
+fn handles() -> GPUHandles {
+    pollster::block_on(initialize_fallback_gpu()).expect("no GPU adapter available")
+}
+
+const SCALE: &str = "
+    @group(0) @binding(0) var<storage, read_write> values: array<f32>;
+
+    @compute @workgroup_size(8)
+    fn double(@builtin(global_invocation_id) id: vec3<u32>) {
+        if (id.x < arrayLength(&values)) {
+            values[id.x] = values[id.x] * 2.0;
+        }
+    }
+
+    @compute @workgroup_size(8)
+    fn halve(@builtin(global_invocation_id) id: vec3<u32>) {
+        if (id.x < arrayLength(&values)) {
+            values[id.x] = values[id.x] * 0.5;
+        }
+    }
+";
+
+#[test]
+fn the_same_kernel_compiles_once() {
+    let handles: GPUHandles = handles();
+
+    KernelInvocation::new(&handles, shaders::VECTOR_ADD, "vector_add").unwrap();
+    KernelInvocation::new(&handles, shaders::VECTOR_ADD, "vector_add").unwrap();
+
+    assert_eq!(handles.kernels.misses(), 1);
+    assert!(handles.kernels.hits() >= 1);
+    assert_eq!(handles.kernels.len(), 1);
+}
+
+#[test]
+fn other_entry_points_compile_separately() {
+    let handles: GPUHandles = handles();
+
+    KernelInvocation::new(&handles, SCALE, "double").unwrap();
+    KernelInvocation::new(&handles, SCALE, "halve").unwrap();
+    assert_eq!(handles.kernels.misses(), 2);
+    assert_eq!(handles.kernels.hits(), 0);
+    assert_eq!(handles.kernels.len(), 2);
+
+    KernelInvocation::new(&handles, SCALE, "halve").unwrap();
+    assert_eq!(handles.kernels.misses(), 2);
+    assert_eq!(handles.kernels.hits(), 1);
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use gpu_hand_in::{
+    initialize_fallback_gpu, shaders, GPUHandles, GPUVector, KernelInvocation, Profile, Profiler,
+    TimingSource, Uniform,
+};
+
+#[test]
+fn every_phase_is_timed_and_counted() {
+    let handles: GPUHandles =
+        pollster::block_on(initialize_fallback_gpu()).expect("no GPU adapter available");
+    let expected: TimingSource = if handles.supports_timestamps() {
+        TimingSource::Timestamps
+    } else {
+        TimingSource::WallClock
+    };
+    assert_eq!(Profiler::new(&handles).profile().source, expected);
+
+    let mut profiler: Profiler = Profiler::wall_clock(&handles);
+    let uniform: Uniform = Uniform::new(&handles, 64, 0, 0, 0);
+    let mut input: GPUVector = GPUVector::zeros(&handles, &[64], "input", false);
+    let mut output: GPUVector = GPUVector::zeros(&handles, &[64], "output", false);
+
+    profiler
+        .upload(&mut input, (0..64).map(|x| x as f32).collect())
+        .unwrap();
+    let invocation: KernelInvocation =
+        KernelInvocation::new(&handles, shaders::VECTOR_ADD, "vector_add")
+            .unwrap()
+            .uniform(&uniform)
+            .read(&input)
+            .read(&input)
+            .read_write(&mut output)
+            .threads(64, 1, 1);
+    profiler.compute(invocation, 64.0, 3 * 64 * 4).unwrap();
+    // Downloads work without a staging buffer, like GpuBuffer::download.
+    let data: Vec<f32> = profiler.download(&mut output).unwrap().to_vec();
+    assert_eq!(data, (0..64).map(|x| 2.0 * x as f32).collect::<Vec<f32>>());
+    assert!(output.staging_buffer.is_none());
+
+    let profile: &Profile = profiler.profile();
+    assert_eq!(profile.source, TimingSource::WallClock);
+    assert_eq!(profile.uploaded_bytes, 256);
+    assert_eq!(profile.downloaded_bytes, 256);
+    assert_eq!(profile.flops, 64.0);
+    assert!(!profile.upload.is_zero() && !profile.compute.is_zero() && !profile.download.is_zero());
+}
+
+#[test]
+fn rates_are_per_second_in_billions() {
+    let profile: Profile = Profile {
+        source: TimingSource::Timestamps,
+        upload: Duration::from_millis(500),
+        compute: Duration::from_secs(2),
+        download: Duration::ZERO,
+        uploaded_bytes: 1_000_000_000,
+        downloaded_bytes: 1_000_000_000,
+        flops: 6e9,
+        accessed_bytes: 4_000_000_000,
+    };
+
+    assert_eq!(profile.upload_bandwidth(), 2.0);
+    assert_eq!(profile.gflops(), 3.0);
+    assert_eq!(profile.compute_bandwidth(), 2.0);
+    // Nothing measured is reported as zero, not infinity.
+    assert_eq!(profile.download_bandwidth(), 0.0);
+    assert_eq!(profile.total(), Duration::from_millis(2500));
+}
@@ -0,0 +1,160 @@
+use gpu_hand_in::reflection::reflect;
+use gpu_hand_in::{
+    initialize_fallback_gpu, shaders, BindingKind, GPUHandles, GPUVector, KernelError,
+    KernelInvocation, Uniform,
+};
+
+fn handles() -> GPUHandles {
+    pollster::block_on(initialize_fallback_gpu()).expect("no GPU adapter available")
+}
+
+#[test]
+fn reflects_the_bindings_and_workgroup_size_of_vector_add() {
+    let reflection = reflect(shaders::VECTOR_ADD, "vector_add").unwrap();
+
+    assert_eq!(reflection.workgroup_size, [32, 1, 1]);
+    let kinds: Vec<(u32, BindingKind)> = reflection
+        .bindings
+        .iter()
+        .map(|binding| (binding.binding, binding.kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (0, BindingKind::Uniform),
+            (1, BindingKind::Storage { read_only: true }),
+            (2, BindingKind::Storage { read_only: true }),
+            (3, BindingKind::Storage { read_only: false }),
+        ]
+    );
+    assert_eq!(reflection.bindings[0].min_size, 16);
+}
+
+#[test]
+fn unused_bindings_are_left_out() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> unused: array<f32>;
+        @group(0) @binding(1) var<storage, read_write> data: array<u32>;
+
+        @compute @workgroup_size(64)
+        fn double(@builtin(global_invocation_id) id: vec3<u32>) {
+            data[id.x] = data[id.x] * 2u;
+        }
+    ";
+    let reflection = reflect(shader, "double").unwrap();
+
+    assert_eq!(reflection.bindings.len(), 1);
+    assert_eq!(reflection.bindings[0].binding, 1);
+}
+
+#[test]
+fn invalid_shaders_and_missing_entry_points_are_errors() {
+    assert!(matches!(
+        reflect("fn broken(", "broken"),
+        Err(KernelError::Shader { .. })
+    ));
+    assert!(matches!(
+        reflect(shaders::VECTOR_ADD, "vector_sub"),
+        Err(KernelError::MissingEntryPoint(name)) if name == "vector_sub"
+    ));
+}
+
+#[test]
+fn mismatched_arguments_are_reported_before_dispatch() {
+    let handles: GPUHandles = handles();
+    let uniform = Uniform::new(&handles, 4, 0, 0, 0);
+    let input = GPUVector::new(&handles, vec![1.0; 4], "input", false);
+    let mut output = GPUVector::new(&handles, vec![0.0; 4], "output", true);
+
+    let missing = KernelInvocation::new(&handles, shaders::VECTOR_ADD, "vector_add")
+        .unwrap()
+        .uniform(&uniform)
+        .read(&input)
+        .read_write(&mut output)
+        .dispatch();
+    assert!(matches!(
+        missing,
+        Err(KernelError::ArgumentCount {
+            expected: 4,
+            found: 3
+        })
+    ));
+
+    let swapped = KernelInvocation::new(&handles, shaders::VECTOR_ADD, "vector_add")
+        .unwrap()
+        .read(&input)
+        .uniform(&uniform)
+        .read(&input)
+        .read_write(&mut output)
+        .dispatch();
+    assert!(matches!(
+        swapped,
+        Err(KernelError::BindingMismatch { binding: 0, .. })
+    ));
+
+    KernelInvocation::new(&handles, shaders::VECTOR_ADD, "vector_add")
+        .unwrap()
+        .uniform(&uniform)
+        .read(&input)
+        .read(&input)
+        .read_write(&mut output)
+        .threads(4, 1, 1)
+        .dispatch()
+        .unwrap();
+    assert_eq!(output.cpu_data, vec![2.0; 4]);
+}
+
+#[test]
+fn kernels_beyond_the_device_limits_are_errors() {
+    let handles: GPUHandles = handles();
+    let limits = handles.device.limits();
+
+    let oversized: String =
+        shaders::specialize(shaders::CONVOLUTION_NAIVE, &[("BLOCK_SIZE", 1024)]).unwrap();
+    let Err(error) = KernelInvocation::new(&handles, &oversized, "conv_naive") else {
+        panic!("a workgroup of 1024 threads compiled");
+    };
+    assert!(matches!(
+        error,
+        KernelError::DeviceLimit { required: 1024, .. }
+    ));
+
+    let shared: u32 = limits.max_compute_workgroup_storage_size / 4 + 1;
+    let too_much_memory: String =
+        shaders::specialize(shaders::CONVOLUTION_SHARED, &[("SHARED_SIZE", shared)]).unwrap();
+    assert!(
+        reflect(&too_much_memory, "conv_shared")
+            .unwrap()
+            .workgroup_storage
+            > shared as u64 * 4
+    );
+    let Err(error) = KernelInvocation::new(&handles, &too_much_memory, "conv_shared") else {
+        panic!("{shared} floats of workgroup memory compiled");
+    };
+    assert!(matches!(
+        error,
+        KernelError::DeviceLimit {
+            limit: "max_compute_workgroup_storage_size",
+            ..
+        }
+    ));
+
+    let uniform = Uniform::new(&handles, 4, 0, 0, 0);
+    let input = GPUVector::new(&handles, vec![1.0; 4], "input", false);
+    let mut output = GPUVector::new(&handles, vec![0.0; 4], "output", true);
+    let too_many_workgroups = KernelInvocation::new(&handles, shaders::VECTOR_ADD, "vector_add")
+        .unwrap()
+        .uniform(&uniform)
+        .read(&input)
+        .read(&input)
+        .read_write(&mut output)
+        .workgroups(limits.max_compute_workgroups_per_dimension + 1, 1, 1)
+        .dispatch();
+    assert!(matches!(
+        too_many_workgroups,
+        Err(KernelError::DeviceLimit {
+            limit: "max_compute_workgroups_per_dimension",
+            ..
+        })
+    ));
+}
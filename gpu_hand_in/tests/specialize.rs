@@ -0,0 +1,153 @@
+use gpu_hand_in::reflection::reflect;
+use gpu_hand_in::{
+    initialize_fallback_gpu, shaders, Convolution, ConvolutionConfig, ConvolutionKernel,
+    GPUHandles, GPUVector, KernelError, Uniform,
+};
+
+fn handles() -> GPUHandles {
+    pollster::block_on(initialize_fallback_gpu()).expect("no GPU adapter available")
+}
+
+#[test]
+fn constants_are_overridden_and_inlined_into_the_workgroup_size() {
+    let shader: String = shaders::specialize(
+        shaders::CONVOLUTION_SHARED,
+        &[
+            ("BLOCK_SIZE", 64),
+            ("MAX_FILTER_SIZE", 5),
+            ("SHARED_SIZE", 68),
+        ],
+    )
+    .unwrap();
+
+    assert!(shader.contains("const BLOCK_SIZE: u32 = 64u;"));
+    assert!(shader.contains("@workgroup_size(64, 1, 1)"));
+    assert_eq!(
+        reflect(&shader, "conv_shared").unwrap().workgroup_size,
+        [64, 1, 1]
+    );
+}
+
+#[test]
+fn unknown_constants_are_errors() {
+    assert!(matches!(
+        shaders::specialize(shaders::CONVOLUTION_NAIVE, &[("TILE_SIZE", 8)]),
+        Err(KernelError::UnknownConstant(name)) if name == "TILE_SIZE"
+    ));
+}
+
+#[test]
+fn the_config_derives_the_workgroup_memory_from_block_and_filter_size() {
+    let config: ConvolutionConfig = ConvolutionConfig::new(ConvolutionKernel::Shared)
+        .with_block_size(64)
+        .with_max_filter_size(5);
+    assert_eq!(config.shared_size(), 68);
+
+    let shader: String = config.shader().unwrap();
+    assert!(shader.contains("const MAX_FILTER_SIZE: u32 = 5u;"));
+    assert!(shader.contains("const SHARED_SIZE: u32 = 68u;"));
+    assert!(shader.contains("@workgroup_size(64, 1, 1)"));
+}
+
+#[test]
+fn convolution_variants_handle_partial_blocks() {
+    let handles: GPUHandles = handles();
+    let (signal_length, filter_size, block_size): (usize, usize, u32) = (100, 5, 64);
+    let signal: Vec<f32> = (0..signal_length).map(|x| (x % 7) as f32).collect();
+    let filter: Vec<f32> = vec![1.0, -1.0, 2.0, -1.0, 1.0];
+    let expected: Vec<f32> = (0..signal_length)
+        .map(|index| {
+            (0..filter_size)
+                .filter_map(|tap| {
+                    let offset: usize = (index + tap).checked_sub(filter_size / 2)?;
+                    signal.get(offset).map(|value| value * filter[tap])
+                })
+                .sum()
+        })
+        .collect();
+
+    let convolution: Convolution = Convolution::new(signal_length, filter_size);
+    let config = |kernel: ConvolutionKernel| {
+        ConvolutionConfig::new(kernel)
+            .with_block_size(block_size)
+            .with_max_filter_size(filter_size as u32)
+    };
+    let uniform: Uniform = convolution.uniform(&handles);
+    let signal_gpu: GPUVector = GPUVector::new(&handles, signal.clone(), "signal", false);
+    let filter_gpu: GPUVector = GPUVector::new(&handles, filter, "filter", false);
+
+    for kernel in [ConvolutionKernel::Naive, ConvolutionKernel::Shared] {
+        let mut output: GPUVector = GPUVector::zeros(&handles, &[signal_length], "output", true);
+        convolution
+            .invocation(
+                &handles,
+                config(kernel),
+                &uniform,
+                &signal_gpu,
+                &filter_gpu,
+                &mut output,
+            )
+            .unwrap()
+            .dispatch()
+            .unwrap();
+        assert_eq!(output.cpu_data, expected, "{kernel:?}");
+    }
+
+    let padded_length: usize = convolution.padded_signal_length(block_size);
+    assert_eq!(padded_length, 2 * block_size as usize + filter_size - 1);
+    let mut signal_padded: Vec<f32> = vec![0.0; filter_size / 2];
+    signal_padded.extend_from_slice(&signal);
+    signal_padded.resize(padded_length, 0.0);
+    let signal_padded: GPUVector = GPUVector::new(&handles, signal_padded, "signal", false);
+    let mut output: GPUVector =
+        GPUVector::zeros(&handles, &[2 * block_size as usize], "output", true);
+    convolution
+        .invocation(
+            &handles,
+            config(ConvolutionKernel::Padded),
+            &uniform,
+            &signal_padded,
+            &filter_gpu,
+            &mut output,
+        )
+        .unwrap()
+        .dispatch()
+        .unwrap();
+    assert_eq!(output.cpu_data[..signal_length], expected);
+}
+
+#[test]
+fn filters_longer_than_the_workgroup_memory_are_errors() {
+    let handles: GPUHandles = handles();
+    let convolution: Convolution = Convolution::new(16, 7);
+    let uniform: Uniform = convolution.uniform(&handles);
+    let signal: GPUVector = GPUVector::new(&handles, vec![1.0; 16], "signal", false);
+    let filter: GPUVector = GPUVector::new(&handles, vec![1.0; 7], "filter", false);
+    let mut output: GPUVector = GPUVector::zeros(&handles, &[16], "output", true);
+
+    for kernel in [ConvolutionKernel::Shared, ConvolutionKernel::Padded] {
+        let config: ConvolutionConfig = ConvolutionConfig::new(kernel).with_max_filter_size(5);
+        let Err(error) =
+            convolution.invocation(&handles, config, &uniform, &signal, &filter, &mut output)
+        else {
+            panic!("{kernel:?} accepted a filter of 7 taps with room for 5");
+        };
+        assert!(matches!(
+            error,
+            KernelError::FilterTooLarge {
+                filter_size: 7,
+                max_filter_size: 5
+            }
+        ));
+    }
+
+    // The naive kernel reads the filter from the storage buffer, any length works.
+    let config: ConvolutionConfig =
+        ConvolutionConfig::new(ConvolutionKernel::Naive).with_max_filter_size(5);
+    convolution
+        .invocation(&handles, config, &uniform, &signal, &filter, &mut output)
+        .unwrap()
+        .dispatch()
+        .unwrap();
+    assert_eq!(output.cpu_data[8], 7.0);
+}
@@ -0,0 +1,168 @@
+use gpu_hand_in::{
+    histogram, initialize_fallback_gpu, shaders, GPUHandles, GpuBuffer, KernelError,
+    KernelInvocation, Uniform, F16,
+};
+
+fn handles() -> GPUHandles {
+    pollster::block_on(initialize_fallback_gpu()).expect("no GPU adapter available")
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct Particle {
+    position: f32,
+    hits: u32,
+}
+
+const STEP: &str = "
+    struct Particle {
+        position: f32,
+        hits: u32,
+    };
+
+    @group(0) @binding(0) var<storage, read_write> particles: array<Particle>;
+
+    @compute @workgroup_size(8)
+    fn step(@builtin(global_invocation_id) id: vec3<u32>) {
+        if (id.x < arrayLength(&particles)) {
+            particles[id.x].position = particles[id.x].position + 0.5;
+            particles[id.x].hits = particles[id.x].hits + 1u;
+        }
+    }
+";
+
+#[test]
+fn f16_round_trips_through_f32() {
+    for value in [
+        0.0f32,
+        -0.0,
+        1.0,
+        -2.5,
+        0.333_251_95,
+        65504.0,
+        6.103_515_6e-5,
+        5.960_464_5e-8,
+    ] {
+        assert_eq!(F16::from_f32(value).to_f32(), value, "{value}");
+    }
+    assert_eq!(F16::from_f32(1.0).0, 0x3c00);
+    assert_eq!(F16::from_f32(70000.0).to_f32(), f32::INFINITY);
+    assert!(F16::from_f32(f32::NAN).to_f32().is_nan());
+    // 1 + 2^-11 is halfway between 1 and the next half, and rounds to even.
+    assert_eq!(F16::from_f32(1.0 + 2f32.powi(-11)).to_f32(), 1.0);
+}
+
+#[test]
+fn user_structs_are_updated_in_place() {
+    let handles: GPUHandles = handles();
+    let start: Vec<Particle> = (0..10)
+        .map(|index| Particle {
+            position: index as f32,
+            hits: 0,
+        })
+        .collect();
+    let mut particles: GpuBuffer<Particle> = GpuBuffer::new(&handles, start, "particles", true);
+
+    KernelInvocation::new(&handles, STEP, "step")
+        .unwrap()
+        .read_write(&mut particles)
+        .threads(10, 1, 1)
+        .dispatch()
+        .unwrap();
+
+    assert_eq!(
+        particles.cpu_data[3],
+        Particle {
+            position: 3.5,
+            hits: 1
+        }
+    );
+}
+
+#[test]
+fn element_sizes_must_match_the_shader() {
+    let handles: GPUHandles = handles();
+    let mut wrong: GpuBuffer<u32> = GpuBuffer::zeros(&handles, &[20], "wrong", true);
+
+    let result = KernelInvocation::new(&handles, STEP, "step")
+        .unwrap()
+        .read_write(&mut wrong)
+        .dispatch();
+    assert!(matches!(
+        result,
+        Err(KernelError::ElementSize {
+            binding: 0,
+            expected: 8,
+            found: 4
+        })
+    ));
+}
+
+#[test]
+fn buffers_keep_their_shape_and_download_without_a_staging_buffer() {
+    let handles: GPUHandles = handles();
+    let mut matrix: GpuBuffer<i32> =
+        GpuBuffer::new(&handles, (0..6).collect(), "matrix", false).with_shape(&[2, 3]);
+    assert_eq!(matrix.shape(), &[2, 3]);
+
+    matrix.upload(&handles, vec![-1, -2, -3, 4, 5, 6]);
+    matrix.cpu_data = vec![0; 6];
+    assert_eq!(matrix.download(&handles).unwrap(), &[-1, -2, -3, 4, 5, 6]);
+    assert!(matrix.staging_buffer.is_none());
+}
+
+#[test]
+fn histogram_matches_the_cpu() {
+    let handles: GPUHandles = handles();
+    let bin_count: usize = 10;
+    let values: Vec<u32> = (0..1000u32)
+        .map(|index| index.wrapping_mul(2_654_435_761) % 13)
+        .collect();
+
+    let mut expected: Vec<u32> = vec![0; bin_count];
+    for &value in &values {
+        expected[(value as usize).min(bin_count - 1)] += 1;
+    }
+
+    let values: GpuBuffer<u32> = GpuBuffer::new(&handles, values, "values", false);
+    let bins: GpuBuffer<u32> = histogram(&handles, &values, bin_count).unwrap();
+    assert_eq!(bins.cpu_data, expected);
+
+    assert!(matches!(
+        histogram(&handles, &values, 0),
+        Err(KernelError::NoBins)
+    ));
+}
+
+#[test]
+fn prefix_sum_scans_each_block_like_the_cpu() {
+    let handles: GPUHandles = handles();
+    let block_size: usize = 256;
+    // Not a multiple of the block, so the last block is partial.
+    let element_count: usize = 2 * block_size + 100;
+    let input: Vec<i32> = (0..element_count as i32).map(|x| x % 11 - 5).collect();
+
+    let uniform: Uniform = Uniform::new(&handles, element_count, 0, 0, 0);
+    let input_gpu: GpuBuffer<i32> = GpuBuffer::new(&handles, input.clone(), "input", false);
+    let mut output: GpuBuffer<i32> = GpuBuffer::zeros(&handles, &[element_count], "output", true);
+
+    KernelInvocation::new(&handles, shaders::PREFIX_SUM, "prefix_sum")
+        .unwrap()
+        .uniform(&uniform)
+        .read(&input_gpu)
+        .read_write(&mut output)
+        .threads(element_count as u32, 1, 1)
+        .dispatch()
+        .unwrap();
+
+    let expected: Vec<i32> = input
+        .chunks(block_size)
+        .flat_map(|block| {
+            block.iter().scan(0, |sum, value| {
+                *sum += value;
+                Some(*sum)
+            })
+        })
+        .collect();
+    assert_eq!(output.cpu_data, expected);
+}